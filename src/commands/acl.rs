@@ -0,0 +1,94 @@
+use crate::utils::acl::{AclError, UserConfig, DEFAULT_USER_NAME};
+
+use super::{dispatcher::CommandContext, response::Response};
+
+/// AUTH [username] password
+pub(super) fn auth(context: &mut CommandContext, args: &[String]) -> Response {
+    let (name, password) = match args {
+        [password] => (DEFAULT_USER_NAME, password),
+        [name, password] => (name.as_str(), password),
+        _ => return Response::wrong_number_of_arguments("auth"),
+    };
+    match context.config.get_user(name) {
+        Some(user) if user.check_password(password) => {
+            context.session.user = Some(name.to_owned());
+            Response::ok()
+        }
+        _ => Response::Error(
+            "WRONGPASS invalid username-password pair or user is disabled.".to_owned(),
+        ),
+    }
+}
+
+/// ACL WHOAMI | ACL USERS | ACL LIST | ACL SETUSER username [rule ...] | ACL DELUSER username [username ...]
+pub(super) fn acl(context: &mut CommandContext, args: &[String]) -> Response {
+    let subcommand = args[0].to_uppercase();
+    match (subcommand.as_str(), args.len()) {
+        ("WHOAMI", 1) => match &context.session.user {
+            Some(name) => Response::Bulk(name.to_owned()),
+            None => Response::Bulk(DEFAULT_USER_NAME.to_owned()),
+        },
+        ("USERS", 1) => Response::from_strings(
+            context
+                .config
+                .users
+                .iter()
+                .map(|user| user.name.to_owned())
+                .collect(),
+        ),
+        ("LIST", 1) => Response::from_strings(
+            context
+                .config
+                .users
+                .iter()
+                .map(|user| user.describe())
+                .collect(),
+        ),
+        ("SETUSER", len) if len >= 2 => {
+            // Validate every rule before touching the user.
+            let mut user = match context.config.get_user(&args[1]) {
+                Some(user) => user.to_owned(),
+                None => UserConfig::new(&args[1]),
+            };
+            for rule in args[2..].iter() {
+                if let Err(AclError::InvalidRule(rule)) = user.apply_rule(rule) {
+                    return Response::error(&format!("invalid ACL rule '{}'", rule));
+                }
+            }
+            if context.config.users.is_empty() {
+                // Access control is about to be enabled, keep the current behaviour
+                // for connections acting as the implicit default user.
+                let mut default_user = UserConfig::new(DEFAULT_USER_NAME);
+                for rule in ["on", "nopass", "+@all", "allkeys", "allstores"] {
+                    default_user.apply_rule(rule).unwrap();
+                }
+                context.config.users.push(default_user);
+            }
+            *context.config.get_or_create_user(&args[1]) = user;
+            Response::ok()
+        }
+        ("DELUSER", len) if len >= 2 => {
+            if args[1..].iter().any(|name| name == DEFAULT_USER_NAME) {
+                return Response::error("The 'default' user cannot be removed");
+            }
+            // No users at all means access control is disabled.
+            if context
+                .config
+                .users
+                .iter()
+                .all(|user| args[1..].contains(&user.name))
+            {
+                return Response::error("the last user cannot be removed");
+            }
+            let removed = args[1..]
+                .iter()
+                .filter(|name| context.config.remove_user(name))
+                .count();
+            Response::Integer(removed as i64)
+        }
+        ("WHOAMI", _) | ("USERS", _) | ("LIST", _) | ("SETUSER", _) | ("DELUSER", _) => {
+            Response::wrong_number_of_arguments(&format!("acl|{}", subcommand))
+        }
+        _ => Response::error(&format!("unknown ACL subcommand '{}'", args[0])),
+    }
+}
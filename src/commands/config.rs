@@ -0,0 +1,80 @@
+use crate::utils::config::{ConfigError, StoreConfig};
+
+use super::{dispatcher::CommandContext, response::Response};
+
+impl From<ConfigError> for Response {
+    fn from(error: ConfigError) -> Self {
+        match error {
+            ConfigError::Io(e) => Response::error(&format!("config file error: {}", e)),
+            ConfigError::Parse(e) => Response::error(&format!("invalid config file: {}", e)),
+            ConfigError::Serialize(e) => {
+                Response::error(&format!("could not serialize config: {}", e))
+            }
+            ConfigError::NoConfigFile => {
+                Response::error("the server is running without a config file")
+            }
+            ConfigError::UnknownParameter(name) => {
+                Response::error(&format!("unknown config parameter '{}'", name))
+            }
+            ConfigError::ReadOnlyParameter(name) => Response::error(&format!(
+                "config parameter '{}' can't be changed at runtime",
+                name
+            )),
+            ConfigError::InvalidValue(value) => {
+                Response::error(&format!("invalid config value '{}'", value))
+            }
+        }
+    }
+}
+
+/// CONFIG GET pattern | CONFIG SET parameter value | CONFIG RELOAD | CONFIG REWRITE
+pub(super) fn config(context: &mut CommandContext, args: &[String]) -> Response {
+    let subcommand = args[0].to_uppercase();
+    match (subcommand.as_str(), args.len()) {
+        ("GET", 2) => _config_get(context, &args[1]),
+        ("SET", 3) => _config_set(context, &args[1], &args[2]),
+        ("RELOAD", 1) => match context.config.reload(context.manager) {
+            Ok(()) => Response::ok(),
+            Err(e) => Response::from(e),
+        },
+        ("REWRITE", 1) => match context.config.rewrite() {
+            Ok(()) => Response::ok(),
+            Err(e) => Response::from(e),
+        },
+        ("GET", _) | ("SET", _) | ("RELOAD", _) | ("REWRITE", _) => {
+            Response::wrong_number_of_arguments(&format!("config|{}", subcommand))
+        }
+        _ => Response::error(&format!("unknown CONFIG subcommand '{}'", args[0])),
+    }
+}
+
+fn _config_get(context: &mut CommandContext, pattern: &str) -> Response {
+    let mut items = vec![];
+    for (name, value) in context.config.get_parameters(pattern) {
+        items.push(name);
+        items.push(value);
+    }
+    Response::from_strings(items)
+}
+
+fn _config_set(context: &mut CommandContext, name: &str, value: &str) -> Response {
+    // Store settings may only be changed for stores that are alive.
+    if let Some((store_name, _)) = name.rsplit_once('.') {
+        if context.manager.get_store(store_name).is_none() {
+            return Response::error(&format!("store '{}' does not exist", store_name));
+        }
+    }
+    if let Err(e) = context.config.set_parameter(name, value) {
+        return Response::from(e);
+    }
+
+    if let Some((store_name, _)) = name.rsplit_once('.') {
+        let store_config: StoreConfig = context
+            .config
+            .get_store_config(store_name)
+            .unwrap()
+            .to_owned();
+        context.manager.apply_store_config(&store_config);
+    }
+    Response::ok()
+}
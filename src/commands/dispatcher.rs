@@ -0,0 +1,530 @@
+use serde::{Deserialize, Serialize};
+
+use crate::managers::manager::RusticManager;
+use crate::stores::store::KeyValueStore;
+use crate::utils::{
+    acl::{find_session_user, UserConfig},
+    config::RusticConfig,
+    constants::DEFAULT_STORE_NAME,
+};
+
+use super::{acl, config, keys, response::Response, scripts, stores, strings};
+
+/// State kept for the lifetime of a client connection.
+pub struct Session {
+    /// Name of the store commands are run against.
+    pub store_name: String,
+
+    /// Name of the user the connection authenticated as.
+    pub user: Option<String>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session {
+            store_name: DEFAULT_STORE_NAME.to_owned(),
+            user: None,
+        }
+    }
+}
+
+/// Everything a command handler can act upon.
+pub struct CommandContext<'a> {
+    pub session: &'a mut Session,
+    pub manager: &'a mut RusticManager,
+    pub config: &'a mut RusticConfig,
+}
+
+impl CommandContext<'_> {
+    /// Gets the store selected by the session, or an error response if it doesn't exist anymore.
+    pub fn current_store(&mut self) -> Result<&mut KeyValueStore, Response> {
+        match self.manager.get_store_mut(&self.session.store_name) {
+            Some(store) => Ok(store),
+            None => Err(Response::error(&format!(
+                "store '{}' does not exist",
+                self.session.store_name
+            ))),
+        }
+    }
+
+    /// Gets the user the session acts as.
+    /// Returns Ok(None) when no users are configured, in which case access control is disabled.
+    /// Sessions that didn't authenticate act as the `default` user if it needs no password.
+    pub fn current_user(&self) -> Result<Option<&UserConfig>, Response> {
+        if self.config.users.is_empty() {
+            return Ok(None);
+        }
+        match find_session_user(&self.config.users, self.session.user.as_deref()) {
+            Some(user) => Ok(Some(user)),
+            None => Err(Response::Error(
+                "NOAUTH Authentication required.".to_owned(),
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandCategory {
+    /// Reads data from a store.
+    Read,
+    /// Modifies data in a store.
+    Write,
+    /// Manages the server or its stores.
+    Admin,
+    /// Only affects the connection itself.
+    Connection,
+}
+
+impl CommandCategory {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommandCategory::Read => "read",
+            CommandCategory::Write => "write",
+            CommandCategory::Admin => "admin",
+            CommandCategory::Connection => "connection",
+        }
+    }
+}
+
+/// Which arguments of a command are keys, used to enforce the key patterns of users.
+#[derive(Clone, Copy)]
+pub enum KeySpec {
+    NoKeys,
+    First,
+    FirstTwo,
+    All,
+    /// Every other argument, starting with the first (`key value [key value ...]`).
+    Pairs,
+    /// As many arguments as the second one says, after it (`script numkeys [key ...] [arg ...]`).
+    Counted,
+}
+
+impl KeySpec {
+    fn keys<'a>(&self, args: &'a [String]) -> impl Iterator<Item = &'a String> {
+        let (skip, count, step) = match self {
+            KeySpec::NoKeys => (0, 0, 1),
+            KeySpec::First => (0, 1, 1),
+            KeySpec::FirstTwo => (0, 2, 1),
+            KeySpec::All => (0, args.len(), 1),
+            KeySpec::Pairs => (0, args.len(), 2),
+            KeySpec::Counted => (
+                2,
+                args.get(1)
+                    .and_then(|numkeys| numkeys.parse().ok())
+                    .unwrap_or(0),
+                1,
+            ),
+        };
+        args.iter().skip(skip).step_by(step).take(count)
+    }
+}
+
+type CommandHandler = fn(&mut CommandContext, &[String]) -> Response;
+
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub category: CommandCategory,
+    keys: KeySpec,
+    /// Minimum number of arguments (excluding the command name).
+    min_args: usize,
+    /// Maximum number of arguments, None if unbounded.
+    max_args: Option<usize>,
+    handler: CommandHandler,
+}
+
+static COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "PING",
+        category: CommandCategory::Connection,
+        keys: KeySpec::NoKeys,
+        min_args: 0,
+        max_args: Some(1),
+        handler: _ping,
+    },
+    CommandSpec {
+        name: "AUTH",
+        category: CommandCategory::Connection,
+        keys: KeySpec::NoKeys,
+        min_args: 1,
+        max_args: Some(2),
+        handler: acl::auth,
+    },
+    CommandSpec {
+        name: "SELECT",
+        category: CommandCategory::Connection,
+        keys: KeySpec::NoKeys,
+        min_args: 1,
+        max_args: Some(1),
+        handler: stores::select,
+    },
+    CommandSpec {
+        // The permissions are checked against the wrapped command instead.
+        name: "USING",
+        category: CommandCategory::Connection,
+        keys: KeySpec::NoKeys,
+        min_args: 2,
+        max_args: None,
+        handler: stores::using,
+    },
+    CommandSpec {
+        name: "GET",
+        category: CommandCategory::Read,
+        keys: KeySpec::First,
+        min_args: 1,
+        max_args: Some(1),
+        handler: strings::get,
+    },
+    CommandSpec {
+        name: "SET",
+        category: CommandCategory::Write,
+        keys: KeySpec::First,
+        min_args: 2,
+        max_args: Some(5),
+        handler: strings::set,
+    },
+    CommandSpec {
+        name: "SETNX",
+        category: CommandCategory::Write,
+        keys: KeySpec::First,
+        min_args: 2,
+        max_args: Some(3),
+        handler: strings::set_if_absent,
+    },
+    CommandSpec {
+        name: "GETSET",
+        category: CommandCategory::Write,
+        keys: KeySpec::First,
+        min_args: 2,
+        max_args: Some(3),
+        handler: strings::get_and_set,
+    },
+    CommandSpec {
+        name: "APPEND",
+        category: CommandCategory::Write,
+        keys: KeySpec::First,
+        min_args: 2,
+        max_args: Some(2),
+        handler: strings::append,
+    },
+    CommandSpec {
+        name: "STRLEN",
+        category: CommandCategory::Read,
+        keys: KeySpec::First,
+        min_args: 1,
+        max_args: Some(1),
+        handler: strings::len,
+    },
+    CommandSpec {
+        name: "GETRANGE",
+        category: CommandCategory::Read,
+        keys: KeySpec::First,
+        min_args: 3,
+        max_args: Some(3),
+        handler: strings::get_range,
+    },
+    CommandSpec {
+        name: "SETRANGE",
+        category: CommandCategory::Write,
+        keys: KeySpec::First,
+        min_args: 3,
+        max_args: Some(3),
+        handler: strings::set_range,
+    },
+    CommandSpec {
+        name: "INCR",
+        category: CommandCategory::Write,
+        keys: KeySpec::First,
+        min_args: 1,
+        max_args: Some(1),
+        handler: strings::incr,
+    },
+    CommandSpec {
+        name: "DECR",
+        category: CommandCategory::Write,
+        keys: KeySpec::First,
+        min_args: 1,
+        max_args: Some(1),
+        handler: strings::decr,
+    },
+    CommandSpec {
+        name: "INCRBY",
+        category: CommandCategory::Write,
+        keys: KeySpec::First,
+        min_args: 2,
+        max_args: Some(2),
+        handler: strings::incr_by,
+    },
+    CommandSpec {
+        name: "DECRBY",
+        category: CommandCategory::Write,
+        keys: KeySpec::First,
+        min_args: 2,
+        max_args: Some(2),
+        handler: strings::decr_by,
+    },
+    CommandSpec {
+        name: "INCRBYFLOAT",
+        category: CommandCategory::Write,
+        keys: KeySpec::First,
+        min_args: 2,
+        max_args: Some(2),
+        handler: strings::incr_by_float,
+    },
+    CommandSpec {
+        name: "MGET",
+        category: CommandCategory::Read,
+        keys: KeySpec::All,
+        min_args: 1,
+        max_args: None,
+        handler: strings::get_many,
+    },
+    CommandSpec {
+        name: "MSET",
+        category: CommandCategory::Write,
+        keys: KeySpec::Pairs,
+        min_args: 2,
+        max_args: None,
+        handler: strings::set_many,
+    },
+    CommandSpec {
+        name: "MSETNX",
+        category: CommandCategory::Write,
+        keys: KeySpec::Pairs,
+        min_args: 2,
+        max_args: None,
+        handler: strings::set_many_if_absent,
+    },
+    CommandSpec {
+        name: "DEL",
+        category: CommandCategory::Write,
+        keys: KeySpec::All,
+        min_args: 1,
+        max_args: None,
+        handler: keys::del,
+    },
+    CommandSpec {
+        name: "EXISTS",
+        category: CommandCategory::Read,
+        keys: KeySpec::All,
+        min_args: 1,
+        max_args: None,
+        handler: keys::exists,
+    },
+    CommandSpec {
+        name: "TYPE",
+        category: CommandCategory::Read,
+        keys: KeySpec::First,
+        min_args: 1,
+        max_args: Some(1),
+        handler: keys::kind,
+    },
+    CommandSpec {
+        name: "RENAME",
+        category: CommandCategory::Write,
+        keys: KeySpec::FirstTwo,
+        min_args: 2,
+        max_args: Some(2),
+        handler: keys::rename,
+    },
+    CommandSpec {
+        name: "RENAMENX",
+        category: CommandCategory::Write,
+        keys: KeySpec::FirstTwo,
+        min_args: 2,
+        max_args: Some(2),
+        handler: keys::rename_if_absent,
+    },
+    CommandSpec {
+        name: "COPY",
+        category: CommandCategory::Write,
+        keys: KeySpec::FirstTwo,
+        min_args: 2,
+        max_args: Some(5),
+        handler: keys::copy,
+    },
+    CommandSpec {
+        name: "SCAN",
+        category: CommandCategory::Read,
+        keys: KeySpec::NoKeys,
+        min_args: 1,
+        max_args: Some(7),
+        handler: keys::scan,
+    },
+    CommandSpec {
+        name: "KEYS",
+        category: CommandCategory::Read,
+        keys: KeySpec::NoKeys,
+        min_args: 1,
+        max_args: Some(1),
+        handler: keys::keys,
+    },
+    CommandSpec {
+        name: "RANDOMKEY",
+        category: CommandCategory::Read,
+        keys: KeySpec::NoKeys,
+        min_args: 0,
+        max_args: Some(1),
+        handler: keys::random_key,
+    },
+    CommandSpec {
+        name: "SORT",
+        category: CommandCategory::Write,
+        keys: KeySpec::First,
+        min_args: 1,
+        max_args: None,
+        handler: keys::sort,
+    },
+    CommandSpec {
+        name: "SORT_RO",
+        category: CommandCategory::Read,
+        keys: KeySpec::First,
+        min_args: 1,
+        max_args: None,
+        handler: keys::sort_read_only,
+    },
+    CommandSpec {
+        name: "EVAL",
+        category: CommandCategory::Write,
+        keys: KeySpec::Counted,
+        min_args: 2,
+        max_args: None,
+        handler: scripts::eval,
+    },
+    CommandSpec {
+        name: "EVALSHA",
+        category: CommandCategory::Write,
+        keys: KeySpec::Counted,
+        min_args: 2,
+        max_args: None,
+        handler: scripts::eval_hash,
+    },
+    CommandSpec {
+        name: "MOVE",
+        category: CommandCategory::Write,
+        keys: KeySpec::First,
+        min_args: 2,
+        max_args: Some(3),
+        handler: stores::move_key,
+    },
+    CommandSpec {
+        name: "CONFIG",
+        category: CommandCategory::Admin,
+        keys: KeySpec::NoKeys,
+        min_args: 1,
+        max_args: None,
+        handler: config::config,
+    },
+    CommandSpec {
+        name: "STORE",
+        category: CommandCategory::Admin,
+        keys: KeySpec::NoKeys,
+        min_args: 1,
+        max_args: None,
+        handler: stores::store,
+    },
+    CommandSpec {
+        name: "ACL",
+        category: CommandCategory::Admin,
+        keys: KeySpec::NoKeys,
+        min_args: 1,
+        max_args: None,
+        handler: acl::acl,
+    },
+    CommandSpec {
+        name: "SCRIPT",
+        category: CommandCategory::Admin,
+        keys: KeySpec::NoKeys,
+        min_args: 1,
+        max_args: None,
+        handler: scripts::script,
+    },
+];
+
+/// Looks up the specification of a command by its (case-insensitive) name.
+pub fn find_command(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS
+        .iter()
+        .find(|spec| spec.name.eq_ignore_ascii_case(name))
+}
+
+fn _ping(_: &mut CommandContext, args: &[String]) -> Response {
+    match args.first() {
+        Some(message) => Response::Bulk(message.to_owned()),
+        None => Response::Simple("PONG".to_owned()),
+    }
+}
+
+/// Checks that the user of the session is allowed to run the command,
+/// against the selected store and on the given keys.
+fn _check_permissions(
+    context: &CommandContext,
+    spec: &CommandSpec,
+    args: &[String],
+) -> Result<(), Response> {
+    if spec.category == CommandCategory::Connection {
+        return Ok(());
+    }
+    let Some(user) = context.current_user()? else {
+        return Ok(());
+    };
+
+    if !user.can_run(spec.category) {
+        return Err(Response::Error(format!(
+            "NOPERM User {} has no permissions to run the '{}' command",
+            user.name,
+            spec.name.to_lowercase()
+        )));
+    }
+    if spec.category == CommandCategory::Read || spec.category == CommandCategory::Write {
+        if !user.can_access_store(&context.session.store_name) {
+            return Err(Response::Error(format!(
+                "NOPERM User {} has no permissions to access the '{}' store",
+                user.name, context.session.store_name
+            )));
+        }
+        if let Some(key) = spec.keys.keys(args).find(|key| !user.can_access_key(key)) {
+            return Err(Response::Error(format!(
+                "NOPERM User {} has no permissions to access the '{}' key",
+                user.name, key
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Runs a parsed command (name followed by its arguments) and returns the reply.
+pub fn execute_command(context: &mut CommandContext, command: &[String]) -> Response {
+    let Some((name, args)) = command.split_first() else {
+        return Response::error("empty command");
+    };
+    let Some(spec) = find_command(name) else {
+        return Response::error(&format!("unknown command '{}'", name));
+    };
+    if args.len() < spec.min_args || spec.max_args.is_some_and(|max| args.len() > max) {
+        return Response::wrong_number_of_arguments(spec.name);
+    }
+    if let Err(response) = _check_permissions(context, spec, args) {
+        return response;
+    }
+
+    if spec.category == CommandCategory::Write {
+        match context.current_store() {
+            Ok(store) if store.is_over_memory_limit() => {
+                return Response::Error(
+                    "OOM command not allowed when used memory > 'max_memory'".to_owned(),
+                );
+            }
+            Ok(_) => (),
+            Err(response) => return response,
+        }
+    }
+
+    (spec.handler)(context, args)
+}
+
+/// Parses a numeric argument, or returns the error response to send back.
+pub fn parse_number<T: std::str::FromStr>(arg: &str) -> Result<T, Response> {
+    arg.parse::<T>()
+        .map_err(|_| Response::error(&format!("value '{}' is not a valid number", arg)))
+}
@@ -0,0 +1,287 @@
+use crate::stores::types::{SortOptions, ValueKind};
+use crate::utils::acl::UserConfig;
+
+use super::{
+    dispatcher::{parse_number, CommandContext},
+    response::Response,
+    stores::check_store_access,
+};
+
+/// DEL key [key ...]
+pub(super) fn del(context: &mut CommandContext, args: &[String]) -> Response {
+    let keys: Vec<&str> = args.iter().map(|key| key.as_str()).collect();
+    match context.current_store() {
+        Ok(store) => Response::Integer(store.remove_many(&keys) as i64),
+        Err(response) => response,
+    }
+}
+
+/// EXISTS key [key ...]
+pub(super) fn exists(context: &mut CommandContext, args: &[String]) -> Response {
+    let keys: Vec<&str> = args.iter().map(|key| key.as_str()).collect();
+    match context.current_store() {
+        Ok(store) => Response::Integer(store.count_existing(&keys) as i64),
+        Err(response) => response,
+    }
+}
+
+/// TYPE key
+pub(super) fn kind(context: &mut CommandContext, args: &[String]) -> Response {
+    match context.current_store() {
+        Ok(store) => match store.get_kind(&args[0]) {
+            Some(kind) => Response::Simple(kind.name().to_owned()),
+            None => Response::Simple("none".to_owned()),
+        },
+        Err(response) => response,
+    }
+}
+
+/// RENAME key new_key
+pub(super) fn rename(context: &mut CommandContext, args: &[String]) -> Response {
+    match context.current_store() {
+        Ok(store) => match store.rename_key(&args[0], &args[1]) {
+            true => Response::ok(),
+            false => Response::error("no such key"),
+        },
+        Err(response) => response,
+    }
+}
+
+/// RENAMENX key new_key
+pub(super) fn rename_if_absent(context: &mut CommandContext, args: &[String]) -> Response {
+    match context.current_store() {
+        Ok(store) => match store.rename_key_if_absent(&args[0], &args[1]) {
+            Some(renamed) => Response::Integer(renamed as i64),
+            None => Response::error("no such key"),
+        },
+        Err(response) => response,
+    }
+}
+
+/// COPY key new_key [STORE store] [REPLACE]
+pub(super) fn copy(context: &mut CommandContext, args: &[String]) -> Response {
+    let (mut destination_store, mut replace) = (None, false);
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+        match option.to_uppercase().as_str() {
+            "REPLACE" => replace = true,
+            "STORE" => match options.next() {
+                Some(store_name) => destination_store = Some(store_name.to_owned()),
+                None => return Response::error("syntax error"),
+            },
+            _ => return Response::error(&format!("syntax error near '{}'", option)),
+        }
+    }
+
+    match destination_store {
+        Some(store_name) if store_name != context.session.store_name => {
+            if let Err(response) = check_store_access(context, &store_name) {
+                return response;
+            }
+            match context.manager.copy_key(
+                &args[0],
+                &context.session.store_name,
+                &args[1],
+                &store_name,
+                replace,
+            ) {
+                Ok(copied) => Response::Integer(copied as i64),
+                Err(e) => Response::from(e),
+            }
+        }
+        _ => match context.current_store() {
+            Ok(store) => Response::Integer(store.copy_key(&args[0], &args[1], replace) as i64),
+            Err(response) => response,
+        },
+    }
+}
+
+/// SCAN cursor [MATCH pattern] [COUNT count] [TYPE kind]
+pub(super) fn scan(context: &mut CommandContext, args: &[String]) -> Response {
+    let cursor = match parse_number::<u64>(&args[0]) {
+        Ok(cursor) => cursor,
+        Err(response) => return response,
+    };
+    let (mut pattern, mut kind, mut count) = (None, None, None);
+    for option in args[1..].chunks(2) {
+        let [name, value] = option else {
+            return Response::error("syntax error");
+        };
+        match name.to_uppercase().as_str() {
+            "MATCH" => pattern = Some(value.as_str()),
+            "COUNT" => match parse_number::<usize>(value) {
+                Ok(value) => count = Some(value),
+                Err(response) => return response,
+            },
+            "TYPE" => match ValueKind::from_name(value) {
+                Some(value) => kind = Some(value),
+                None => return Response::error(&format!("unknown type '{}'", value)),
+            },
+            _ => return Response::error(&format!("syntax error near '{}'", name)),
+        }
+    }
+
+    let user = match context.current_user() {
+        Ok(user) => user.cloned(),
+        Err(response) => return response,
+    };
+    let store = match context.current_store() {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    let (next_cursor, keys) = store.scan(cursor, pattern, kind, count);
+    Response::Array(vec![
+        Response::Bulk(next_cursor.to_string()),
+        Response::from_strings(_visible_keys(&user, keys)),
+    ])
+}
+
+/// KEYS pattern
+pub(super) fn keys(context: &mut CommandContext, args: &[String]) -> Response {
+    let user = match context.current_user() {
+        Ok(user) => user.cloned(),
+        Err(response) => return response,
+    };
+    match context.current_store() {
+        Ok(store) => Response::from_strings(_visible_keys(&user, store.keys(&args[0]))),
+        Err(response) => response,
+    }
+}
+
+/// Whether the user (None when access control is disabled) may see a key listed by SCAN,
+/// KEYS or RANDOMKEY. Scripts listing keys follow the same rule.
+fn _is_visible(user: &Option<UserConfig>, key: &str) -> bool {
+    user.as_ref().is_none_or(|user| user.can_access_key(key))
+}
+
+fn _visible_keys(user: &Option<UserConfig>, keys: Vec<String>) -> Vec<String> {
+    keys.into_iter()
+        .filter(|key| _is_visible(user, key))
+        .collect()
+}
+
+/// RANDOMKEY [count]
+pub(super) fn random_key(context: &mut CommandContext, args: &[String]) -> Response {
+    let count = match args.first().map(|arg| parse_number::<usize>(arg)) {
+        Some(Ok(count)) => Some(count),
+        Some(Err(response)) => return response,
+        None => None,
+    };
+    let user = match context.current_user() {
+        Ok(user) => user.cloned(),
+        Err(response) => return response,
+    };
+    let store = match context.current_store() {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    let is_visible = |key: &str| _is_visible(&user, key);
+    match count {
+        Some(count) => Response::from_strings(store.random_keys(count, is_visible)),
+        None => match store.random_keys(1, is_visible).pop() {
+            Some(key) => Response::Bulk(key),
+            None => Response::Nil,
+        },
+    }
+}
+
+/// Checks that the user of the session may access a key that isn't one of the keys the
+/// command is declared with, or every key with None.
+fn _check_key_access(context: &CommandContext, key: Option<&str>) -> Result<(), Response> {
+    let Some(user) = context.current_user()? else {
+        return Ok(());
+    };
+    match key {
+        Some(key) if !user.can_access_key(key) => Err(Response::Error(format!(
+            "NOPERM User {} has no permissions to access the '{}' key",
+            user.name, key
+        ))),
+        None if !user.can_access_all_keys() => Err(Response::Error(format!(
+            "NOPERM User {} has no permissions to access keys through patterns",
+            user.name
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn _sort(context: &mut CommandContext, args: &[String], allow_store: bool) -> Response {
+    let mut options = SortOptions::default();
+    let mut destination = None;
+    let mut options_args = args[1..].iter();
+    while let Some(option) = options_args.next() {
+        match option.to_uppercase().as_str() {
+            "ASC" => options.descending = false,
+            "DESC" => options.descending = true,
+            "ALPHA" => options.alpha = true,
+            "BY" => match options_args.next() {
+                Some(pattern) => options.by = Some(pattern.to_owned()),
+                None => return Response::error("syntax error"),
+            },
+            "GET" => match options_args.next() {
+                Some(pattern) => options.get.push(pattern.to_owned()),
+                None => return Response::error("syntax error"),
+            },
+            "LIMIT" => {
+                let (Some(offset), Some(count)) = (options_args.next(), options_args.next()) else {
+                    return Response::error("syntax error");
+                };
+                match (parse_number::<usize>(offset), parse_number::<usize>(count)) {
+                    (Ok(offset), Ok(count)) => {
+                        options.offset = offset;
+                        options.limit = Some(count);
+                    }
+                    (Err(response), _) | (_, Err(response)) => return response,
+                }
+            }
+            "STORE" if allow_store => match options_args.next() {
+                Some(key) => destination = Some(key.to_owned()),
+                None => return Response::error("syntax error"),
+            },
+            _ => return Response::error(&format!("syntax error near '{}'", option)),
+        }
+    }
+
+    // BY and GET patterns can read any key, `#` and patterns skipping the sort don't.
+    let reads_other_keys = options.by.as_ref().is_some_and(|by| by.contains('*'))
+        || options.get.iter().any(|pattern| pattern != "#");
+    if reads_other_keys {
+        if let Err(response) = _check_key_access(context, None) {
+            return response;
+        }
+    }
+    if let Some(destination) = &destination {
+        if let Err(response) = _check_key_access(context, Some(destination)) {
+            return response;
+        }
+    }
+
+    let store = match context.current_store() {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    match destination {
+        Some(destination) => match store.sort_and_store(&args[0], &options, &destination) {
+            Ok(length) => Response::Integer(length as i64),
+            Err(e) => Response::from(e),
+        },
+        None => match store.sort(&args[0], &options) {
+            Ok(values) => Response::Array(
+                values
+                    .into_iter()
+                    .map(|value| value.map_or(Response::Nil, Response::Bulk))
+                    .collect(),
+            ),
+            Err(e) => Response::from(e),
+        },
+    }
+}
+
+/// SORT key [BY pattern] [LIMIT offset count] [GET pattern ...] [ASC|DESC] [ALPHA] [STORE destination]
+pub(super) fn sort(context: &mut CommandContext, args: &[String]) -> Response {
+    _sort(context, args, true)
+}
+
+/// SORT_RO key [BY pattern] [LIMIT offset count] [GET pattern ...] [ASC|DESC] [ALPHA]
+pub(super) fn sort_read_only(context: &mut CommandContext, args: &[String]) -> Response {
+    _sort(context, args, false)
+}
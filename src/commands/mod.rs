@@ -0,0 +1,11 @@
+mod acl;
+mod config;
+pub mod dispatcher;
+mod keys;
+pub mod parser;
+pub mod response;
+pub mod scripts;
+mod stores;
+mod strings;
+#[cfg(test)]
+mod tests;
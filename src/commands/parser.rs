@@ -0,0 +1,51 @@
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    UnbalancedQuotes,
+}
+
+/// Splits an inline command into its arguments.
+/// Arguments are separated by whitespace and may be quoted:
+/// double quotes support the `\n`, `\r`, `\t`, `\"` and `\\` escapes, single quotes are literal.
+pub fn parse_command_line(line: &str) -> Result<Vec<String>, ParseError> {
+    let mut args = vec![];
+    let mut chars = line.chars().peekable();
+
+    loop {
+        while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+        let Some(&first) = chars.peek() else {
+            return Ok(args);
+        };
+
+        let mut arg = String::new();
+        if first == '"' || first == '\'' {
+            chars.next();
+            let mut closed = false;
+            while let Some(ch) = chars.next() {
+                if ch == first {
+                    closed = true;
+                    break;
+                }
+                if ch == '\\' && first == '"' {
+                    match chars.next() {
+                        Some('n') => arg.push('\n'),
+                        Some('r') => arg.push('\r'),
+                        Some('t') => arg.push('\t'),
+                        Some(escaped) => arg.push(escaped),
+                        None => return Err(ParseError::UnbalancedQuotes),
+                    }
+                } else {
+                    arg.push(ch);
+                }
+            }
+            // A closing quote must be followed by a separator.
+            if !closed || chars.peek().is_some_and(|ch| !ch.is_whitespace()) {
+                return Err(ParseError::UnbalancedQuotes);
+            }
+        } else {
+            while let Some(ch) = chars.next_if(|ch| !ch.is_whitespace()) {
+                arg.push(ch);
+            }
+        }
+        args.push(arg);
+    }
+}
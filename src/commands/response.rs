@@ -0,0 +1,74 @@
+use crate::stores::errors::ValueError;
+
+/// Reply to a client command, encoded on the wire using the RESP2 format.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Response {
+    Simple(String),
+    Error(String),
+    Integer(i64),
+    Bulk(String),
+    Nil,
+    Array(Vec<Response>),
+}
+
+impl Response {
+    pub fn ok() -> Self {
+        Response::Simple("OK".to_owned())
+    }
+
+    /// Generic error, prefixed with `ERR` like any error without a more specific code.
+    pub fn error(message: &str) -> Self {
+        Response::Error(format!("ERR {}", message))
+    }
+
+    pub fn wrong_number_of_arguments(command: &str) -> Self {
+        Response::error(&format!(
+            "wrong number of arguments for '{}' command",
+            command.to_lowercase()
+        ))
+    }
+
+    pub fn from_strings(items: Vec<String>) -> Self {
+        Response::Array(items.into_iter().map(Response::Bulk).collect())
+    }
+
+    /// Encodes the response as RESP2 bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        self._encode_into(&mut buf);
+        buf
+    }
+
+    fn _encode_into(&self, buf: &mut Vec<u8>) {
+        match self {
+            Response::Simple(string) => buf.extend(format!("+{}\r\n", string).as_bytes()),
+            Response::Error(message) => buf.extend(format!("-{}\r\n", message).as_bytes()),
+            Response::Integer(integer) => buf.extend(format!(":{}\r\n", integer).as_bytes()),
+            Response::Bulk(string) => {
+                buf.extend(format!("${}\r\n", string.len()).as_bytes());
+                buf.extend(string.as_bytes());
+                buf.extend(b"\r\n");
+            }
+            Response::Nil => buf.extend(b"$-1\r\n"),
+            Response::Array(items) => {
+                buf.extend(format!("*{}\r\n", items.len()).as_bytes());
+                for item in items {
+                    item._encode_into(buf);
+                }
+            }
+        }
+    }
+}
+
+impl From<ValueError> for Response {
+    fn from(error: ValueError) -> Self {
+        match error {
+            ValueError::TypeConversionImpossible(_) => Response::Error(
+                "WRONGTYPE Operation against a key holding the wrong kind of value".to_owned(),
+            ),
+            ValueError::TypeConversionError(e) => {
+                Response::error(&format!("value conversion failed: {:?}", e))
+            }
+        }
+    }
+}
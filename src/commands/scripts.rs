@@ -0,0 +1,162 @@
+use rhai::{Array, Dynamic, Map, INT};
+
+use crate::scripting::{engine, errors::ScriptError};
+use crate::utils::acl::find_session_user;
+
+use super::{
+    dispatcher::{parse_number, CommandCategory, CommandContext, Session},
+    response::Response,
+};
+
+impl From<ScriptError> for Response {
+    fn from(error: ScriptError) -> Self {
+        match error {
+            ScriptError::Compile(message) => {
+                Response::error(&format!("error compiling script: {}", message))
+            }
+            ScriptError::Runtime(message) => {
+                Response::error(&format!("error running script: {}", message))
+            }
+            ScriptError::TimedOut(time_limit) => Response::error(&format!(
+                "script timed out after {} ms, see 'script_time_limit_ms'",
+                time_limit
+            )),
+            ScriptError::Killed => Response::error("script killed by user with SCRIPT KILL"),
+        }
+    }
+}
+
+/// Converts the value a script evaluates to: strings and numbers are replied as is,
+/// `()` and `false` as nil, `true` as 1, arrays as arrays and maps as flat arrays of pairs.
+fn _script_reply(value: Dynamic) -> Response {
+    if value.is_unit() {
+        Response::Nil
+    } else if let Ok(value) = value.as_bool() {
+        match value {
+            true => Response::Integer(1),
+            false => Response::Nil,
+        }
+    } else if let Ok(value) = value.as_int() {
+        Response::Integer(value)
+    } else if value.is::<Array>() {
+        Response::Array(
+            value
+                .cast::<Array>()
+                .into_iter()
+                .map(_script_reply)
+                .collect(),
+        )
+    } else if value.is::<Map>() {
+        let mut items = vec![];
+        for (key, value) in value.cast::<Map>() {
+            items.push(Response::Bulk(key.to_string()));
+            items.push(_script_reply(value));
+        }
+        Response::Array(items)
+    } else if value.is_string() || value.is_char() || value.is::<rhai::FLOAT>() {
+        Response::Bulk(value.to_string())
+    } else {
+        Response::error(&format!(
+            "scripts can't reply with a value of type '{}'",
+            value.type_name()
+        ))
+    }
+}
+
+/// Runs the script registered under `hash` with `numkeys key [key ...] [arg ...]`.
+fn _run(context: &mut CommandContext, hash: &str, args: &[String]) -> Response {
+    let numkeys = match parse_number::<usize>(&args[0]) {
+        Ok(numkeys) if numkeys < args.len() => numkeys,
+        Ok(_) => return Response::error("number of keys can't be greater than number of args"),
+        Err(response) => return response,
+    };
+    if let Err(response) = context.current_store() {
+        return response;
+    }
+    let Some(ast) = context.manager.scripts().get(hash).cloned() else {
+        return Response::Error("NOSCRIPT No matching script. Please use EVAL.".to_owned());
+    };
+    let (keys, args) = args[1..].split_at(numkeys);
+    match engine::run_script(
+        &ast,
+        context.manager,
+        context.config,
+        &context.session.store_name,
+        context.session.user.as_deref(),
+        keys.to_vec(),
+        args.to_vec(),
+    ) {
+        Ok(value) => _script_reply(value),
+        Err(e) => Response::from(e),
+    }
+}
+
+/// EVAL script numkeys [key ...] [arg ...]
+/// The script is registered as well, to be run again with EVALSHA.
+pub(super) fn eval(context: &mut CommandContext, args: &[String]) -> Response {
+    match context.manager.scripts_mut().load(&args[0]) {
+        Ok(hash) => _run(context, &hash, &args[1..]),
+        Err(e) => Response::from(e),
+    }
+}
+
+/// EVALSHA hash numkeys [key ...] [arg ...]
+pub(super) fn eval_hash(context: &mut CommandContext, args: &[String]) -> Response {
+    _run(context, &args[0], &args[1..])
+}
+
+/// SCRIPT LOAD script | SCRIPT EXISTS hash [hash ...] | SCRIPT FLUSH | SCRIPT KILL
+pub(super) fn script(context: &mut CommandContext, args: &[String]) -> Response {
+    let subcommand = args[0].to_uppercase();
+    match (subcommand.as_str(), args.len()) {
+        ("LOAD", 2) => match context.manager.scripts_mut().load(&args[1]) {
+            Ok(hash) => Response::Bulk(hash),
+            Err(e) => Response::from(e),
+        },
+        ("EXISTS", 2..) => Response::Array(
+            args[1..]
+                .iter()
+                .map(|hash| Response::Integer(context.manager.scripts().contains(hash) as INT))
+                .collect(),
+        ),
+        ("FLUSH", 1) => {
+            context.manager.scripts_mut().flush();
+            Response::ok()
+        }
+        // A running script holds the manager, so it is killed before getting here.
+        ("KILL", 1) => Response::Error("NOTBUSY No scripts in execution right now.".to_owned()),
+        ("LOAD", _) | ("EXISTS", _) | ("FLUSH", _) | ("KILL", _) => {
+            Response::wrong_number_of_arguments(&format!("script|{}", subcommand))
+        }
+        _ => Response::error(&format!("unknown SCRIPT subcommand '{}'", args[0])),
+    }
+}
+
+/// Handles `SCRIPT KILL` while a script is running, without waiting for the script to release
+/// the manager. Returns None for other commands, or if no script is running.
+pub fn kill_running_script(session: &Session, command: &[String]) -> Option<Response> {
+    match command {
+        [name, subcommand]
+            if name.eq_ignore_ascii_case("SCRIPT") && subcommand.eq_ignore_ascii_case("KILL") => {}
+        _ => return None,
+    }
+    let killed = engine::kill_running_script(|users| {
+        if users.is_empty() {
+            return Ok(());
+        }
+        match find_session_user(users, session.user.as_deref()) {
+            Some(user) if user.can_run(CommandCategory::Admin) => Ok(()),
+            Some(user) => Err(Response::Error(format!(
+                "NOPERM User {} has no permissions to run the 'script' command",
+                user.name
+            ))),
+            None => Err(Response::Error(
+                "NOAUTH Authentication required.".to_owned(),
+            )),
+        }
+    })?;
+    Some(match killed {
+        Ok(()) => Response::ok(),
+        Err(response) => response,
+    })
+}
@@ -0,0 +1,217 @@
+use crate::managers::errors::StoreManagerError;
+use crate::utils::{config::StoreConfig, constants::INTERNAL_STORE_NAME};
+
+use super::{
+    dispatcher::{execute_command, parse_number, CommandContext},
+    response::Response,
+};
+
+impl From<StoreManagerError> for Response {
+    fn from(error: StoreManagerError) -> Self {
+        match error {
+            StoreManagerError::StoreAlreadyExists(name) => {
+                Response::error(&format!("store '{}' already exists", name))
+            }
+            StoreManagerError::StoreNotFound(name) => {
+                Response::error(&format!("store '{}' does not exist", name))
+            }
+            StoreManagerError::SameStore(name) => Response::error(&format!(
+                "source and destination are the same store '{}'",
+                name
+            )),
+        }
+    }
+}
+
+/// Checks that the store exists and that the user of the session may access it.
+pub(super) fn check_store_access(
+    context: &CommandContext,
+    store_name: &str,
+) -> Result<(), Response> {
+    if context.manager.get_store(store_name).is_none() {
+        return Err(Response::error(&format!(
+            "store '{}' does not exist",
+            store_name
+        )));
+    }
+    _check_store_permission(context, store_name)
+}
+
+/// Checks that the user of the session may access the store, whether it exists or not.
+fn _check_store_permission(context: &CommandContext, store_name: &str) -> Result<(), Response> {
+    match context.current_user()? {
+        Some(user) if !user.can_access_store(store_name) => Err(Response::Error(format!(
+            "NOPERM User {} has no permissions to access the '{}' store",
+            user.name, store_name
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// SELECT store
+pub(super) fn select(context: &mut CommandContext, args: &[String]) -> Response {
+    if let Err(response) = check_store_access(context, &args[0]) {
+        return response;
+    }
+    context.session.store_name = args[0].to_owned();
+    Response::ok()
+}
+
+/// USING store command [arg ...]
+/// Runs a single command against the store, without changing the selected store.
+pub(super) fn using(context: &mut CommandContext, args: &[String]) -> Response {
+    if let Err(response) = check_store_access(context, &args[0]) {
+        return response;
+    }
+    let selected_store_name =
+        std::mem::replace(&mut context.session.store_name, args[0].to_owned());
+    let response = execute_command(context, &args[1..]);
+    context.session.store_name = selected_store_name;
+    response
+}
+
+/// MOVE key store [REPLACE]
+/// Moves a key of the selected store to another store, keeping its time to live.
+pub(super) fn move_key(context: &mut CommandContext, args: &[String]) -> Response {
+    let replace = match args.get(2) {
+        Some(arg) if arg.eq_ignore_ascii_case("REPLACE") => true,
+        Some(arg) => return Response::error(&format!("syntax error near '{}'", arg)),
+        None => false,
+    };
+    if let Err(response) = check_store_access(context, &args[1]) {
+        return response;
+    }
+    match context
+        .manager
+        .move_key(&args[0], &context.session.store_name, &args[1], replace)
+    {
+        Ok(moved) => Response::Integer(moved as i64),
+        Err(e) => Response::from(e),
+    }
+}
+
+fn _store_info(context: &mut CommandContext, name: &str) -> Response {
+    let info = match context.manager.get_store_info(name) {
+        Ok(info) => info,
+        Err(e) => return Response::from(e),
+    };
+    let format_optional = |value: Option<u64>| match value {
+        Some(value) => Response::Integer(value as i64),
+        None => Response::Nil,
+    };
+    Response::Array(vec![
+        Response::Bulk("name".to_owned()),
+        Response::Bulk(info.name),
+        Response::Bulk("keys".to_owned()),
+        Response::Integer(info.keys as i64),
+        Response::Bulk("expiring_keys".to_owned()),
+        Response::Integer(info.expiring_keys as i64),
+        Response::Bulk("default_ttl".to_owned()),
+        format_optional(info.default_ttl),
+        Response::Bulk("max_memory".to_owned()),
+        format_optional(info.max_memory),
+        Response::Bulk("estimated_memory_usage".to_owned()),
+        Response::Integer(info.estimated_memory_usage as i64),
+    ])
+}
+
+/// STORE CREATE name [default_ttl] | STORE DROP name | STORE LIST | STORE RENAME name new_name
+/// | STORE COPY name new_name | STORE FLUSH name | STORE INFO name
+pub(super) fn store(context: &mut CommandContext, args: &[String]) -> Response {
+    let subcommand = args[0].to_uppercase();
+    // The stores the subcommand acts on, all of which the user has to be allowed to access.
+    let store_names = match (subcommand.as_str(), args.len()) {
+        ("DROP", 2) | ("FLUSH", 2) | ("INFO", 2) => &args[1..2],
+        ("RENAME", 3) | ("COPY", 3) => &args[1..3],
+        _ => &[],
+    };
+    for name in store_names {
+        if let Err(response) = _check_store_permission(context, name) {
+            return response;
+        }
+    }
+    match (subcommand.as_str(), args.len()) {
+        ("CREATE", 2) | ("CREATE", 3) => {
+            let default_ttl = match args.get(2).map(|arg| parse_number::<u64>(arg)) {
+                Some(Ok(default_ttl)) => Some(default_ttl),
+                Some(Err(response)) => return response,
+                None => None,
+            };
+            if let Err(e) = context.manager.create_store(&args[1], default_ttl) {
+                return Response::from(e);
+            }
+            // Keep the config in sync so that the store survives a config rewrite.
+            context.config.stores.retain(|store| store.name != args[1]);
+            context.config.stores.push(StoreConfig {
+                name: args[1].to_owned(),
+                default_ttl,
+                max_memory: None,
+            });
+            Response::ok()
+        }
+        ("DROP", 2) => {
+            if args[1] == INTERNAL_STORE_NAME {
+                return Response::error("the internal store can't be dropped");
+            }
+            context.config.stores.retain(|store| store.name != args[1]);
+            Response::Integer(context.manager.remove_store(&args[1]) as i64)
+        }
+        ("RENAME", 3) => {
+            if args[1] == INTERNAL_STORE_NAME || args[2] == INTERNAL_STORE_NAME {
+                return Response::error("the internal store can't be renamed");
+            }
+            if let Err(e) = context.manager.rename_store(&args[1], &args[2]) {
+                return Response::from(e);
+            }
+            for store_config in context.config.stores.iter_mut() {
+                if store_config.name == args[1] {
+                    store_config.name = args[2].to_owned();
+                }
+            }
+            Response::ok()
+        }
+        ("COPY", 3) => {
+            if args[1] == INTERNAL_STORE_NAME || args[2] == INTERNAL_STORE_NAME {
+                return Response::error("the internal store can't be copied");
+            }
+            if let Err(e) = context.manager.copy_store(&args[1], &args[2]) {
+                return Response::from(e);
+            }
+            if let Some(store_config) = context.config.get_store_config(&args[1]) {
+                let new_store_config = StoreConfig {
+                    name: args[2].to_owned(),
+                    ..store_config.to_owned()
+                };
+                context.config.stores.retain(|store| store.name != args[2]);
+                context.config.stores.push(new_store_config);
+            }
+            Response::ok()
+        }
+        ("FLUSH", 2) if args[1] == INTERNAL_STORE_NAME => {
+            Response::error("the internal store can't be flushed")
+        }
+        ("FLUSH", 2) => match context.manager.flush_store(&args[1]) {
+            Ok(()) => Response::ok(),
+            Err(e) => Response::from(e),
+        },
+        ("INFO", 2) => _store_info(context, &args[1]),
+        ("LIST", 1) => {
+            let mut names: Vec<String> = context
+                .manager
+                .list_store_names()
+                .iter()
+                .map(|name| name.to_string())
+                .collect();
+            names.sort();
+            Response::from_strings(names)
+        }
+        ("CREATE", _)
+        | ("DROP", _)
+        | ("LIST", _)
+        | ("RENAME", _)
+        | ("COPY", _)
+        | ("FLUSH", _)
+        | ("INFO", _) => Response::wrong_number_of_arguments(&format!("store|{}", subcommand)),
+        _ => Response::error(&format!("unknown STORE subcommand '{}'", args[0])),
+    }
+}
@@ -0,0 +1,266 @@
+use super::{
+    dispatcher::{parse_number, CommandContext},
+    response::Response,
+};
+
+/// GET key
+pub(super) fn get(context: &mut CommandContext, args: &[String]) -> Response {
+    let store = match context.current_store() {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    match store.get_string(&args[0]) {
+        Some(Ok(value)) => Response::Bulk(value),
+        Some(Err(e)) => Response::from(e),
+        None => Response::Nil,
+    }
+}
+
+/// SET key value [ttl_in_milliseconds] [NX|XX] [GET]
+pub(super) fn set(context: &mut CommandContext, args: &[String]) -> Response {
+    let mut options = &args[2..];
+    let ttl = match options.first().map(|arg| arg.parse::<u64>()) {
+        Some(Ok(ttl)) => {
+            options = &options[1..];
+            Some(ttl)
+        }
+        _ => None,
+    };
+    let (mut if_absent, mut if_present, mut get) = (false, false, false);
+    for option in options {
+        match option.to_uppercase().as_str() {
+            "NX" => if_absent = true,
+            "XX" => if_present = true,
+            "GET" => get = true,
+            _ => return Response::error(&format!("syntax error near '{}'", option)),
+        }
+    }
+    if if_absent && if_present {
+        return Response::error("NX and XX options are mutually exclusive");
+    }
+
+    let store = match context.current_store() {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    let (key, value) = (&args[0], args[1].to_owned());
+    let old_value = match store.get_string(key) {
+        Some(Err(e)) if get => return Response::from(e),
+        Some(Ok(old_value)) if get => Response::Bulk(old_value),
+        _ => Response::Nil,
+    };
+    let is_set = if if_absent {
+        store.set_string_if_absent(key, value, ttl)
+    } else if if_present {
+        store.set_string_if_present(key, value, ttl)
+    } else {
+        store.set_string(key, value, ttl);
+        true
+    };
+    match (get, is_set) {
+        (true, _) => old_value,
+        (false, true) => Response::ok(),
+        (false, false) => Response::Nil,
+    }
+}
+
+/// SETNX key value [ttl_in_milliseconds]
+pub(super) fn set_if_absent(context: &mut CommandContext, args: &[String]) -> Response {
+    let ttl = match args.get(2).map(|arg| parse_number::<u64>(arg)) {
+        Some(Ok(ttl)) => Some(ttl),
+        Some(Err(response)) => return response,
+        None => None,
+    };
+    match context.current_store() {
+        Ok(store) => {
+            Response::Integer(store.set_string_if_absent(&args[0], args[1].to_owned(), ttl) as i64)
+        }
+        Err(response) => response,
+    }
+}
+
+/// GETSET key value [ttl_in_milliseconds]
+pub(super) fn get_and_set(context: &mut CommandContext, args: &[String]) -> Response {
+    let ttl = match args.get(2).map(|arg| parse_number::<u64>(arg)) {
+        Some(Ok(ttl)) => Some(ttl),
+        Some(Err(response)) => return response,
+        None => None,
+    };
+    let store = match context.current_store() {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    match store.get_and_set_string(&args[0], args[1].to_owned(), ttl) {
+        Some(Ok(value)) => Response::Bulk(value),
+        Some(Err(e)) => Response::from(e),
+        None => Response::Nil,
+    }
+}
+
+/// APPEND key value
+pub(super) fn append(context: &mut CommandContext, args: &[String]) -> Response {
+    match context.current_store() {
+        Ok(store) => match store.string_append(&args[0], &args[1]) {
+            Ok(length) => Response::Integer(length as i64),
+            Err(e) => Response::from(e),
+        },
+        Err(response) => response,
+    }
+}
+
+/// STRLEN key
+pub(super) fn len(context: &mut CommandContext, args: &[String]) -> Response {
+    match context.current_store() {
+        Ok(store) => match store.string_len(&args[0]) {
+            Some(Ok(length)) => Response::Integer(length as i64),
+            Some(Err(e)) => Response::from(e),
+            None => Response::Integer(0),
+        },
+        Err(response) => response,
+    }
+}
+
+/// GETRANGE key start end
+pub(super) fn get_range(context: &mut CommandContext, args: &[String]) -> Response {
+    let (start, end) = match (parse_number::<i64>(&args[1]), parse_number::<i64>(&args[2])) {
+        (Ok(start), Ok(end)) => (start, end),
+        (Err(response), _) | (_, Err(response)) => return response,
+    };
+    match context.current_store() {
+        Ok(store) => match store.string_range(&args[0], start, end) {
+            Some(Ok(value)) => Response::Bulk(value),
+            Some(Err(e)) => Response::from(e),
+            None => Response::Bulk(String::new()),
+        },
+        Err(response) => response,
+    }
+}
+
+/// SETRANGE key offset value
+pub(super) fn set_range(context: &mut CommandContext, args: &[String]) -> Response {
+    let offset = match parse_number::<usize>(&args[1]) {
+        Ok(offset) => offset,
+        Err(response) => return response,
+    };
+    match context.current_store() {
+        Ok(store) => match store.string_set_range(&args[0], offset, &args[2]) {
+            Ok(length) => Response::Integer(length as i64),
+            Err(e) => Response::from(e),
+        },
+        Err(response) => response,
+    }
+}
+
+/// MGET key [key ...]
+pub(super) fn get_many(context: &mut CommandContext, args: &[String]) -> Response {
+    let keys: Vec<&str> = args.iter().map(|key| key.as_str()).collect();
+    match context.current_store() {
+        Ok(store) => Response::Array(
+            store
+                .get_many_strings(&keys)
+                .into_iter()
+                .map(|value| value.map_or(Response::Nil, Response::Bulk))
+                .collect(),
+        ),
+        Err(response) => response,
+    }
+}
+
+fn _parse_pairs(args: &[String]) -> Result<Vec<(String, String)>, Response> {
+    if !args.len().is_multiple_of(2) {
+        return Err(Response::error("keys and values must come in pairs"));
+    }
+    Ok(args
+        .chunks(2)
+        .map(|pair| (pair[0].to_owned(), pair[1].to_owned()))
+        .collect())
+}
+
+/// MSET key value [key value ...]
+pub(super) fn set_many(context: &mut CommandContext, args: &[String]) -> Response {
+    let kv_pairs = match _parse_pairs(args) {
+        Ok(kv_pairs) => kv_pairs,
+        Err(response) => return response,
+    };
+    match context.current_store() {
+        Ok(store) => {
+            store.set_many_strings(kv_pairs, None);
+            Response::ok()
+        }
+        Err(response) => response,
+    }
+}
+
+/// MSETNX key value [key value ...]
+pub(super) fn set_many_if_absent(context: &mut CommandContext, args: &[String]) -> Response {
+    let kv_pairs = match _parse_pairs(args) {
+        Ok(kv_pairs) => kv_pairs,
+        Err(response) => return response,
+    };
+    match context.current_store() {
+        Ok(store) => Response::Integer(store.set_many_strings_if_absent(kv_pairs, None) as i64),
+        Err(response) => response,
+    }
+}
+
+/// Shared by INCR, DECR, INCRBY and DECRBY.
+fn _incr(
+    context: &mut CommandContext,
+    key: &str,
+    delta: Option<&String>,
+    decrement: bool,
+) -> Response {
+    let delta = match delta.map(|arg| parse_number::<i64>(arg)) {
+        Some(Ok(delta)) => Some(delta),
+        Some(Err(response)) => return response,
+        None => None,
+    };
+    let store = match context.current_store() {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    let result = if decrement {
+        store.decr(key, delta)
+    } else {
+        store.incr(key, delta)
+    };
+    match result {
+        Ok(value) => Response::Integer(value),
+        Err(e) => Response::from(e),
+    }
+}
+
+/// INCR key
+pub(super) fn incr(context: &mut CommandContext, args: &[String]) -> Response {
+    _incr(context, &args[0], None, false)
+}
+
+/// DECR key
+pub(super) fn decr(context: &mut CommandContext, args: &[String]) -> Response {
+    _incr(context, &args[0], None, true)
+}
+
+/// INCRBY key delta
+pub(super) fn incr_by(context: &mut CommandContext, args: &[String]) -> Response {
+    _incr(context, &args[0], args.get(1), false)
+}
+
+/// DECRBY key delta
+pub(super) fn decr_by(context: &mut CommandContext, args: &[String]) -> Response {
+    _incr(context, &args[0], args.get(1), true)
+}
+
+/// INCRBYFLOAT key delta
+pub(super) fn incr_by_float(context: &mut CommandContext, args: &[String]) -> Response {
+    let delta = match parse_number::<f64>(&args[1]) {
+        Ok(delta) => delta,
+        Err(response) => return response,
+    };
+    match context.current_store() {
+        Ok(store) => match store.incr_by_float(&args[0], delta) {
+            Ok(value) => Response::Bulk(value.to_string()),
+            Err(e) => Response::from(e),
+        },
+        Err(response) => response,
+    }
+}
@@ -0,0 +1,818 @@
+use std::{env, fs};
+
+use crate::managers::manager::RusticManager;
+use crate::scripting::registry::script_hash;
+use crate::utils::{
+    config::{RusticConfig, StoreConfig},
+    constants::DEFAULT_STORE_NAME,
+};
+
+use super::{
+    dispatcher::{execute_command, CommandContext, Session},
+    parser::{parse_command_line, ParseError},
+    response::Response,
+    scripts::kill_running_script,
+};
+
+/// Parses and runs a single command line.
+fn run(
+    session: &mut Session,
+    manager: &mut RusticManager,
+    config: &mut RusticConfig,
+    line: &str,
+) -> Response {
+    execute_command(
+        &mut CommandContext {
+            session,
+            manager,
+            config,
+        },
+        &parse_command_line(line).unwrap(),
+    )
+}
+
+fn setup() -> (Session, RusticManager, RusticConfig) {
+    let config = RusticConfig::default();
+    let mut manager = RusticManager::new();
+    for store_config in config.stores.iter() {
+        manager.apply_store_config(store_config);
+    }
+    (Session::new(), manager, config)
+}
+
+#[test]
+fn test_parse_command_line() {
+    assert_eq!(
+        parse_command_line("  SET key   \"hello world\" 'a \\n b' ").unwrap(),
+        vec!["SET", "key", "hello world", "a \\n b"]
+    );
+    assert_eq!(
+        parse_command_line("SET key \"line\\nbreak\"").unwrap(),
+        vec!["SET", "key", "line\nbreak"]
+    );
+    assert!(parse_command_line("   ").unwrap().is_empty());
+    assert_eq!(
+        parse_command_line("SET key \"unbalanced"),
+        Err(ParseError::UnbalancedQuotes)
+    );
+}
+
+#[test]
+fn test_response_encoding() {
+    assert_eq!(Response::ok().encode(), b"+OK\r\n");
+    assert_eq!(Response::Nil.encode(), b"$-1\r\n");
+    assert_eq!(
+        Response::Array(vec![Response::Integer(5), Response::Bulk("abc".to_owned())]).encode(),
+        b"*2\r\n:5\r\n$3\r\nabc\r\n"
+    );
+}
+
+#[test]
+fn test_execute_basic_commands() {
+    let (mut session, mut manager, mut config) = setup();
+    let (s, m, c) = (&mut session, &mut manager, &mut config);
+
+    assert_eq!(run(s, m, c, "ping"), Response::Simple("PONG".to_owned()));
+    assert_eq!(run(s, m, c, "SET ABC hello"), Response::ok());
+    assert_eq!(run(s, m, c, "GET ABC"), Response::Bulk("hello".to_owned()));
+    assert_eq!(run(s, m, c, "DEL ABC XYZ"), Response::Integer(1));
+    assert_eq!(run(s, m, c, "GET ABC"), Response::Nil);
+
+    assert!(matches!(run(s, m, c, "GET"), Response::Error(_)));
+    assert!(matches!(run(s, m, c, "NOPE"), Response::Error(_)));
+    assert!(matches!(
+        run(s, m, c, "SET a b notanumber"),
+        Response::Error(_)
+    ));
+}
+
+#[test]
+fn test_config_get_set() {
+    let (mut session, mut manager, mut config) = setup();
+    let (s, m, c) = (&mut session, &mut manager, &mut config);
+
+    assert_eq!(
+        run(s, m, c, "CONFIG GET default_store.*"),
+        Response::from_strings(vec![
+            "default_store.default_ttl".to_owned(),
+            "none".to_owned(),
+            "default_store.max_memory".to_owned(),
+            "none".to_owned(),
+        ])
+    );
+
+    assert_eq!(
+        run(s, m, c, "CONFIG SET default_store.default_ttl 5000"),
+        Response::ok()
+    );
+    assert_eq!(
+        m.get_store("default_store").unwrap().get_default_ttl(),
+        Some(5000)
+    );
+
+    assert_eq!(
+        run(s, m, c, "CONFIG SET slowlog_threshold_ms 10"),
+        Response::ok()
+    );
+    assert_eq!(c.slowlog_threshold_ms, Some(10));
+    assert_eq!(
+        run(s, m, c, "CONFIG SET slowlog_threshold_ms none"),
+        Response::ok()
+    );
+    assert_eq!(c.slowlog_threshold_ms, None);
+    assert_eq!(
+        run(s, m, c, "CONFIG SET script_cross_store yes"),
+        Response::ok()
+    );
+    assert!(c.script_cross_store);
+    assert!(matches!(
+        run(s, m, c, "CONFIG SET script_cross_store maybe"),
+        Response::Error(_)
+    ));
+
+    assert!(matches!(
+        run(s, m, c, "CONFIG SET missing_store.default_ttl 10"),
+        Response::Error(_)
+    ));
+    assert!(matches!(
+        run(s, m, c, "CONFIG SET bind 0.0.0.0:1"),
+        Response::Error(_)
+    ));
+    assert!(matches!(
+        run(s, m, c, "CONFIG SET default_store.default_ttl abc"),
+        Response::Error(_)
+    ));
+    assert!(matches!(run(s, m, c, "CONFIG REWRITE"), Response::Error(_)));
+}
+
+#[test]
+fn test_max_memory_rejects_writes() {
+    let (mut session, mut manager, mut config) = setup();
+    let (s, m, c) = (&mut session, &mut manager, &mut config);
+
+    assert_eq!(
+        run(s, m, c, "CONFIG SET default_store.max_memory 1"),
+        Response::ok()
+    );
+    // The store is empty so the first write goes through.
+    assert_eq!(run(s, m, c, "SET ABC hello"), Response::ok());
+    assert!(matches!(run(s, m, c, "SET XYZ hello"), Response::Error(_)));
+    assert_eq!(run(s, m, c, "GET ABC"), Response::Bulk("hello".to_owned()));
+}
+
+#[test]
+fn test_config_reload_and_rewrite() {
+    let path = env::temp_dir().join(format!("rustic_config_{}.toml", std::process::id()));
+    fs::write(
+        &path,
+        "slowlog_threshold_ms = 25\n\n[[stores]]\nname = \"default_store\"\ndefault_ttl = 1000\n",
+    )
+    .unwrap();
+
+    let mut config = RusticConfig::load(path.to_owned()).unwrap();
+    let mut manager = RusticManager::new();
+    for store_config in config.stores.iter() {
+        manager.apply_store_config(store_config);
+    }
+    let mut session = Session::new();
+    let (s, m, c) = (&mut session, &mut manager, &mut config);
+    assert_eq!(c.slowlog_threshold_ms, Some(25));
+
+    assert_eq!(run(s, m, c, "SET ABC hello"), Response::ok());
+    fs::write(
+        &path,
+        "[tls]\nbind = \"127.0.0.1:0\"\ncert_file = \"cert.pem\"\nkey_file = \"key.pem\"\n\n\
+         [[stores]]\nname = \"default_store\"\ndefault_ttl = 2000\n\n[[stores]]\nname = \"other\"\n",
+    )
+    .unwrap();
+    assert_eq!(run(s, m, c, "CONFIG RELOAD"), Response::ok());
+    assert_eq!(c.slowlog_threshold_ms, None);
+    assert_eq!(
+        m.get_store("default_store").unwrap().get_default_ttl(),
+        Some(2000)
+    );
+    assert!(m.get_store("other").is_some());
+    // Reloading keeps the data around.
+    assert_eq!(run(s, m, c, "GET ABC"), Response::Bulk("hello".to_owned()));
+
+    assert_eq!(
+        run(s, m, c, "CONFIG SET other.max_memory 4096"),
+        Response::ok()
+    );
+    assert_eq!(run(s, m, c, "CONFIG REWRITE"), Response::ok());
+    let rewritten = RusticConfig::load(path.to_owned()).unwrap();
+    assert_eq!(
+        rewritten.get_store_config("other").unwrap().max_memory,
+        Some(4096)
+    );
+    assert_eq!(rewritten.tls.unwrap().bind, "127.0.0.1:0");
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_auth_and_acl() {
+    let (mut session, mut manager, mut config) = setup();
+    manager.create_store("other_store", None).unwrap();
+    let (s, m, c) = (&mut session, &mut manager, &mut config);
+
+    // Without users, access control is disabled.
+    assert_eq!(run(s, m, c, "SET user:1 a"), Response::ok());
+    assert_eq!(run(s, m, c, "SET order:1 b"), Response::ok());
+    assert_eq!(
+        run(
+            s,
+            m,
+            c,
+            "ACL SETUSER reader on >secret +@read ~user:* %default_store"
+        ),
+        Response::ok()
+    );
+    assert!(matches!(
+        run(s, m, c, "ACL SETUSER broken +@nothing"),
+        Response::Error(_)
+    ));
+    assert!(c.get_user("broken").is_none());
+    assert_eq!(
+        run(s, m, c, "ACL USERS"),
+        Response::from_strings(vec!["default".to_owned(), "reader".to_owned()])
+    );
+
+    // Lock out the default user, connections now have to authenticate.
+    assert_eq!(
+        run(
+            s,
+            m,
+            c,
+            "ACL SETUSER admin on >root +@all allkeys allstores"
+        ),
+        Response::ok()
+    );
+    assert_eq!(run(s, m, c, "ACL SETUSER default off"), Response::ok());
+    assert!(matches!(run(s, m, c, "GET user:1"), Response::Error(e) if e.starts_with("NOAUTH")));
+    assert_eq!(run(s, m, c, "PING"), Response::Simple("PONG".to_owned()));
+
+    assert!(
+        matches!(run(s, m, c, "AUTH reader wrong"), Response::Error(e) if e.starts_with("WRONGPASS"))
+    );
+    assert_eq!(run(s, m, c, "AUTH reader secret"), Response::ok());
+    assert_eq!(run(s, m, c, "GET user:1"), Response::Bulk("a".to_owned()));
+    assert!(matches!(run(s, m, c, "GET order:1"), Response::Error(e) if e.starts_with("NOPERM")));
+    assert!(matches!(run(s, m, c, "SET user:1 b"), Response::Error(e) if e.starts_with("NOPERM")));
+    assert!(matches!(run(s, m, c, "CONFIG GET *"), Response::Error(e) if e.starts_with("NOPERM")));
+
+    // Listing keys leaves out the ones the user may not access, like scripts do.
+    let user_keys = Response::from_strings(vec!["user:1".to_owned()]);
+    assert_eq!(run(s, m, c, "KEYS *"), user_keys);
+    assert_eq!(
+        run(s, m, c, "SCAN 0"),
+        Response::Array(vec![Response::Bulk("0".to_owned()), user_keys.clone()])
+    );
+    assert_eq!(run(s, m, c, "RANDOMKEY 10"), user_keys);
+
+    s.store_name = "other_store".to_owned();
+    assert!(matches!(run(s, m, c, "GET user:1"), Response::Error(e) if e.starts_with("NOPERM")));
+
+    assert_eq!(run(s, m, c, "AUTH admin root"), Response::ok());
+    assert_eq!(run(s, m, c, "GET user:1"), Response::Nil);
+    assert_eq!(
+        run(s, m, c, "ACL DELUSER reader nobody"),
+        Response::Integer(1)
+    );
+
+    // Deleting the default or the last user would disable access control.
+    assert!(matches!(
+        run(s, m, c, "ACL DELUSER default admin"),
+        Response::Error(_)
+    ));
+    c.users.retain(|user| user.name != "default");
+    assert!(matches!(
+        run(s, m, c, "ACL DELUSER admin"),
+        Response::Error(_)
+    ));
+    assert_eq!(
+        run(s, m, c, "ACL USERS"),
+        Response::from_strings(vec!["admin".to_owned()])
+    );
+    *s = Session::new();
+    assert!(matches!(run(s, m, c, "GET user:1"), Response::Error(e) if e.starts_with("NOAUTH")));
+}
+
+#[test]
+fn test_multiple_stores() {
+    let (mut session, mut manager, mut config) = setup();
+    let (s, m, c) = (&mut session, &mut manager, &mut config);
+
+    assert_eq!(run(s, m, c, "STORE CREATE sessions 60000"), Response::ok());
+    assert!(matches!(
+        run(s, m, c, "STORE CREATE sessions"),
+        Response::Error(_)
+    ));
+    assert_eq!(
+        run(s, m, c, "STORE LIST"),
+        Response::from_strings(vec!["default_store".to_owned(), "sessions".to_owned()])
+    );
+    assert_eq!(
+        c.get_store_config("sessions").unwrap().default_ttl,
+        Some(60000)
+    );
+
+    assert_eq!(run(s, m, c, "SET ABC default"), Response::ok());
+    assert_eq!(
+        run(s, m, c, "USING sessions SET ABC session"),
+        Response::ok()
+    );
+    assert_eq!(
+        run(s, m, c, "GET ABC"),
+        Response::Bulk("default".to_owned())
+    );
+    assert_eq!(
+        run(s, m, c, "USING sessions GET ABC"),
+        Response::Bulk("session".to_owned())
+    );
+
+    assert_eq!(run(s, m, c, "SELECT sessions"), Response::ok());
+    assert_eq!(
+        run(s, m, c, "GET ABC"),
+        Response::Bulk("session".to_owned())
+    );
+    assert!(matches!(run(s, m, c, "SELECT missing"), Response::Error(_)));
+    assert!(matches!(
+        run(s, m, c, "USING missing GET ABC"),
+        Response::Error(_)
+    ));
+
+    assert_eq!(run(s, m, c, "STORE DROP sessions"), Response::Integer(1));
+    assert_eq!(run(s, m, c, "STORE DROP sessions"), Response::Integer(0));
+    assert!(c.get_store_config("sessions").is_none());
+    // The selected store is gone.
+    assert!(matches!(run(s, m, c, "GET ABC"), Response::Error(_)));
+    assert_eq!(run(s, m, c, "SELECT default_store"), Response::ok());
+    assert_eq!(
+        run(s, m, c, "GET ABC"),
+        Response::Bulk("default".to_owned())
+    );
+}
+
+#[test]
+fn test_store_administration() {
+    let (mut session, mut manager, mut config) = setup();
+    let (s, m, c) = (&mut session, &mut manager, &mut config);
+
+    assert_eq!(run(s, m, c, "STORE CREATE archive"), Response::ok());
+    assert_eq!(run(s, m, c, "SET ABC hello 5000"), Response::ok());
+    assert_eq!(run(s, m, c, "MOVE ABC archive"), Response::Integer(1));
+    assert_eq!(run(s, m, c, "MOVE ABC archive"), Response::Integer(0));
+    assert!(matches!(
+        run(s, m, c, "MOVE ABC missing"),
+        Response::Error(_)
+    ));
+
+    assert_eq!(
+        run(s, m, c, "STORE RENAME archive old_archive"),
+        Response::ok()
+    );
+    assert!(c.get_store_config("old_archive").is_some());
+    assert_eq!(
+        run(s, m, c, "STORE COPY old_archive backup"),
+        Response::ok()
+    );
+    assert_eq!(
+        run(s, m, c, "USING backup GET ABC"),
+        Response::Bulk("hello".to_owned())
+    );
+    assert_eq!(run(s, m, c, "STORE FLUSH old_archive"), Response::ok());
+    assert_eq!(run(s, m, c, "USING old_archive GET ABC"), Response::Nil);
+
+    match run(s, m, c, "STORE INFO backup") {
+        Response::Array(items) => {
+            assert_eq!(items[1], Response::Bulk("backup".to_owned()));
+            assert_eq!(items[3], Response::Integer(1));
+            assert_eq!(items[5], Response::Integer(1));
+            assert_eq!(items[7], Response::Nil);
+        }
+        response => panic!("unexpected response {:?}", response),
+    }
+    assert!(matches!(
+        run(s, m, c, "STORE RENAME __INTERNAL_STORE__ public"),
+        Response::Error(_)
+    ));
+    for command in [
+        "STORE FLUSH __INTERNAL_STORE__",
+        "STORE COPY __INTERNAL_STORE__ public",
+        "STORE COPY backup __INTERNAL_STORE__",
+    ] {
+        assert!(matches!(run(s, m, c, command), Response::Error(_)));
+    }
+
+    // A stale config of the destination is replaced, not declared twice.
+    assert_eq!(run(s, m, c, "STORE DROP backup"), Response::Integer(1));
+    c.stores.push(StoreConfig {
+        name: "backup".to_owned(),
+        default_ttl: Some(1000),
+        max_memory: None,
+    });
+    assert_eq!(
+        run(s, m, c, "STORE COPY old_archive backup"),
+        Response::ok()
+    );
+    assert_eq!(
+        c.stores
+            .iter()
+            .filter(|store| store.name == "backup")
+            .count(),
+        1
+    );
+
+    // Admins are still limited to the stores they were granted.
+    run(
+        s,
+        m,
+        c,
+        "ACL SETUSER default on nopass +@all allkeys %default_store %old_*",
+    );
+    for command in [
+        "STORE INFO backup",
+        "STORE FLUSH backup",
+        "STORE DROP backup",
+        "STORE RENAME old_archive backup",
+        "STORE COPY old_archive new_archive",
+        "STORE COPY backup old_backup",
+    ] {
+        assert!(
+            matches!(run(s, m, c, command), Response::Error(e) if e.starts_with("NOPERM")),
+            "{}",
+            command
+        );
+    }
+    assert!(matches!(
+        run(s, m, c, "STORE INFO old_archive"),
+        Response::Array(_)
+    ));
+}
+
+#[test]
+fn test_keyspace_iteration() {
+    let (mut session, mut manager, mut config) = setup();
+    let (s, m, c) = (&mut session, &mut manager, &mut config);
+
+    for idx in 0..20 {
+        run(s, m, c, &format!("SET key:{} value", idx));
+    }
+    run(s, m, c, "SET other value");
+
+    let mut scanned = vec![];
+    let mut cursor = "0".to_owned();
+    loop {
+        match run(
+            s,
+            m,
+            c,
+            &format!("SCAN {} MATCH key:* COUNT 3 TYPE string", cursor),
+        ) {
+            Response::Array(items) => {
+                let Response::Bulk(next_cursor) = &items[0] else {
+                    panic!("unexpected cursor {:?}", items[0]);
+                };
+                let Response::Array(keys) = &items[1] else {
+                    panic!("unexpected keys {:?}", items[1]);
+                };
+                scanned.extend(keys.to_owned());
+                cursor = next_cursor.to_owned();
+            }
+            response => panic!("unexpected response {:?}", response),
+        }
+        if cursor == "0" {
+            break;
+        }
+    }
+    assert_eq!(scanned.len(), 20);
+
+    assert!(matches!(
+        run(s, m, c, "SCAN 0 TYPE nope"),
+        Response::Error(_)
+    ));
+    assert!(matches!(run(s, m, c, "SCAN 0 COUNT"), Response::Error(_)));
+    assert_eq!(
+        run(s, m, c, "KEYS oth*"),
+        Response::from_strings(vec!["other".to_owned()])
+    );
+    assert!(matches!(run(s, m, c, "RANDOMKEY"), Response::Bulk(_)));
+    assert!(matches!(run(s, m, c, "RANDOMKEY 3"), Response::Array(keys) if keys.len() == 3));
+}
+
+#[test]
+fn test_generic_key_commands() {
+    let (mut session, mut manager, mut config) = setup();
+    let (s, m, c) = (&mut session, &mut manager, &mut config);
+
+    run(s, m, c, "SET ABC hello");
+    run(s, m, c, "SET XYZ world");
+    assert_eq!(
+        run(s, m, c, "TYPE ABC"),
+        Response::Simple("string".to_owned())
+    );
+    assert_eq!(
+        run(s, m, c, "TYPE nope"),
+        Response::Simple("none".to_owned())
+    );
+    assert_eq!(
+        run(s, m, c, "EXISTS ABC XYZ nope ABC"),
+        Response::Integer(3)
+    );
+
+    assert_eq!(run(s, m, c, "RENAMENX ABC XYZ"), Response::Integer(0));
+    assert_eq!(run(s, m, c, "RENAMENX ABC DEF"), Response::Integer(1));
+    assert_eq!(run(s, m, c, "RENAME DEF ABC"), Response::ok());
+    assert!(matches!(run(s, m, c, "RENAME DEF ABC"), Response::Error(_)));
+
+    assert_eq!(run(s, m, c, "COPY ABC XYZ"), Response::Integer(0));
+    assert_eq!(run(s, m, c, "COPY ABC XYZ REPLACE"), Response::Integer(1));
+    assert_eq!(run(s, m, c, "GET XYZ"), Response::Bulk("hello".to_owned()));
+    assert!(matches!(
+        run(s, m, c, "COPY ABC XYZ STORE"),
+        Response::Error(_)
+    ));
+
+    run(s, m, c, "STORE CREATE other");
+    assert_eq!(
+        run(s, m, c, "COPY ABC copy STORE other"),
+        Response::Integer(1)
+    );
+    assert_eq!(
+        run(s, m, c, "USING other GET copy"),
+        Response::Bulk("hello".to_owned())
+    );
+
+    assert_eq!(run(s, m, c, "DEL ABC XYZ nope"), Response::Integer(2));
+    assert_eq!(run(s, m, c, "EXISTS ABC XYZ"), Response::Integer(0));
+}
+
+#[test]
+fn test_string_commands() {
+    let (mut session, mut manager, mut config) = setup();
+    let (s, m, c) = (&mut session, &mut manager, &mut config);
+
+    assert_eq!(run(s, m, c, "SET lock owner NX"), Response::ok());
+    assert_eq!(run(s, m, c, "SET lock other NX"), Response::Nil);
+    assert_eq!(run(s, m, c, "SET missing value XX"), Response::Nil);
+    assert_eq!(
+        run(s, m, c, "SET lock other 5000 XX GET"),
+        Response::Bulk("owner".to_owned())
+    );
+    assert!(matches!(
+        run(s, m, c, "SET lock x NX XX"),
+        Response::Error(_)
+    ));
+    assert!(matches!(
+        run(s, m, c, "SET lock x nope"),
+        Response::Error(_)
+    ));
+    assert_eq!(run(s, m, c, "SETNX lock again"), Response::Integer(0));
+    assert_eq!(
+        run(s, m, c, "GETSET lock mine"),
+        Response::Bulk("other".to_owned())
+    );
+
+    assert_eq!(run(s, m, c, "APPEND greeting hello"), Response::Integer(5));
+    assert_eq!(run(s, m, c, "APPEND greeting !"), Response::Integer(6));
+    assert_eq!(run(s, m, c, "STRLEN greeting"), Response::Integer(6));
+    assert_eq!(run(s, m, c, "STRLEN nope"), Response::Integer(0));
+    assert_eq!(run(s, m, c, "SETRANGE greeting 0 J"), Response::Integer(6));
+    assert_eq!(
+        run(s, m, c, "GETRANGE greeting 0 -2"),
+        Response::Bulk("Jello".to_owned())
+    );
+    assert!(matches!(
+        run(s, m, c, "SETRANGE greeting 99999999999 x"),
+        Response::Error(_)
+    ));
+
+    assert_eq!(run(s, m, c, "MSET a 1 b 2"), Response::ok());
+    assert!(matches!(run(s, m, c, "MSET a 1 b"), Response::Error(_)));
+    assert_eq!(run(s, m, c, "MSETNX b 3 c 3"), Response::Integer(0));
+    assert_eq!(run(s, m, c, "MSETNX c 3 d 4"), Response::Integer(1));
+    assert_eq!(
+        run(s, m, c, "MGET a nope d"),
+        Response::Array(vec![
+            Response::Bulk("1".to_owned()),
+            Response::Nil,
+            Response::Bulk("4".to_owned()),
+        ])
+    );
+}
+
+#[test]
+fn test_counter_commands() {
+    let (mut session, mut manager, mut config) = setup();
+    let (s, m, c) = (&mut session, &mut manager, &mut config);
+
+    assert_eq!(run(s, m, c, "INCR counter"), Response::Integer(1));
+    assert_eq!(run(s, m, c, "INCRBY counter -5"), Response::Integer(-4));
+    assert_eq!(run(s, m, c, "DECRBY counter 6"), Response::Integer(-10));
+    assert_eq!(run(s, m, c, "DECR counter"), Response::Integer(-11));
+    assert_eq!(
+        run(s, m, c, "GET counter"),
+        Response::Bulk("-11".to_owned())
+    );
+    assert_eq!(
+        run(s, m, c, "TYPE counter"),
+        Response::Simple("integer".to_owned())
+    );
+
+    run(s, m, c, &format!("SET big {}", i64::MAX));
+    assert!(matches!(run(s, m, c, "INCR big"), Response::Error(_)));
+    assert!(matches!(
+        run(s, m, c, "INCRBY big nope"),
+        Response::Error(_)
+    ));
+
+    assert_eq!(
+        run(s, m, c, "INCRBYFLOAT ratio 0.5"),
+        Response::Bulk("0.5".to_owned())
+    );
+    assert_eq!(
+        run(s, m, c, "INCRBYFLOAT counter 1.5"),
+        Response::Bulk("-9.5".to_owned())
+    );
+    assert_eq!(
+        run(s, m, c, "TYPE ratio"),
+        Response::Simple("float".to_owned())
+    );
+}
+
+#[test]
+fn test_sort_command() {
+    let (mut session, mut manager, mut config) = setup();
+    let store = manager.get_store_mut(DEFAULT_STORE_NAME).unwrap();
+    store.set_list(
+        "ids",
+        vec!["3".to_owned(), "1".to_owned(), "2".to_owned()],
+        None,
+    );
+    store.set_hset("tags", vec!["b".to_owned(), "a".to_owned()], None);
+    let (s, m, c) = (&mut session, &mut manager, &mut config);
+    run(s, m, c, "SET weight_1 30");
+    run(s, m, c, "SET weight_2 10");
+    run(s, m, c, "SET weight_3 20");
+    run(s, m, c, "SET name_1 one");
+    run(s, m, c, "SET name_3 three");
+
+    let bulks = |values: &[&str]| {
+        Response::from_strings(values.iter().map(|value| value.to_string()).collect())
+    };
+    assert_eq!(run(s, m, c, "SORT ids"), bulks(&["1", "2", "3"]));
+    assert_eq!(run(s, m, c, "SORT ids DESC LIMIT 0 2"), bulks(&["3", "2"]));
+    assert_eq!(run(s, m, c, "sort_ro tags alpha"), bulks(&["a", "b"]));
+    assert_eq!(
+        run(s, m, c, "SORT ids BY weight_*"),
+        bulks(&["2", "3", "1"])
+    );
+    assert_eq!(
+        run(s, m, c, "SORT ids BY weight_* GET # GET name_*"),
+        Response::Array(vec![
+            Response::Bulk("2".to_owned()),
+            Response::Nil,
+            Response::Bulk("3".to_owned()),
+            Response::Bulk("three".to_owned()),
+            Response::Bulk("1".to_owned()),
+            Response::Bulk("one".to_owned()),
+        ])
+    );
+    assert_eq!(
+        run(s, m, c, "SORT ids BY weight_* STORE sorted"),
+        Response::Integer(3)
+    );
+    assert_eq!(
+        run(s, m, c, "TYPE sorted"),
+        Response::Simple("list".to_owned())
+    );
+    assert!(matches!(run(s, m, c, "SORT tags"), Response::Error(_)));
+    assert!(
+        matches!(run(s, m, c, "SORT name_1"), Response::Error(e) if e.starts_with("WRONGTYPE"))
+    );
+    assert!(matches!(
+        run(s, m, c, "SORT ids LIMIT 1"),
+        Response::Error(_)
+    ));
+    assert!(matches!(
+        run(s, m, c, "SORT_RO ids STORE sorted"),
+        Response::Error(_)
+    ));
+
+    // Patterns read keys the user may not have been granted.
+    run(
+        s,
+        m,
+        c,
+        "ACL SETUSER default on nopass +@all ~ids ~sorted_* %default_store",
+    );
+    assert_eq!(
+        run(s, m, c, "SORT ids BY nosort GET #"),
+        bulks(&["3", "1", "2"])
+    );
+    assert!(
+        matches!(run(s, m, c, "SORT ids BY weight_*"), Response::Error(e) if e.starts_with("NOPERM"))
+    );
+    assert!(
+        matches!(run(s, m, c, "SORT ids STORE sorted"), Response::Error(e) if e.starts_with("NOPERM"))
+    );
+    assert_eq!(
+        run(s, m, c, "SORT ids STORE sorted_ids"),
+        Response::Integer(3)
+    );
+}
+
+#[test]
+fn test_script_commands() {
+    let (mut session, mut manager, mut config) = setup();
+    let (s, m, c) = (&mut session, &mut manager, &mut config);
+    let source = r#"let count = store.incr(KEYS[0]); if count <= parse_int(ARGV[0]) { store.list_pushb(KEYS[1], ARGV[1]); store.set_ttl(KEYS[1], 60000); } count"#;
+    let hash = script_hash(source);
+
+    assert_eq!(
+        run(s, m, c, &format!("EVALSHA {} 0", hash)),
+        Response::Error("NOSCRIPT No matching script. Please use EVAL.".to_owned())
+    );
+    assert_eq!(
+        run(s, m, c, &format!("SCRIPT LOAD '{}'", source)),
+        Response::Bulk(hash.to_owned())
+    );
+    assert_eq!(
+        run(s, m, c, &format!("SCRIPT EXISTS {} missing", hash)),
+        Response::Array(vec![Response::Integer(1), Response::Integer(0)])
+    );
+    for expected in [1, 2, 3] {
+        assert_eq!(
+            run(s, m, c, &format!("EVALSHA {} 2 hits events 2 hit", hash)),
+            Response::Integer(expected)
+        );
+    }
+    assert_eq!(
+        m.get_store(DEFAULT_STORE_NAME)
+            .unwrap()
+            .get_list("events")
+            .unwrap()
+            .unwrap(),
+        vec!["hit", "hit"]
+    );
+
+    assert_eq!(
+        run(
+            s,
+            m,
+            c,
+            r#"EVAL '[KEYS, ARGV, #{a: 1.5}, true, false, ()]' 1 k v"#
+        ),
+        Response::Array(vec![
+            Response::from_strings(vec!["k".to_owned()]),
+            Response::from_strings(vec!["v".to_owned()]),
+            Response::Array(vec![
+                Response::Bulk("a".to_owned()),
+                Response::Bulk("1.5".to_owned()),
+            ]),
+            Response::Integer(1),
+            Response::Nil,
+            Response::Nil,
+        ])
+    );
+    assert!(matches!(run(s, m, c, "EVAL '1 +' 0"), Response::Error(_)));
+    assert!(matches!(
+        run(s, m, c, "EVAL 'store.incr(KEYS[0])' 2 a"),
+        Response::Error(_)
+    ));
+    assert!(
+        matches!(run(s, m, c, "EVAL 'store.get_list(KEYS[0])' 1 hits"), Response::Error(e) if e.contains("WRONGTYPE"))
+    );
+    assert!(matches!(run(s, m, c, "EVAL 'store' 0"), Response::Error(e) if e.contains("Store")));
+    assert!(matches!(run(s, m, c, "SCRIPT KILL"), Response::Error(e) if e.starts_with("NOTBUSY")));
+    assert_eq!(kill_running_script(s, &["PING".to_owned()]), None);
+
+    // The declared keys are checked against the key patterns of the user.
+    run(
+        s,
+        m,
+        c,
+        "ACL SETUSER default on nopass +@write ~hits %default_store",
+    );
+    assert!(
+        matches!(run(s, m, c, &format!("EVALSHA {} 2 hits events 2 hit", hash)), Response::Error(e) if e.starts_with("NOPERM"))
+    );
+    assert!(
+        matches!(run(s, m, c, "EVAL 'store.get(\"events\")' 1 hits"), Response::Error(e) if e.contains("NOPERM"))
+    );
+    assert_eq!(
+        run(s, m, c, "EVAL 'store.get(KEYS[0])' 1 hits"),
+        Response::Bulk("3".to_owned())
+    );
+    assert!(matches!(run(s, m, c, "SCRIPT FLUSH"), Response::Error(e) if e.starts_with("NOPERM")));
+
+    c.get_or_create_user("default")
+        .apply_rule("+@admin")
+        .unwrap();
+    assert_eq!(run(s, m, c, "SCRIPT FLUSH"), Response::ok());
+    assert_eq!(
+        run(s, m, c, &format!("SCRIPT EXISTS {}", hash)),
+        Response::Array(vec![Response::Integer(0)])
+    );
+}
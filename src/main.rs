@@ -1,16 +1,23 @@
+mod commands;
 mod managers;
+mod scripting;
 mod stores;
 mod utils;
 
 use env_logger;
-use log::info;
+use log::{error, info};
 use managers::manager::RusticManager;
-use std::sync::Arc;
+use std::{env, path::PathBuf, process, sync::Arc};
 use stores::store::KeyValueStore;
 use tokio::{net::TcpListener, sync::RwLock, time::Instant};
 use utils::{
-    constants::{INTERNAL_STORE_NAME, IP_PORT_BINDING},
-    event_loop::event_loop,
+    config::RusticConfig,
+    constants::{CONFIG_PATH_ENV_VAR, INTERNAL_STORE_NAME},
+    event_loop::{
+        event_loop, reload_config_on_sighup, shutdown_signal, tls_event_loop, unix_event_loop,
+    },
+    tls::build_tls_acceptor,
+    unix_socket::{bind_unix_socket, remove_unix_socket},
 };
 
 #[tokio::main]
@@ -19,18 +26,85 @@ async fn main() {
     let start_instant = Instant::now();
     info!("Hello, rustics!");
 
+    // The config file can be passed as the first argument or through the environment.
+    let config = match env::args().nth(1).or(env::var(CONFIG_PATH_ENV_VAR).ok()) {
+        Some(path) => {
+            info!("Loading config from {}", path);
+            match RusticConfig::load(PathBuf::from(&path)) {
+                Ok(config) => config,
+                Err(err) => {
+                    error!("Could not load config from {}: {:?}", path, err);
+                    process::exit(1);
+                }
+            }
+        }
+        None => RusticConfig::default(),
+    };
+
     // Bind the listener to the address
-    let listener = TcpListener::bind(IP_PORT_BINDING).await.unwrap();
+    let listener = TcpListener::bind(&config.bind).await.unwrap();
 
-    info!("Listening on {}", IP_PORT_BINDING);
+    info!("Listening on {}", config.bind);
 
     // Using RwLock as it allows us to spawn n number of threads to read data, but only 1 to write it.
     let shared_manager = Arc::new(RwLock::new(RusticManager::new()));
-    shared_manager
-        .write()
-        .await
-        .create_store(INTERNAL_STORE_NAME, None);
+    {
+        let mut manager = shared_manager.write().await;
+        manager.create_store(INTERNAL_STORE_NAME, None).unwrap();
+        for store_config in config.stores.iter() {
+            manager.apply_store_config(store_config);
+        }
+    }
+    let tls = config.tls.to_owned();
+    let unix_socket = config.unix_socket.to_owned();
+    let shared_config = Arc::new(RwLock::new(config));
+
+    if let Some(tls_config) = tls {
+        let acceptor = match build_tls_acceptor(&tls_config) {
+            Ok(acceptor) => acceptor,
+            Err(err) => {
+                error!("Invalid TLS config: {}", err);
+                process::exit(1);
+            }
+        };
+        let tls_listener = TcpListener::bind(&tls_config.bind).await.unwrap();
+        info!("Listening for TLS connections on {}", tls_config.bind);
+        tokio::spawn(tls_event_loop(
+            tls_listener,
+            acceptor,
+            Arc::clone(&shared_manager),
+            Arc::clone(&shared_config),
+        ));
+    }
+
+    if let Some(unix_socket_config) = &unix_socket {
+        let unix_listener = bind_unix_socket(unix_socket_config).unwrap();
+        info!("Listening on Unix socket {:?}", unix_socket_config.path);
+        tokio::spawn(unix_event_loop(
+            unix_listener,
+            unix_socket_config.path.to_owned(),
+            Arc::clone(&shared_manager),
+            Arc::clone(&shared_config),
+        ));
+    }
+
+    tokio::spawn(reload_config_on_sighup(
+        Arc::clone(&shared_manager),
+        Arc::clone(&shared_config),
+    ));
+
+    // Start the event loop, until the process is asked to stop.
+    tokio::select! {
+        _ = event_loop(listener, shared_manager, shared_config, start_instant) => {}
+        _ = shutdown_signal() => {}
+    }
 
-    // Start the event loop
-    event_loop(listener, shared_manager, start_instant).await;
+    if let Some(unix_socket_config) = &unix_socket {
+        if let Err(err) = remove_unix_socket(unix_socket_config) {
+            error!(
+                "Could not remove Unix socket {:?}: {:?}",
+                unix_socket_config.path, err
+            );
+        }
+    }
 }
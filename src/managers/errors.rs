@@ -0,0 +1,6 @@
+#[derive(Debug, PartialEq)]
+pub enum StoreManagerError {
+    StoreAlreadyExists(String),
+    StoreNotFound(String),
+    SameStore(String),
+}
@@ -1,26 +1,38 @@
-use crate::stores::store::KeyValueStore;
+use super::errors::StoreManagerError;
+use crate::scripting::registry::ScriptRegistry;
+use crate::stores::store::{KeyValueStore, StoreInfo};
+use crate::utils::config::StoreConfig;
 use log::info;
 use std::collections::HashMap;
 
 pub struct RusticManager {
     _stores_map: HashMap<String, KeyValueStore>,
+
+    /// Scripts registered for all the stores.
+    _scripts: ScriptRegistry,
 }
 
 impl RusticManager {
     pub fn new() -> RusticManager {
         return RusticManager {
             _stores_map: HashMap::new(),
+            _scripts: ScriptRegistry::new(),
         };
     }
 
     /// Creates a data store and returns a mutable reference to it.
-    pub fn create_store(&mut self, name: &str, default_ttl: Option<u64>) -> &mut KeyValueStore {
+    /// Fails if a store with this name already exists.
+    pub fn create_store(
+        &mut self,
+        name: &str,
+        default_ttl: Option<u64>,
+    ) -> Result<&mut KeyValueStore, StoreManagerError> {
         if self._stores_map.contains_key(&name.to_owned()) {
-            panic!("Store with this name already exists.");
+            return Err(StoreManagerError::StoreAlreadyExists(name.to_owned()));
         }
         let new_kv_store: KeyValueStore = KeyValueStore::new(name, default_ttl);
         self._stores_map.insert(name.to_owned(), new_kv_store);
-        self._stores_map.get_mut(&name.to_owned()).unwrap()
+        Ok(self._stores_map.get_mut(&name.to_owned()).unwrap())
     }
 
     /// Removes the data store and returns True or False based on if it existed.
@@ -43,11 +55,124 @@ impl RusticManager {
         self._stores_map.get(&name.to_owned())
     }
 
+    pub fn scripts(&self) -> &ScriptRegistry {
+        &self._scripts
+    }
+
+    pub fn scripts_mut(&mut self) -> &mut ScriptRegistry {
+        &mut self._scripts
+    }
+
     /// List names of all the stores
     pub fn list_store_names(&self) -> Vec<&String> {
         self._stores_map.keys().collect()
     }
 
+    /// Renames a data store, keeping its data and settings.
+    pub fn rename_store(&mut self, name: &str, new_name: &str) -> Result<(), StoreManagerError> {
+        if !self._stores_map.contains_key(name) {
+            return Err(StoreManagerError::StoreNotFound(name.to_owned()));
+        }
+        if self._stores_map.contains_key(new_name) {
+            return Err(StoreManagerError::StoreAlreadyExists(new_name.to_owned()));
+        }
+        let mut store = self._stores_map.remove(name).unwrap();
+        store.set_name(new_name);
+        self._stores_map.insert(new_name.to_owned(), store);
+        Ok(())
+    }
+
+    /// Deep copies a data store (data and settings) into a new store and returns a mutable reference to it.
+    pub fn copy_store(
+        &mut self,
+        name: &str,
+        new_name: &str,
+    ) -> Result<&mut KeyValueStore, StoreManagerError> {
+        if self._stores_map.contains_key(new_name) {
+            return Err(StoreManagerError::StoreAlreadyExists(new_name.to_owned()));
+        }
+        let mut new_store = match self._stores_map.get(name) {
+            Some(store) => store.clone(),
+            None => return Err(StoreManagerError::StoreNotFound(name.to_owned())),
+        };
+        new_store.set_name(new_name);
+        self._stores_map.insert(new_name.to_owned(), new_store);
+        Ok(self._stores_map.get_mut(new_name).unwrap())
+    }
+
+    /// Removes all the keys of a data store.
+    pub fn flush_store(&mut self, name: &str) -> Result<(), StoreManagerError> {
+        match self._stores_map.get_mut(name) {
+            Some(store) => {
+                store.clear();
+                Ok(())
+            }
+            None => Err(StoreManagerError::StoreNotFound(name.to_owned())),
+        }
+    }
+
+    /// Summarizes the contents and settings of a data store.
+    pub fn get_store_info(&self, name: &str) -> Result<StoreInfo, StoreManagerError> {
+        match self._stores_map.get(name) {
+            Some(store) => Ok(store.info()),
+            None => Err(StoreManagerError::StoreNotFound(name.to_owned())),
+        }
+    }
+
+    fn _get_two_stores_mut(
+        &mut self,
+        name: &str,
+        other_name: &str,
+    ) -> Result<[&mut KeyValueStore; 2], StoreManagerError> {
+        if name == other_name {
+            return Err(StoreManagerError::SameStore(name.to_owned()));
+        }
+        match self._stores_map.get_disjoint_mut([name, other_name]) {
+            [Some(store), Some(other_store)] => Ok([store, other_store]),
+            [None, _] => Err(StoreManagerError::StoreNotFound(name.to_owned())),
+            [_, None] => Err(StoreManagerError::StoreNotFound(other_name.to_owned())),
+        }
+    }
+
+    /// Moves a key (keeping its expiration) from a data store to another one.
+    /// Returns false if the key doesn't exist, or if it exists in the destination and `replace` is false.
+    pub fn move_key(
+        &mut self,
+        key: &str,
+        source: &str,
+        destination: &str,
+        replace: bool,
+    ) -> Result<bool, StoreManagerError> {
+        let [source_store, destination_store] = self._get_two_stores_mut(source, destination)?;
+        Ok(source_store.move_key_to(key, destination_store, replace))
+    }
+
+    /// Copies a key (keeping its expiration) from a data store to another one, under a new key.
+    /// Returns false if the key doesn't exist, or if the destination key exists and `replace` is false.
+    pub fn copy_key(
+        &mut self,
+        key: &str,
+        source: &str,
+        destination_key: &str,
+        destination: &str,
+        replace: bool,
+    ) -> Result<bool, StoreManagerError> {
+        let [source_store, destination_store] = self._get_two_stores_mut(source, destination)?;
+        Ok(source_store.copy_key_to(key, destination_store, destination_key, replace))
+    }
+
+    /// Creates the store described by the config if it doesn't exist,
+    /// otherwise updates the settings of the existing store.
+    pub fn apply_store_config(&mut self, store_config: &StoreConfig) {
+        if !self._stores_map.contains_key(&store_config.name) {
+            self.create_store(&store_config.name, store_config.default_ttl)
+                .unwrap();
+        }
+        let store = self._stores_map.get_mut(&store_config.name).unwrap();
+        store.set_default_ttl(store_config.default_ttl);
+        store.set_max_memory(store_config.max_memory);
+    }
+
     /// Clear expired keys periodically
     pub fn clear_expired_keys_in_all_stores(&mut self) {
         // Remove expired keys
@@ -1,10 +1,10 @@
-use super::manager::RusticManager;
+use super::{errors::StoreManagerError, manager::RusticManager};
 
 #[test]
 fn test_create_store() {
     let mut manager = RusticManager::new();
     // create a store with infinite TTL
-    let store = manager.create_store("store0", None);
+    let store = manager.create_store("store0", None).unwrap();
     store.set_i64("key", 5, None);
     assert_eq!(store.get_i64("key").unwrap().unwrap(), 5);
 }
@@ -13,7 +13,7 @@ fn test_create_store() {
 fn test_get_store() {
     let mut manager = RusticManager::new();
     // create a store with infinite TTL
-    manager.create_store("store0", None);
+    manager.create_store("store0", None).unwrap();
 
     let mut_store = manager.get_store_mut("store0").unwrap();
     mut_store.set_i64("key", 5, None);
@@ -25,7 +25,7 @@ fn test_get_store() {
 #[test]
 fn test_remove_store() {
     let mut manager = RusticManager::new();
-    manager.create_store("store0", None);
+    manager.create_store("store0", None).unwrap();
 
     let store = manager.get_store_mut("store0").unwrap();
     store.set_i64("key", 5, None);
@@ -37,21 +37,23 @@ fn test_remove_store() {
 }
 
 #[test]
-#[should_panic]
 fn test_duplicate_store_names() {
     let mut manager = RusticManager::new();
     // create a store with infinite TTL
-    manager.create_store("store0", None);
-    manager.create_store("store0", None);
+    manager.create_store("store0", None).unwrap();
+    assert_eq!(
+        manager.create_store("store0", None).err(),
+        Some(StoreManagerError::StoreAlreadyExists("store0".to_owned()))
+    );
 }
 
 #[test]
 fn test_list_store_names() {
     let mut manager = RusticManager::new();
     // create a store with infinite TTL
-    manager.create_store("store0", None);
-    manager.create_store("store1", None);
-    manager.create_store("store2", None);
+    manager.create_store("store0", None).unwrap();
+    manager.create_store("store1", None).unwrap();
+    manager.create_store("store2", None).unwrap();
 
     let mut expected_keys: Vec<String> = manager
         .list_store_names()
@@ -68,3 +70,112 @@ fn test_list_store_names() {
         ]
     )
 }
+
+#[test]
+fn test_rename_copy_flush_store() {
+    let mut manager = RusticManager::new();
+    let store = manager.create_store("store0", Some(5000)).unwrap();
+    store.set_i64("key", 5, None);
+    manager.create_store("store1", None).unwrap();
+
+    assert_eq!(
+        manager.rename_store("store0", "store1"),
+        Err(StoreManagerError::StoreAlreadyExists("store1".to_owned()))
+    );
+    assert_eq!(
+        manager.rename_store("missing", "store2"),
+        Err(StoreManagerError::StoreNotFound("missing".to_owned()))
+    );
+    manager.rename_store("store0", "renamed").unwrap();
+    assert!(manager.get_store("store0").is_none());
+    let renamed = manager.get_store("renamed").unwrap();
+    assert_eq!(renamed.get_name(), "renamed");
+    assert_eq!(renamed.get_default_ttl(), Some(5000));
+    assert_eq!(renamed.get_i64("key").unwrap().unwrap(), 5);
+
+    // The copy is independent from the original store.
+    let copy = manager.copy_store("renamed", "copy").unwrap();
+    copy.set_i64("key", 6, None);
+    assert_eq!(copy.get_default_ttl(), Some(5000));
+    assert_eq!(
+        manager
+            .get_store("renamed")
+            .unwrap()
+            .get_i64("key")
+            .unwrap()
+            .unwrap(),
+        5
+    );
+
+    manager.flush_store("copy").unwrap();
+    assert!(manager.get_store("copy").unwrap().get_i64("key").is_none());
+    assert_eq!(
+        manager.flush_store("missing"),
+        Err(StoreManagerError::StoreNotFound("missing".to_owned()))
+    );
+}
+
+#[test]
+fn test_store_info() {
+    let mut manager = RusticManager::new();
+    let store = manager.create_store("store0", None).unwrap();
+    store.set_i64("forever", 5, None);
+    store.set_string("expiring", "value".to_owned(), Some(5000));
+    store.set_string("expired", "value".to_owned(), Some(0));
+    store.set_max_memory(Some(1024));
+
+    let info = manager.get_store_info("store0").unwrap();
+    assert_eq!(info.name, "store0");
+    assert_eq!(info.keys, 2);
+    assert_eq!(info.expiring_keys, 1);
+    assert_eq!(info.default_ttl, None);
+    assert_eq!(info.max_memory, Some(1024));
+    assert!(info.estimated_memory_usage > 0);
+}
+
+#[test]
+fn test_move_copy_key_between_stores() {
+    let mut manager = RusticManager::new();
+    let store = manager.create_store("store0", None).unwrap();
+    store.set_string("key", "value".to_owned(), Some(5000));
+    store.set_string("other", "value".to_owned(), None);
+    let store = manager.create_store("store1", None).unwrap();
+    store.set_string("other", "existing".to_owned(), None);
+
+    assert_eq!(
+        manager.move_key("key", "store0", "store0", false),
+        Err(StoreManagerError::SameStore("store0".to_owned()))
+    );
+    assert_eq!(
+        manager.move_key("missing", "store0", "store1", false),
+        Ok(false)
+    );
+    assert_eq!(
+        manager.move_key("other", "store0", "store1", false),
+        Ok(false)
+    );
+
+    assert_eq!(manager.move_key("key", "store0", "store1", false), Ok(true));
+    assert!(manager
+        .get_store("store0")
+        .unwrap()
+        .get_string("key")
+        .is_none());
+    let store1 = manager.get_store("store1").unwrap();
+    assert_eq!(store1.get_string("key").unwrap().unwrap(), "value");
+    // The time to live is kept.
+    assert_eq!(store1.info().expiring_keys, 1);
+
+    assert_eq!(
+        manager.copy_key("other", "store0", "other", "store1", true),
+        Ok(true)
+    );
+    assert_eq!(
+        manager.copy_key("other", "store0", "other_copy", "store1", false),
+        Ok(true)
+    );
+    let store1 = manager.get_store("store1").unwrap();
+    assert_eq!(store1.get_string("other").unwrap().unwrap(), "value");
+    assert_eq!(store1.get_string("other_copy").unwrap().unwrap(), "value");
+    assert!(manager.get_store("store0").unwrap().contains_key("other"));
+}
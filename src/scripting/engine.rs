@@ -0,0 +1,568 @@
+use log::info;
+use rhai::{
+    module_resolvers::DummyModuleResolver, Array, Dynamic, Engine, EvalAltResult, Map, Scope, AST,
+    INT,
+};
+use std::{
+    collections::HashMap,
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+use super::errors::ScriptError;
+use crate::managers::manager::RusticManager;
+use crate::stores::{
+    errors::ValueError,
+    store::{KeySnapshot, KeyValueStore},
+};
+use crate::utils::{
+    acl::{find_session_user, UserConfig},
+    config::RusticConfig,
+};
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Limits on what a script may allocate or how deep it may go, so that a script can't take
+/// the server down. Operations are limited as well, in case no time limit is configured.
+const MAX_STRING_SIZE: usize = 16 * 1024 * 1024;
+const MAX_ARRAY_SIZE: usize = 1024 * 1024;
+const MAX_MAP_SIZE: usize = 1024 * 1024;
+const MAX_CALL_LEVELS: usize = 64;
+const MAX_EXPR_DEPTH: usize = 64;
+const MAX_FUNCTION_EXPR_DEPTH: usize = 32;
+const MAX_OPERATIONS: u64 = 1_000_000_000;
+
+/// A script being run, so that other connections can kill it while it holds the manager.
+struct RunningScript {
+    killed: Arc<AtomicBool>,
+    /// Users configured when the script started, to check who may kill it.
+    users: Vec<UserConfig>,
+}
+
+/// Scripts being run, there is at most one on a server as a script holds the manager.
+static RUNNING_SCRIPTS: Mutex<Vec<RunningScript>> = Mutex::new(vec![]);
+
+fn _running_scripts() -> MutexGuard<'static, Vec<RunningScript>> {
+    RUNNING_SCRIPTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Kills the running scripts `can_kill` allows, given the users configured when each script
+/// started. Returns None if no script is running, otherwise an error if none could be killed.
+pub fn kill_running_script<E>(
+    can_kill: impl Fn(&[UserConfig]) -> Result<(), E>,
+) -> Option<Result<(), E>> {
+    let mut result = None;
+    for running_script in _running_scripts().iter() {
+        match can_kill(&running_script.users) {
+            Ok(()) => {
+                running_script.killed.store(true, Ordering::Relaxed);
+                result = Some(Ok(()));
+            }
+            Err(e) if result.is_none() => result = Some(Err(e)),
+            Err(_) => (),
+        }
+    }
+    result
+}
+
+/// What a script can reach: the stores of the manager, with the permissions of its user.
+struct ScriptState {
+    manager: RusticManager,
+    /// None when access control is disabled.
+    user: Option<UserConfig>,
+    cross_store: bool,
+    /// Keys as they were before the script first wrote to them, by store and key.
+    snapshots: HashMap<(String, String), KeySnapshot>,
+}
+
+impl ScriptState {
+    /// Puts back the keys the script wrote to, so that a script that fails leaves no trace.
+    fn _rollback(&mut self) {
+        for ((store_name, key), snapshot) in self.snapshots.drain() {
+            if let Some(store) = self.manager.get_store_mut(&store_name) {
+                store.restore_key(&key, snapshot);
+            }
+        }
+    }
+
+    fn _check_key(&self, key: &str) -> ScriptResult<()> {
+        match &self.user {
+            Some(user) if !user.can_access_key(key) => Err(format!(
+                "NOPERM User {} has no permissions to access the '{}' key",
+                user.name, key
+            )
+            .into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Handle on a store, through which scripts use the `KeyValueStore` API.
+/// The script gets the store it runs against as `store`, and may open others with
+/// `store.open_store(name)` when `script_cross_store` is enabled.
+#[derive(Clone)]
+pub struct ScriptStore {
+    name: String,
+    state: Arc<Mutex<ScriptState>>,
+}
+
+fn _value_error(error: ValueError) -> Box<EvalAltResult> {
+    match error {
+        ValueError::TypeConversionImpossible(_) => {
+            "WRONGTYPE Operation against a key holding the wrong kind of value".into()
+        }
+        ValueError::TypeConversionError(e) => format!("value conversion failed: {:?}", e).into(),
+    }
+}
+
+fn _ttl(ttl: INT) -> ScriptResult<u64> {
+    u64::try_from(ttl).map_err(|_| format!("invalid time to live {}", ttl).into())
+}
+
+fn _optional_string(value: Option<Result<String, ValueError>>) -> ScriptResult<Dynamic> {
+    match value {
+        Some(Ok(value)) => Ok(value.into()),
+        Some(Err(e)) => Err(_value_error(e)),
+        None => Ok(Dynamic::UNIT),
+    }
+}
+
+fn _strings(values: impl IntoIterator<Item = String>) -> Array {
+    values.into_iter().map(Dynamic::from).collect()
+}
+
+impl ScriptStore {
+    /// Runs `operation` against the store, once the user is known to be allowed to access the key.
+    /// Writes are rejected when the store is over its memory limit, otherwise the key is
+    /// snapshotted before it is first written to, to roll the script back if it fails.
+    fn _with_key<T>(
+        &mut self,
+        key: &str,
+        write: bool,
+        operation: impl FnOnce(&mut KeyValueStore) -> ScriptResult<T>,
+    ) -> ScriptResult<T> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state._check_key(key)?;
+        let ScriptState {
+            manager, snapshots, ..
+        } = &mut *state;
+        let Some(store) = manager.get_store_mut(&self.name) else {
+            return Err(format!("store '{}' does not exist", self.name).into());
+        };
+        if write {
+            if store.is_over_memory_limit() {
+                return Err("OOM command not allowed when used memory > 'max_memory'".into());
+            }
+            snapshots
+                .entry((self.name.to_owned(), key.to_owned()))
+                .or_insert_with(|| store.snapshot_key(key));
+        }
+        operation(store)
+    }
+
+    fn open_store(&mut self, name: &str) -> ScriptResult<ScriptStore> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if name != self.name && !state.cross_store {
+            return Err("scripts may not open other stores, see 'script_cross_store'".into());
+        }
+        if let Some(user) = state
+            .user
+            .as_ref()
+            .filter(|user| !user.can_access_store(name))
+        {
+            return Err(format!(
+                "NOPERM User {} has no permissions to access the '{}' store",
+                user.name, name
+            )
+            .into());
+        }
+        if state.manager.get_store(name).is_none() {
+            return Err(format!("store '{}' does not exist", name).into());
+        }
+        Ok(ScriptStore {
+            name: name.to_owned(),
+            state: Arc::clone(&self.state),
+        })
+    }
+
+    fn get(&mut self, key: &str) -> ScriptResult<Dynamic> {
+        self._with_key(key, false, |store| _optional_string(store.get_string(key)))
+    }
+
+    fn set(&mut self, key: &str, value: Dynamic) -> ScriptResult<()> {
+        self._with_key(key, true, |store| {
+            store.set_string(key, value.to_string(), None);
+            Ok(())
+        })
+    }
+
+    fn set_with_ttl(&mut self, key: &str, value: Dynamic, ttl: INT) -> ScriptResult<()> {
+        let ttl = _ttl(ttl)?;
+        self._with_key(key, true, |store| {
+            store.set_string(key, value.to_string(), Some(ttl));
+            Ok(())
+        })
+    }
+
+    fn remove(&mut self, key: &str) -> ScriptResult<bool> {
+        self._with_key(key, true, |store| Ok(store.remove(key)))
+    }
+
+    fn exists(&mut self, key: &str) -> ScriptResult<bool> {
+        self._with_key(key, false, |store| Ok(store.count_existing(&[key]) == 1))
+    }
+
+    fn kind(&mut self, key: &str) -> ScriptResult<Dynamic> {
+        self._with_key(key, false, |store| {
+            Ok(match store.get_kind(key) {
+                Some(kind) => kind.name().into(),
+                None => Dynamic::UNIT,
+            })
+        })
+    }
+
+    /// Gets the keys matching the glob pattern, leaving out the ones the user may not access.
+    fn keys(&mut self, pattern: &str) -> ScriptResult<Array> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(store) = state.manager.get_store(&self.name) else {
+            return Err(format!("store '{}' does not exist", self.name).into());
+        };
+        let keys = store
+            .keys(pattern)
+            .into_iter()
+            .filter(|key| state._check_key(key).is_ok());
+        Ok(_strings(keys))
+    }
+
+    fn incr(&mut self, key: &str, by: INT) -> ScriptResult<INT> {
+        self._with_key(key, true, |store| {
+            store.incr(key, Some(by)).map_err(_value_error)
+        })
+    }
+
+    fn decr(&mut self, key: &str, by: INT) -> ScriptResult<INT> {
+        self._with_key(key, true, |store| {
+            store.decr(key, Some(by)).map_err(_value_error)
+        })
+    }
+
+    fn set_ttl(&mut self, key: &str, ttl: INT) -> ScriptResult<bool> {
+        let ttl = _ttl(ttl)?;
+        self._with_key(key, true, |store| Ok(store.set_ttl(key, ttl)))
+    }
+
+    /// Gets the remaining time to live of a key, -1 if it never expires and -2 if it doesn't exist.
+    fn get_ttl(&mut self, key: &str) -> ScriptResult<INT> {
+        self._with_key(key, false, |store| {
+            Ok(match store.get_ttl(key) {
+                Some(Some(ttl)) => ttl as INT,
+                Some(None) => -1,
+                None => -2,
+            })
+        })
+    }
+
+    fn remove_ttl(&mut self, key: &str) -> ScriptResult<bool> {
+        self._with_key(key, true, |store| Ok(store.remove_ttl(key)))
+    }
+
+    /// Pushes to the back (or the front) of a list, creating it if needed.
+    /// Returns the length of the list.
+    fn _list_push(&mut self, key: &str, value: Dynamic, front: bool) -> ScriptResult<INT> {
+        self._with_key(key, true, |store| {
+            let value = value.to_string();
+            let pushed = if front {
+                store.list_pushf(key, value.to_owned())
+            } else {
+                store.list_pushb(key, value.to_owned())
+            };
+            match pushed {
+                Some(Ok(_)) => (),
+                Some(Err(e)) => return Err(_value_error(e)),
+                None => store.set_list(key, vec![value], None),
+            }
+            Ok(store.list_size(key).unwrap().map_err(_value_error)? as INT)
+        })
+    }
+
+    fn list_pushb(&mut self, key: &str, value: Dynamic) -> ScriptResult<INT> {
+        self._list_push(key, value, false)
+    }
+
+    fn list_pushf(&mut self, key: &str, value: Dynamic) -> ScriptResult<INT> {
+        self._list_push(key, value, true)
+    }
+
+    fn list_popb(&mut self, key: &str) -> ScriptResult<Dynamic> {
+        self._with_key(key, true, |store| _optional_string(store.list_popb(key)))
+    }
+
+    fn list_popf(&mut self, key: &str) -> ScriptResult<Dynamic> {
+        self._with_key(key, true, |store| _optional_string(store.list_popf(key)))
+    }
+
+    fn list_size(&mut self, key: &str) -> ScriptResult<INT> {
+        self._with_key(key, false, |store| match store.list_size(key) {
+            Some(Ok(size)) => Ok(size as INT),
+            Some(Err(e)) => Err(_value_error(e)),
+            None => Ok(0),
+        })
+    }
+
+    fn get_list(&mut self, key: &str) -> ScriptResult<Array> {
+        self._with_key(key, false, |store| match store.get_list(key) {
+            Some(Ok(list)) => Ok(_strings(list)),
+            Some(Err(e)) => Err(_value_error(e)),
+            None => Ok(Array::new()),
+        })
+    }
+
+    fn hmap_get(&mut self, key: &str, field: &str) -> ScriptResult<Dynamic> {
+        self._with_key(key, false, |store| {
+            _optional_string(store.hmap_get(key, field.to_owned()))
+        })
+    }
+
+    /// Sets a field of a hmap, creating it if needed. Returns the size of the hmap.
+    fn hmap_insert(&mut self, key: &str, field: &str, value: Dynamic) -> ScriptResult<INT> {
+        self._with_key(key, true, |store| {
+            match store.hmap_insert(key, (field.to_owned(), value.to_string())) {
+                Ok(size) => Ok(size as INT),
+                Err(e) => Err(_value_error(e)),
+            }
+        })
+    }
+
+    fn hmap_remove(&mut self, key: &str, field: &str) -> ScriptResult<bool> {
+        self._with_key(key, true, |store| {
+            match store.hmap_remove(key, field.to_owned()) {
+                Some(Ok(_)) => Ok(true),
+                Some(Err(e)) => Err(_value_error(e)),
+                None => Ok(false),
+            }
+        })
+    }
+
+    fn hmap_incr(&mut self, key: &str, field: &str, by: INT) -> ScriptResult<INT> {
+        self._with_key(key, true, |store| {
+            store
+                .hmap_incr(key, field.to_owned(), by)
+                .map_err(_value_error)
+        })
+    }
+
+    fn get_hmap(&mut self, key: &str) -> ScriptResult<Map> {
+        self._with_key(key, false, |store| match store.get_hmap(key) {
+            Some(Ok(hmap)) => Ok(hmap
+                .into_iter()
+                .map(|(field, value)| (field.into(), value.into()))
+                .collect()),
+            Some(Err(e)) => Err(_value_error(e)),
+            None => Ok(Map::new()),
+        })
+    }
+
+    /// Adds a member to a set, creating it if needed. Returns the cardinality of the set.
+    fn hset_add(&mut self, key: &str, member: Dynamic) -> ScriptResult<INT> {
+        self._with_key(key, true, |store| {
+            match store.hset_add(key, member.to_string()) {
+                Some(Ok(size)) => Ok(size as INT),
+                Some(Err(e)) => Err(_value_error(e)),
+                None => {
+                    store.set_hset(key, vec![member.to_string()], None);
+                    Ok(1)
+                }
+            }
+        })
+    }
+
+    fn hset_remove(&mut self, key: &str, member: Dynamic) -> ScriptResult<INT> {
+        self._with_key(key, true, |store| {
+            match store.hset_remove(key, member.to_string()) {
+                Some(Ok(size)) => Ok(size as INT),
+                Some(Err(e)) => Err(_value_error(e)),
+                None => Ok(0),
+            }
+        })
+    }
+
+    fn hset_contains(&mut self, key: &str, member: Dynamic) -> ScriptResult<bool> {
+        self._with_key(key, false, |store| {
+            match store.hset_contains(key, member.to_string()) {
+                Some(Ok(contains)) => Ok(contains),
+                Some(Err(e)) => Err(_value_error(e)),
+                None => Ok(false),
+            }
+        })
+    }
+
+    fn get_hset(&mut self, key: &str) -> ScriptResult<Array> {
+        self._with_key(key, false, |store| match store.get_hset(key) {
+            Some(Ok(hset)) => Ok(_strings(hset)),
+            Some(Err(e)) => Err(_value_error(e)),
+            None => Ok(Array::new()),
+        })
+    }
+}
+
+/// Builds an engine that can't load modules and enforces the limits above, to compile
+/// scripts as well as to run them.
+pub(super) fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_ARRAY_SIZE)
+        .set_max_map_size(MAX_MAP_SIZE)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_expr_depths(MAX_EXPR_DEPTH, MAX_FUNCTION_EXPR_DEPTH)
+        .set_max_operations(MAX_OPERATIONS);
+    engine
+}
+
+/// Builds a sandboxed engine exposing the `Store` type, which stops scripts once `killed` is set
+/// or they run longer than `time_limit`.
+fn _build_engine(killed: Arc<AtomicBool>, time_limit: Option<Duration>) -> Engine {
+    let mut engine = sandboxed_engine();
+    let started = Instant::now();
+    engine.on_progress(move |_| {
+        let stop = killed.load(Ordering::Relaxed)
+            || time_limit.is_some_and(|time_limit| started.elapsed() >= time_limit);
+        stop.then_some(Dynamic::UNIT)
+    });
+    engine.on_print(|text| info!("Script: {}", text));
+    engine.on_debug(|text, _, _| info!("Script: {}", text));
+
+    engine
+        .register_type_with_name::<ScriptStore>("Store")
+        .register_fn("open_store", ScriptStore::open_store)
+        .register_fn("get", ScriptStore::get)
+        .register_fn("set", ScriptStore::set)
+        .register_fn("set", ScriptStore::set_with_ttl)
+        .register_fn("remove", ScriptStore::remove)
+        .register_fn("exists", ScriptStore::exists)
+        .register_fn("kind", ScriptStore::kind)
+        .register_fn("keys", ScriptStore::keys)
+        .register_fn("incr", |store: &mut ScriptStore, key: &str| {
+            store.incr(key, 1)
+        })
+        .register_fn("incr", ScriptStore::incr)
+        .register_fn("decr", |store: &mut ScriptStore, key: &str| {
+            store.decr(key, 1)
+        })
+        .register_fn("decr", ScriptStore::decr)
+        .register_fn("set_ttl", ScriptStore::set_ttl)
+        .register_fn("get_ttl", ScriptStore::get_ttl)
+        .register_fn("remove_ttl", ScriptStore::remove_ttl)
+        .register_fn("list_pushb", ScriptStore::list_pushb)
+        .register_fn("list_pushf", ScriptStore::list_pushf)
+        .register_fn("list_popb", ScriptStore::list_popb)
+        .register_fn("list_popf", ScriptStore::list_popf)
+        .register_fn("list_size", ScriptStore::list_size)
+        .register_fn("get_list", ScriptStore::get_list)
+        .register_fn("hmap_get", ScriptStore::hmap_get)
+        .register_fn("hmap_insert", ScriptStore::hmap_insert)
+        .register_fn("hmap_remove", ScriptStore::hmap_remove)
+        .register_fn("hmap_incr", ScriptStore::hmap_incr)
+        .register_fn("get_hmap", ScriptStore::get_hmap)
+        .register_fn("hset_add", ScriptStore::hset_add)
+        .register_fn("hset_remove", ScriptStore::hset_remove)
+        .register_fn("hset_contains", ScriptStore::hset_contains)
+        .register_fn("get_hset", ScriptStore::get_hset);
+    engine
+}
+
+/// Puts the stores back into the manager once the script is done (even if a binding panicked),
+/// and forgets about the running script.
+struct RunGuard<'a> {
+    manager: &'a mut RusticManager,
+    state: Arc<Mutex<ScriptState>>,
+    killed: Arc<AtomicBool>,
+}
+
+impl Drop for RunGuard<'_> {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        mem::swap(self.manager, &mut state.manager);
+        _running_scripts()
+            .retain(|running_script| !Arc::ptr_eq(&running_script.killed, &self.killed));
+    }
+}
+
+/// Runs a compiled script against a store, on behalf of a user (None if the connection didn't
+/// authenticate), with its `KEYS` and `ARGV` arrays. Returns the value the script evaluates to.
+/// The whole script runs while the manager is held, so no other command sees it half done,
+/// and the writes of a script that fails, times out or is killed are rolled back.
+pub fn run_script(
+    ast: &AST,
+    manager: &mut RusticManager,
+    config: &RusticConfig,
+    store_name: &str,
+    user_name: Option<&str>,
+    keys: Vec<String>,
+    args: Vec<String>,
+) -> Result<Dynamic, ScriptError> {
+    let user = match config.users.is_empty() {
+        true => None,
+        false => match find_session_user(&config.users, user_name) {
+            Some(user) => Some(user.to_owned()),
+            None => {
+                return Err(ScriptError::Runtime(
+                    "NOAUTH Authentication required.".to_owned(),
+                ))
+            }
+        },
+    };
+    let killed = Arc::new(AtomicBool::new(false));
+    _running_scripts().push(RunningScript {
+        killed: Arc::clone(&killed),
+        users: config.users.to_owned(),
+    });
+    let state = Arc::new(Mutex::new(ScriptState {
+        manager: mem::replace(manager, RusticManager::new()),
+        user,
+        cross_store: config.script_cross_store,
+        snapshots: HashMap::new(),
+    }));
+    let _guard = RunGuard {
+        manager,
+        state: Arc::clone(&state),
+        killed: Arc::clone(&killed),
+    };
+
+    let engine = _build_engine(
+        Arc::clone(&killed),
+        config.script_time_limit_ms.map(Duration::from_millis),
+    );
+    let mut scope = Scope::new();
+    scope.push_constant("KEYS", _strings(keys));
+    scope.push_constant("ARGV", _strings(args));
+    scope.push(
+        "store",
+        ScriptStore {
+            name: store_name.to_owned(),
+            state: Arc::clone(&state),
+        },
+    );
+    let result = engine.eval_ast_with_scope::<Dynamic>(&mut scope, ast);
+    if result.is_err() {
+        state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            ._rollback();
+    }
+    result.map_err(|error| match *error {
+        EvalAltResult::ErrorTerminated(..) if killed.load(Ordering::Relaxed) => ScriptError::Killed,
+        EvalAltResult::ErrorTerminated(..) => {
+            ScriptError::TimedOut(config.script_time_limit_ms.unwrap_or_default())
+        }
+        EvalAltResult::ErrorRuntime(value, _) => ScriptError::Runtime(value.to_string()),
+        error => ScriptError::Runtime(error.to_string()),
+    })
+}
@@ -0,0 +1,8 @@
+#[derive(Debug, PartialEq)]
+pub enum ScriptError {
+    Compile(String),
+    Runtime(String),
+    /// The script ran longer than the time limit (in milliseconds).
+    TimedOut(u64),
+    Killed,
+}
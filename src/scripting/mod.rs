@@ -0,0 +1,5 @@
+pub mod engine;
+pub mod errors;
+pub mod registry;
+#[cfg(test)]
+mod tests;
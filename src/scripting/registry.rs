@@ -0,0 +1,52 @@
+use rhai::AST;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use super::{engine::sandboxed_engine, errors::ScriptError};
+
+/// Hex encoded SHA-256 digest of the source of a script, under which it is registered.
+pub fn script_hash(source: &str) -> String {
+    Sha256::digest(source.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Compiled scripts, by hash.
+#[derive(Default)]
+pub struct ScriptRegistry {
+    _scripts: HashMap<String, AST>,
+}
+
+impl ScriptRegistry {
+    pub fn new() -> Self {
+        ScriptRegistry::default()
+    }
+
+    /// Compiles a script and registers it, then returns its hash.
+    /// Loading the same script again keeps the compiled one.
+    pub fn load(&mut self, source: &str) -> Result<String, ScriptError> {
+        let hash = script_hash(source);
+        if !self._scripts.contains_key(&hash) {
+            let ast = sandboxed_engine()
+                .compile(source)
+                .map_err(|e| ScriptError::Compile(e.to_string()))?;
+            self._scripts.insert(hash.to_owned(), ast);
+        }
+        Ok(hash)
+    }
+
+    /// Gets a compiled script by its (case-insensitive) hash.
+    pub fn get(&self, hash: &str) -> Option<&AST> {
+        self._scripts.get(&hash.to_lowercase())
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.get(hash).is_some()
+    }
+
+    /// Removes all the scripts.
+    pub fn flush(&mut self) {
+        self._scripts.clear();
+    }
+}
@@ -0,0 +1,261 @@
+use rhai::Dynamic;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::{
+    engine::{kill_running_script, run_script},
+    errors::ScriptError,
+    registry::{script_hash, ScriptRegistry},
+};
+use crate::managers::manager::RusticManager;
+use crate::stores::types::ValueKind;
+use crate::utils::{
+    acl::UserConfig,
+    config::RusticConfig,
+    constants::{DEFAULT_SCRIPT_TIME_LIMIT_MS, DEFAULT_STORE_NAME},
+};
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn setup() -> (RusticManager, RusticConfig) {
+    let config = RusticConfig::default();
+    let mut manager = RusticManager::new();
+    manager.create_store(DEFAULT_STORE_NAME, None).unwrap();
+    manager.create_store("other_store", None).unwrap();
+    (manager, config)
+}
+
+/// Compiles and runs a script against the default store, as the default user.
+fn run(
+    manager: &mut RusticManager,
+    config: &RusticConfig,
+    source: &str,
+    keys: &[&str],
+    args: &[&str],
+) -> Result<Dynamic, ScriptError> {
+    let hash = manager.scripts_mut().load(source).unwrap();
+    let ast = manager.scripts().get(&hash).unwrap().clone();
+    run_script(
+        &ast,
+        manager,
+        config,
+        DEFAULT_STORE_NAME,
+        None,
+        strings(keys),
+        strings(args),
+    )
+}
+
+#[test]
+fn test_script_registry() {
+    let mut registry = ScriptRegistry::new();
+    let hash = registry.load("1 + 1").unwrap();
+    assert_eq!(hash, script_hash("1 + 1"));
+    assert_eq!(hash.len(), 64);
+    assert_eq!(registry.load("1 + 1").unwrap(), hash);
+    assert!(registry.contains(&hash));
+    assert!(registry.contains(&hash.to_uppercase()));
+    assert!(!registry.contains(&script_hash("1 + 2")));
+    assert!(matches!(registry.load("1 +"), Err(ScriptError::Compile(_))));
+
+    registry.flush();
+    assert!(!registry.contains(&hash));
+}
+
+#[test]
+fn test_run_script() {
+    let (mut manager, config) = setup();
+    let source = r#"
+        let count = store.incr(KEYS[0]);
+        if count <= parse_int(ARGV[0]) {
+            store.list_pushb(KEYS[1], ARGV[1]);
+            store.set_ttl(KEYS[1], 60000);
+        }
+        [count, store.list_size(KEYS[1])]
+    "#;
+    for expected in [[1, 1], [2, 2], [3, 2]] {
+        let result = run(
+            &mut manager,
+            &config,
+            source,
+            &["visits", "events"],
+            &["2", "visited"],
+        )
+        .unwrap();
+        let result: Vec<i64> = result
+            .into_array()
+            .unwrap()
+            .into_iter()
+            .map(|value| value.as_int().unwrap())
+            .collect();
+        assert_eq!(result, expected);
+    }
+    let store = manager.get_store(DEFAULT_STORE_NAME).unwrap();
+    assert_eq!(store.get_i64("visits").unwrap().unwrap(), 3);
+    assert_eq!(
+        store.get_list("events").unwrap().unwrap(),
+        vec!["visited", "visited"]
+    );
+    assert!(store.get_ttl("events").unwrap().unwrap() > 50000);
+
+    let source = r#"
+        store.hmap_insert("user:1", "name", "ada");
+        store.hmap_incr("user:1", "age", 36);
+        store.hset_add("tags", 42);
+        store.set("greeting", "hello", 60000);
+        [store.get_hmap("user:1").age, store.hset_contains("tags", "42"), store.kind("user:1"),
+         store.get("missing"), store.get_ttl("missing"), store.get_ttl("user:1"), store.keys("user:*")]
+    "#;
+    let result = run(&mut manager, &config, source, &[], &[]).unwrap();
+    assert_eq!(
+        format!("{:?}", result),
+        r#"["36", true, "hmap", (), -2, -1, ["user:1"]]"#
+    );
+
+    // Errors stop the script, and the writes already made are rolled back.
+    let result = run(
+        &mut manager,
+        &config,
+        r#"store.set("before", 1); store.incr("visits", 10); store.remove("user:1");
+           store.remove_ttl("events"); store.incr("events")"#,
+        &[],
+        &[],
+    );
+    assert!(matches!(result, Err(ScriptError::Runtime(e)) if e.starts_with("WRONGTYPE")));
+    let store = manager.get_store(DEFAULT_STORE_NAME).unwrap();
+    assert!(store.get_string("before").is_none());
+    assert_eq!(store.get_i64("visits").unwrap().unwrap(), 3);
+    assert_eq!(store.get_kind("user:1"), Some(ValueKind::HashMap));
+    assert!(store.get_ttl("events").unwrap().is_some());
+    assert!(matches!(
+        run(&mut manager, &config, r#"throw "boom""#, &[], &[]),
+        Err(ScriptError::Runtime(e)) if e == "boom"
+    ));
+    assert!(manager.get_store("other_store").is_some());
+}
+
+#[test]
+fn test_script_permissions() {
+    let (mut manager, mut config) = setup();
+    manager
+        .get_store_mut("other_store")
+        .unwrap()
+        .set_string("secret", "42".to_string(), None);
+
+    let source = r#"store.open_store("other_store").get("secret")"#;
+    assert!(matches!(
+        run(&mut manager, &config, source, &[], &[]),
+        Err(ScriptError::Runtime(e)) if e.contains("script_cross_store")
+    ));
+    config.script_cross_store = true;
+    assert_eq!(
+        run(&mut manager, &config, source, &[], &[])
+            .unwrap()
+            .into_string()
+            .unwrap(),
+        "42"
+    );
+
+    let mut user = UserConfig::default();
+    for rule in ["nopass", "+@write", "~user:*", "%default_store"] {
+        user.apply_rule(rule).unwrap();
+    }
+    config.users.push(user);
+    assert!(matches!(
+        run(&mut manager, &config, source, &[], &[]),
+        Err(ScriptError::Runtime(e)) if e.starts_with("NOPERM")
+    ));
+    assert!(matches!(
+        run(&mut manager, &config, r#"store.set("admin", 1)"#, &[], &[]),
+        Err(ScriptError::Runtime(e)) if e.starts_with("NOPERM")
+    ));
+    assert!(run(&mut manager, &config, r#"store.set("user:1", 1)"#, &[], &[]).is_ok());
+    manager
+        .get_store_mut(DEFAULT_STORE_NAME)
+        .unwrap()
+        .set_string("admin", "1".to_string(), None);
+    assert_eq!(
+        format!(
+            "{:?}",
+            run(&mut manager, &config, r#"store.keys("*")"#, &[], &[]).unwrap()
+        ),
+        r#"["user:1"]"#
+    );
+}
+
+#[test]
+fn test_script_limits() {
+    let (mut manager, mut config) = setup();
+    assert_eq!(
+        config.script_time_limit_ms,
+        Some(DEFAULT_SCRIPT_TIME_LIMIT_MS)
+    );
+    config.script_time_limit_ms = Some(50);
+    assert_eq!(
+        run(&mut manager, &config, "loop {}", &[], &[]).unwrap_err(),
+        ScriptError::TimedOut(50)
+    );
+
+    // Only the users configured when the script started are checked by the killer,
+    // which tells this script apart from the ones other tests may be running.
+    config.script_time_limit_ms = None;
+    let mut user = UserConfig::default();
+    for rule in ["nopass", "allkeys"] {
+        user.apply_rule(rule).unwrap();
+    }
+    config.users.push(user);
+    config.users.push(UserConfig::new("killer"));
+    let done = Arc::new(AtomicBool::new(false));
+    let killer = {
+        let done = Arc::clone(&done);
+        thread::spawn(move || {
+            while !done.load(Ordering::Relaxed) {
+                kill_running_script(|users| match users.iter().any(|u| u.name == "killer") {
+                    true => Ok(()),
+                    false => Err(()),
+                });
+                thread::sleep(Duration::from_millis(10));
+            }
+        })
+    };
+    let result = run(
+        &mut manager,
+        &config,
+        r#"store.set("started", 1); loop {}"#,
+        &[],
+        &[],
+    );
+    done.store(true, Ordering::Relaxed);
+    killer.join().unwrap();
+    assert_eq!(result.unwrap_err(), ScriptError::Killed);
+    assert!(manager
+        .get_store(DEFAULT_STORE_NAME)
+        .unwrap()
+        .get_string("started")
+        .is_none());
+}
+
+#[test]
+fn test_script_sandbox() {
+    let (mut manager, config) = setup();
+    assert!(matches!(
+        run(&mut manager, &config, r#"import "std" as std; 1"#, &[], &[]),
+        Err(ScriptError::Runtime(e)) if e.contains("std")
+    ));
+    assert!(matches!(
+        run(&mut manager, &config, r#"let s = "x"; loop { s += s; }"#, &[], &[]),
+        Err(ScriptError::Runtime(e)) if e.contains("too large")
+    ));
+    assert!(matches!(
+        run(&mut manager, &config, "fn f(n) { f(n + 1) } f(0)", &[], &[]),
+        Err(ScriptError::Runtime(_))
+    ));
+}
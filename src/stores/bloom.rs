@@ -0,0 +1,138 @@
+use super::hashing::double_hash;
+
+/// Each new layer has a false positive rate this many times lower than the previous one,
+/// so the overall rate stays below the requested one however many layers are added.
+const TIGHTENING_RATIO: f64 = 0.5;
+/// Layers can't grow past 512MB (2^32 bits).
+const MAX_LAYER_BITS: u64 = 1 << 32;
+
+#[derive(Clone, Debug, PartialEq)]
+struct BloomLayer {
+    bits: Vec<u64>,
+    bit_count: u64,
+    hash_count: u32,
+    capacity: u64,
+    /// Number of elements added to the layer.
+    count: u64,
+}
+
+impl BloomLayer {
+    /// Returns None if the layer would need more than `MAX_LAYER_BITS` bits.
+    fn new(capacity: u64, error_rate: f64) -> Option<Self> {
+        let ln2 = std::f64::consts::LN_2;
+        let bit_count = (-(capacity as f64) * error_rate.ln() / (ln2 * ln2)).ceil();
+        if bit_count > MAX_LAYER_BITS as f64 {
+            return None;
+        }
+        let bit_count = (bit_count as u64).max(64);
+        Some(BloomLayer {
+            bits: vec![0; bit_count.div_ceil(64) as usize],
+            bit_count,
+            hash_count: (-error_rate.log2()).ceil().max(1.0) as u32,
+            capacity,
+            count: 0,
+        })
+    }
+
+    fn _bit_indexes(&self, hashes: (u64, u64)) -> impl Iterator<Item = u64> + '_ {
+        (0..self.hash_count as u64)
+            .map(move |idx| hashes.0.wrapping_add(idx.wrapping_mul(hashes.1)) % self.bit_count)
+    }
+
+    fn contains(&self, hashes: (u64, u64)) -> bool {
+        self._bit_indexes(hashes)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    fn insert(&mut self, hashes: (u64, u64)) {
+        let bit_indexes: Vec<u64> = self._bit_indexes(hashes).collect();
+        for bit in bit_indexes {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        self.count += 1;
+    }
+}
+
+/// Scalable Bloom filter: a set membership test with false positives but no false negatives.
+/// Once a layer holds as many elements as it was sized for, a larger one is added.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BloomFilter {
+    capacity: u64,
+    error_rate: f64,
+    expansion: u32,
+    layers: Vec<BloomLayer>,
+}
+
+impl BloomFilter {
+    /// Returns None if the capacity or expansion is 0, the error rate isn't between 0 and 1,
+    /// or the first layer would need more than `MAX_LAYER_BITS` bits.
+    /// Arguments:
+    /// * `capacity` - number of elements the first layer is sized for.
+    /// * `error_rate` - false positive rate of the whole filter.
+    /// * `expansion` - each new layer is sized for this many times the elements of the previous one.
+    pub fn new(capacity: u64, error_rate: f64, expansion: u32) -> Option<Self> {
+        if capacity == 0 || expansion == 0 || !(error_rate > 0.0 && error_rate < 1.0) {
+            return None;
+        }
+        Some(BloomFilter {
+            capacity,
+            error_rate,
+            expansion,
+            layers: vec![BloomLayer::new(capacity, error_rate * TIGHTENING_RATIO)?],
+        })
+    }
+
+    /// Size (in bytes) of the bits of all the layers.
+    pub fn size(&self) -> usize {
+        self.layers.iter().map(|layer| layer.bits.len() * 8).sum()
+    }
+
+    pub fn contains(&self, element: &str) -> bool {
+        let hashes = double_hash(element);
+        self.layers.iter().any(|layer| layer.contains(hashes))
+    }
+
+    /// Adds an element, and returns false if it seemed already present.
+    pub fn add(&mut self, element: &str) -> bool {
+        let hashes = double_hash(element);
+        if self.layers.iter().any(|layer| layer.contains(hashes)) {
+            return false;
+        }
+        let last_layer = self.layers.last().unwrap();
+        if last_layer.count >= last_layer.capacity {
+            let capacity = last_layer.capacity.saturating_mul(self.expansion as u64);
+            let error_rate = self.error_rate * TIGHTENING_RATIO.powi(self.layers.len() as i32 + 1);
+            // Past the largest layer, the last one keeps filling up (and its false positive
+            // rate keeps rising).
+            if let Some(layer) = BloomLayer::new(capacity, error_rate) {
+                self.layers.push(layer);
+            }
+        }
+        self.layers.last_mut().unwrap().insert(hashes);
+        true
+    }
+
+    /// Merges another filter built with the same parameters in this one, which then contains
+    /// the elements of both. Returns false (leaving the filter untouched) if the parameters differ.
+    pub fn merge(&mut self, other: &BloomFilter) -> bool {
+        if (self.capacity, self.error_rate, self.expansion)
+            != (other.capacity, other.error_rate, other.expansion)
+        {
+            return false;
+        }
+        // Layers of the same rank have the same size, as they come from the same parameters.
+        for (idx, other_layer) in other.layers.iter().enumerate() {
+            match self.layers.get_mut(idx) {
+                Some(layer) => {
+                    for (word, other_word) in layer.bits.iter_mut().zip(other_layer.bits.iter()) {
+                        *word |= other_word;
+                    }
+                    // Elements of both layers may overlap, this is an upper bound.
+                    layer.count = (layer.count + other_layer.count).min(layer.capacity);
+                }
+                None => self.layers.push(other_layer.to_owned()),
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,94 @@
+use super::hashing::double_hash;
+
+/// Sketches can't grow past 512MB (2^26 counters).
+const MAX_COUNTERS: usize = 1 << 26;
+
+/// Count-Min sketch: estimates how many times elements were counted, never underestimating.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CountMinSketch {
+    width: usize,
+    depth: usize,
+    /// `depth` rows of `width` counters.
+    counters: Vec<u64>,
+}
+
+impl CountMinSketch {
+    /// Returns None if the width or depth is 0, or if the sketch would hold more than
+    /// `MAX_COUNTERS` counters.
+    pub fn new(width: usize, depth: usize) -> Option<Self> {
+        if width == 0 || depth == 0 {
+            return None;
+        }
+        let counter_count = width
+            .checked_mul(depth)
+            .filter(|count| *count <= MAX_COUNTERS)?;
+        Some(CountMinSketch {
+            width,
+            depth,
+            counters: vec![0; counter_count],
+        })
+    }
+
+    /// Sizes the sketch so estimates overshoot by at most `error` times the total of the
+    /// counts, with a probability of at least `1 - probability`.
+    /// Returns None if the error or probability isn't between 0 and 1, or if they are too small
+    /// for the sketch to fit in `MAX_COUNTERS` counters.
+    pub fn with_error(error: f64, probability: f64) -> Option<Self> {
+        if !(error > 0.0 && error < 1.0 && probability > 0.0 && probability < 1.0) {
+            return None;
+        }
+        let width = (std::f64::consts::E / error).ceil() as usize;
+        let depth = (1.0 / probability).ln().ceil().max(1.0) as usize;
+        CountMinSketch::new(width, depth)
+    }
+
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.depth)
+    }
+
+    /// Size (in bytes) of the counters.
+    pub fn size(&self) -> usize {
+        self.counters.len() * std::mem::size_of::<u64>()
+    }
+
+    fn _counter_indexes(&self, element: &str) -> impl Iterator<Item = usize> + '_ {
+        let (first, second) = double_hash(element);
+        (0..self.depth).map(move |row| {
+            let column = first.wrapping_add((row as u64).wrapping_mul(second)) % self.width as u64;
+            row * self.width + column as usize
+        })
+    }
+
+    /// Counts an element `increment` more times, and returns its new estimate.
+    pub fn increment(&mut self, element: &str, increment: u64) -> u64 {
+        let indexes: Vec<usize> = self._counter_indexes(element).collect();
+        indexes
+            .into_iter()
+            .map(|idx| {
+                self.counters[idx] = self.counters[idx].saturating_add(increment);
+                self.counters[idx]
+            })
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Estimated number of times an element was counted.
+    pub fn query(&self, element: &str) -> u64 {
+        self._counter_indexes(element)
+            .map(|idx| self.counters[idx])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Adds the counts of another sketch of the same dimensions to this one.
+    /// Returns false (leaving the sketch untouched) if the dimensions differ.
+    pub fn merge(&mut self, other: &CountMinSketch) -> bool {
+        if self.dimensions() != other.dimensions() {
+            return false;
+        }
+        for (counter, other_counter) in self.counters.iter_mut().zip(other.counters.iter()) {
+            *counter = counter.saturating_add(*other_counter);
+        }
+        true
+    }
+}
@@ -0,0 +1,201 @@
+use rand::Rng;
+
+use super::hashing::{hash, mix};
+
+const BUCKET_SIZE: usize = 4;
+/// Number of fingerprints moved around before giving up and adding a layer.
+const MAX_KICKS: usize = 500;
+/// Empty slots hold this fingerprint, actual fingerprints are never 0.
+const EMPTY: u16 = 0;
+/// Filters can't grow past 512MB (2^26 buckets, all layers together).
+const MAX_BUCKET_COUNT: usize = 1 << 26;
+/// Filters can't grow past this many layers.
+const MAX_LAYERS: usize = 8;
+/// An element can't be added more times than both its buckets can hold.
+const MAX_COPIES: usize = 2 * BUCKET_SIZE;
+
+type Bucket = [u16; BUCKET_SIZE];
+
+/// Why a Cuckoo filter can't take more elements.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CuckooError {
+    /// Filters of different sizes can't be merged.
+    Incompatible,
+    /// The element was already added `MAX_COPIES` times, or there is no room left for it and
+    /// no layer can be added.
+    Full,
+}
+
+/// Cuckoo filter: a set membership test with false positives but no false negatives,
+/// which unlike a Bloom filter supports deleting elements.
+/// Each element is stored as a 16-bit fingerprint in one of two buckets. Once the buckets
+/// of an element are full in every layer, a layer of the same size is added, up to
+/// `MAX_LAYERS` layers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CuckooFilter {
+    bucket_count: usize,
+    layers: Vec<Vec<Bucket>>,
+}
+
+impl CuckooFilter {
+    /// Returns None if the capacity is 0 or a layer would hold more than `MAX_BUCKET_COUNT`
+    /// buckets.
+    /// Arguments:
+    /// * `capacity` - number of elements a layer is sized for.
+    pub fn new(capacity: u64) -> Option<Self> {
+        if capacity == 0 {
+            return None;
+        }
+        let bucket_count = usize::try_from(capacity.div_ceil(BUCKET_SIZE as u64))
+            .ok()?
+            .checked_next_power_of_two()
+            .filter(|count| *count <= MAX_BUCKET_COUNT)?;
+        Some(CuckooFilter {
+            bucket_count,
+            layers: vec![vec![[EMPTY; BUCKET_SIZE]; bucket_count]],
+        })
+    }
+
+    /// Size (in bytes) of the buckets of all the layers.
+    pub fn size(&self) -> usize {
+        self.layers.len() * self.bucket_count * std::mem::size_of::<Bucket>()
+    }
+
+    /// Fingerprint and first bucket of an element.
+    fn _locate(&self, element: &str) -> (u16, usize) {
+        let hash = hash(element);
+        let fingerprint = ((hash >> 48) as u16).max(1);
+        (fingerprint, hash as usize & (self.bucket_count - 1))
+    }
+
+    /// The other bucket of a fingerprint, the alternate of the alternate being the first one.
+    fn _alternate(&self, bucket: usize, fingerprint: u16) -> usize {
+        (bucket ^ mix(fingerprint as u64) as usize) & (self.bucket_count - 1)
+    }
+
+    fn _count_in(&self, fingerprint: u16, buckets: [usize; 2]) -> usize {
+        self.layers
+            .iter()
+            .flat_map(|layer| {
+                // Both buckets are the same one for some fingerprints.
+                let alternate = (buckets[0] != buckets[1]).then(|| &layer[buckets[1]]);
+                layer[buckets[0]]
+                    .iter()
+                    .chain(alternate.into_iter().flatten())
+            })
+            .filter(|slot| **slot == fingerprint)
+            .count()
+    }
+
+    fn _can_grow(&self) -> bool {
+        self.layers.len() < MAX_LAYERS
+            && (self.layers.len() + 1) * self.bucket_count <= MAX_BUCKET_COUNT
+    }
+
+    /// Stores a fingerprint in one of its buckets, moving other fingerprints to their
+    /// alternate bucket to make room if needed. Fails (leaving the filter untouched) if there
+    /// is no room left and no layer can be added.
+    fn _insert(&mut self, fingerprint: u16, bucket: usize) -> Result<(), CuckooError> {
+        let buckets = [bucket, self._alternate(bucket, fingerprint)];
+        for layer in self.layers.iter_mut() {
+            for bucket in buckets {
+                if let Some(slot) = layer[bucket].iter_mut().find(|slot| **slot == EMPTY) {
+                    *slot = fingerprint;
+                    return Ok(());
+                }
+            }
+        }
+
+        let mut rng = rand::thread_rng();
+        let (mut fingerprint, mut bucket) = (fingerprint, buckets[rng.gen_range(0..2)]);
+        let last_layer = self.layers.len() - 1;
+        let mut kicks = Vec::with_capacity(MAX_KICKS);
+        for _ in 0..MAX_KICKS {
+            let slot = rng.gen_range(0..BUCKET_SIZE);
+            fingerprint =
+                std::mem::replace(&mut self.layers[last_layer][bucket][slot], fingerprint);
+            kicks.push((bucket, slot));
+            bucket = self._alternate(bucket, fingerprint);
+            let layer = &mut self.layers[last_layer];
+            if let Some(slot) = layer[bucket].iter_mut().find(|slot| **slot == EMPTY) {
+                *slot = fingerprint;
+                return Ok(());
+            }
+        }
+        if self._can_grow() {
+            // The fingerprint left without a bucket goes to a new layer.
+            let mut layer = vec![[EMPTY; BUCKET_SIZE]; self.bucket_count];
+            layer[bucket][0] = fingerprint;
+            self.layers.push(layer);
+            return Ok(());
+        }
+        // Put the moved fingerprints back, the last one moved being the one left in hand.
+        for (bucket, slot) in kicks.into_iter().rev() {
+            fingerprint =
+                std::mem::replace(&mut self.layers[last_layer][bucket][slot], fingerprint);
+        }
+        Err(CuckooError::Full)
+    }
+
+    /// Adds an element, even if it is already present.
+    /// Fails (adding nothing) if the filter is full, see `CuckooError::Full`.
+    pub fn add(&mut self, element: &str) -> Result<(), CuckooError> {
+        if self.count(element) >= MAX_COPIES {
+            return Err(CuckooError::Full);
+        }
+        let (fingerprint, bucket) = self._locate(element);
+        self._insert(fingerprint, bucket)
+    }
+
+    /// Adds an element, and returns false if it seemed already present.
+    pub fn add_if_absent(&mut self, element: &str) -> Result<bool, CuckooError> {
+        if self.contains(element) {
+            return Ok(false);
+        }
+        self.add(element)?;
+        Ok(true)
+    }
+
+    pub fn contains(&self, element: &str) -> bool {
+        self.count(element) > 0
+    }
+
+    /// Estimated number of times an element was added, never less than the actual number.
+    pub fn count(&self, element: &str) -> usize {
+        let (fingerprint, bucket) = self._locate(element);
+        self._count_in(fingerprint, [bucket, self._alternate(bucket, fingerprint)])
+    }
+
+    /// Removes one copy of an element, and returns whether it was present.
+    /// Removing an element that was never added may remove another one with the same fingerprint.
+    pub fn remove(&mut self, element: &str) -> bool {
+        let (fingerprint, bucket) = self._locate(element);
+        let buckets = [bucket, self._alternate(bucket, fingerprint)];
+        for layer in self.layers.iter_mut().rev() {
+            for bucket in buckets {
+                if let Some(slot) = layer[bucket].iter_mut().find(|slot| **slot == fingerprint) {
+                    *slot = EMPTY;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Adds the elements of another filter of the same size to this one.
+    /// Fails (leaving the filter untouched) if the sizes differ, and (leaving part of the
+    /// elements of the other filter in this one) if it gets full.
+    pub fn merge(&mut self, other: &CuckooFilter) -> Result<(), CuckooError> {
+        if self.bucket_count != other.bucket_count {
+            return Err(CuckooError::Incompatible);
+        }
+        for layer in other.layers.iter() {
+            for (bucket, slots) in layer.iter().enumerate() {
+                for fingerprint in slots.iter().filter(|slot| **slot != EMPTY) {
+                    self._insert(*fingerprint, bucket)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::{
+        Bound::{Excluded, Unbounded},
+        Deref,
+    },
+};
+
+use super::hashing;
+
+/// Stable hash ordering items for cursor based iteration.
+/// Never returns 0, as a cursor of 0 means "start" (and "done" once returned).
+pub fn cursor_hash(item: &str) -> u64 {
+    hashing::hash(item).max(1)
+}
+
+/// Next cursor and items of a batch returned by a cursor based iteration.
+/// Items present for the whole iteration are returned exactly once, items added or
+/// removed in the meantime may or may not be returned.
+pub type ScanBatch<T> = (u64, Vec<T>);
+
+/// Items of a collection grouped by `cursor_hash`, kept next to the collection so that a batch
+/// of a cursor based iteration only looks at the items it returns.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CursorOrder {
+    buckets: BTreeMap<u64, Vec<String>>,
+}
+
+impl CursorOrder {
+    pub fn insert(&mut self, item: &str) {
+        let items = self.buckets.entry(cursor_hash(item)).or_default();
+        if !items.iter().any(|other| other == item) {
+            items.push(item.to_owned());
+        }
+    }
+
+    pub fn remove(&mut self, item: &str) {
+        let hash = cursor_hash(item);
+        if let Some(items) = self.buckets.get_mut(&hash) {
+            items.retain(|other| other != item);
+            if items.is_empty() {
+                self.buckets.remove(&hash);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+    }
+
+    /// Gets the items whose hash follows the cursor, in the order of their hash, along with
+    /// the next cursor (0 once the iteration is complete). Returns about `count` items,
+    /// items sharing the hash of the last one are always returned together.
+    pub fn scan(&self, cursor: u64, count: usize) -> ScanBatch<&String> {
+        let mut batch = vec![];
+        let mut buckets = self.buckets.range((Excluded(cursor), Unbounded));
+        while let Some((hash, items)) = buckets.next() {
+            batch.extend(items);
+            if batch.len() >= count.max(1) {
+                let next_cursor = match buckets.next() {
+                    Some(_) => *hash,
+                    None => 0,
+                };
+                return (next_cursor, batch);
+            }
+        }
+        (0, batch)
+    }
+}
+
+/// Members of a set, which can be iterated over with a cursor.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CursorSet {
+    members: HashSet<String>,
+    order: CursorOrder,
+    /// Bytes held by the members, see `size`.
+    size: usize,
+}
+
+impl Deref for CursorSet {
+    type Target = HashSet<String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.members
+    }
+}
+
+impl FromIterator<String> for CursorSet {
+    fn from_iter<I: IntoIterator<Item = String>>(members: I) -> Self {
+        let mut set = CursorSet::default();
+        for member in members {
+            set.insert(member);
+        }
+        set
+    }
+}
+
+impl CursorSet {
+    /// Adds a member, and returns whether it wasn't in the set yet.
+    pub fn insert(&mut self, member: String) -> bool {
+        if self.members.contains(&member) {
+            return false;
+        }
+        self.order.insert(&member);
+        self.size += 2 * member.len();
+        self.members.insert(member)
+    }
+
+    /// Removes a member, and returns whether it was in the set.
+    pub fn remove(&mut self, member: &str) -> bool {
+        if !self.members.remove(member) {
+            return false;
+        }
+        self.order.remove(member);
+        self.size -= 2 * member.len();
+        true
+    }
+
+    /// Rough estimate (in bytes) of the memory held by the members, which are held twice,
+    /// the second time in their cursor order.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn scan(&self, cursor: u64, count: usize) -> ScanBatch<&String> {
+        self.order.scan(cursor, count)
+    }
+}
+
+/// Fields and values of a hmap, whose fields can be iterated over with a cursor.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CursorMap {
+    fields: HashMap<String, String>,
+    order: CursorOrder,
+    /// Bytes held by the fields and values, see `size`.
+    size: usize,
+}
+
+impl Deref for CursorMap {
+    type Target = HashMap<String, String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.fields
+    }
+}
+
+impl FromIterator<(String, String)> for CursorMap {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(fields: I) -> Self {
+        let mut map = CursorMap::default();
+        for (field, value) in fields {
+            map.insert(field, value);
+        }
+        map
+    }
+}
+
+impl CursorMap {
+    /// Sets the value of a field, and returns its previous value.
+    pub fn insert(&mut self, field: String, value: String) -> Option<String> {
+        if !self.fields.contains_key(&field) {
+            self.order.insert(&field);
+            self.size += 2 * field.len();
+        }
+        self.size += value.len();
+        let previous = self.fields.insert(field, value);
+        if let Some(previous) = previous.as_ref() {
+            self.size -= previous.len();
+        }
+        previous
+    }
+
+    pub fn remove_entry(&mut self, field: &str) -> Option<(String, String)> {
+        let (field, value) = self.fields.remove_entry(field)?;
+        self.order.remove(&field);
+        self.size -= 2 * field.len() + value.len();
+        Some((field, value))
+    }
+
+    pub fn remove(&mut self, field: &str) -> Option<String> {
+        self.remove_entry(field).map(|(_, value)| value)
+    }
+
+    /// Rough estimate (in bytes) of the memory held by the fields and values, the fields being
+    /// held twice, the second time in their cursor order.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Gets the (field, value) pairs of a batch, see `CursorOrder::scan`.
+    pub fn scan(&self, cursor: u64, count: usize) -> ScanBatch<(&String, &String)> {
+        let (next_cursor, fields) = self.order.scan(cursor, count);
+        let items = fields
+            .into_iter()
+            .filter_map(|field| self.fields.get_key_value(field))
+            .collect();
+        (next_cursor, items)
+    }
+}
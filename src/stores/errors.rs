@@ -1,5 +1,5 @@
 use std::{
-    num::{ParseIntError, TryFromIntError},
+    num::{ParseFloatError, ParseIntError},
     string::FromUtf8Error,
 };
 
@@ -7,7 +7,34 @@ use std::{
 pub enum TypeConversionError {
     ParseIntError(ParseIntError),
     FromUtf8Error(FromUtf8Error),
-    TryFromIntError(TryFromIntError),
+    ParseFloatError(ParseFloatError),
+    /// The result of an integer operation doesn't fit in an i64.
+    IntegerOverflow,
+    /// The result of an operation on a bounded counter is out of its bounds.
+    CounterOutOfBounds,
+    /// The lower bound of a bounded counter is above its upper bound.
+    InvalidCounterBounds,
+    /// The bit offset is past the largest bitmap allowed.
+    BitOffsetOutOfRange,
+    /// The String would be longer than the largest one allowed.
+    StringTooLong,
+    /// The result of a float operation is NaN or infinite.
+    NonFiniteFloat,
+    /// The longitude or latitude is out of the range that can be indexed.
+    InvalidCoordinates,
+    /// The JSONPath can't be parsed.
+    InvalidJsonPath,
+    /// The parameters of a probabilistic structure are out of range.
+    InvalidSketchParameters,
+    /// Probabilistic structures built with different parameters can't be merged.
+    IncompatibleSketches,
+    /// The Cuckoo filter is full, see `CuckooError::Full`.
+    FilterFull,
+    /// The dimension of the vector index is 0, or the vector doesn't have the dimension of the
+    /// index, has non finite values, or is 0 with the cosine metric.
+    InvalidVector,
+    /// The full-text query can't be parsed.
+    InvalidTextQuery,
     // Add other type cast error variants as needed
 }
 
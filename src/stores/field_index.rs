@@ -0,0 +1,211 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap},
+};
+
+/// Separator of the tags of a field value, e.g. `"books,music"`.
+pub const TAG_SEPARATOR: char = ',';
+
+/// Condition on the value of the indexed field.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IndexQuery {
+    /// The value is exactly this string.
+    Exact(String),
+    /// The value is a number between min and max (both included).
+    Range(f64, f64),
+    /// One of the tags of the value is one of these tags (compared case-insensitively).
+    Tags(Vec<String>),
+}
+
+/// What the keys returned by an index query are sorted by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IndexSort {
+    Key,
+    /// Numbers come first (by value), then other values (lexically).
+    Value,
+}
+
+/// Keys returned by an index query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexPage {
+    /// Number of keys matching the query, whatever the offset and limit.
+    pub total: usize,
+    pub keys: Vec<String>,
+}
+
+/// Finite number ordered by `total_cmp`, so it can be used as a BTreeMap key.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct IndexedNumber(f64);
+
+impl Eq for IndexedNumber {}
+
+impl PartialOrd for IndexedNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for IndexedNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Parses a field value as a number, -0 is the same as 0 and non-finite numbers aren't numbers.
+fn parse_number(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+        .map(|number| number + 0.0)
+}
+
+fn parse_tags(value: &str) -> BTreeSet<String> {
+    value
+        .split(TAG_SEPARATOR)
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// Orders field values the way `IndexSort::Value` does.
+pub fn compare_values(a: &str, b: &str) -> Ordering {
+    match (parse_number(a), parse_number(b)) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+fn remove_from(keys_by_value: &mut BTreeSet<String>, key: &str) -> bool {
+    keys_by_value.remove(key);
+    keys_by_value.is_empty()
+}
+
+/// Secondary index over one field of the hmaps whose key starts with a prefix.
+/// A value is looked up as is, as a number and as a list of tags, so every query works
+/// on every index.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FieldIndex {
+    pub prefix: String,
+    pub field: String,
+    /// Indexed value of each key.
+    values: HashMap<String, String>,
+    exact: HashMap<String, BTreeSet<String>>,
+    numbers: BTreeMap<IndexedNumber, BTreeSet<String>>,
+    tags: HashMap<String, BTreeSet<String>>,
+    /// Rough estimate (in bytes) of the memory held by the index, kept up to date.
+    size: usize,
+}
+
+/// Rough estimate (in bytes) of the memory the index holds for a value.
+fn value_size(key: &str, value: &str) -> usize {
+    3 * key.len() + 2 * value.len()
+}
+
+impl FieldIndex {
+    pub fn new(prefix: &str, field: &str) -> Self {
+        FieldIndex {
+            prefix: prefix.to_owned(),
+            field: field.to_owned(),
+            ..FieldIndex::default()
+        }
+    }
+
+    pub fn covers(&self, key: &str) -> bool {
+        key.starts_with(&self.prefix)
+    }
+
+    /// Gets the indexed value of a key.
+    pub fn value(&self, key: &str) -> Option<&String> {
+        self.values.get(key)
+    }
+
+    /// Sets (or removes, with None) the indexed value of a key.
+    pub fn update(&mut self, key: &str, value: Option<&str>) {
+        if self.values.get(key).map(|old| old.as_str()) == value {
+            return;
+        }
+        if let Some(old) = self.values.remove(key) {
+            self.size -= value_size(key, &old);
+            if let Some(keys) = self.exact.get_mut(&old) {
+                if remove_from(keys, key) {
+                    self.exact.remove(&old);
+                }
+            }
+            if let Some(number) = parse_number(&old).map(IndexedNumber) {
+                if let Some(keys) = self.numbers.get_mut(&number) {
+                    if remove_from(keys, key) {
+                        self.numbers.remove(&number);
+                    }
+                }
+            }
+            for tag in parse_tags(&old) {
+                if let Some(keys) = self.tags.get_mut(&tag) {
+                    if remove_from(keys, key) {
+                        self.tags.remove(&tag);
+                    }
+                }
+            }
+        }
+        let Some(value) = value else {
+            return;
+        };
+        self.size += value_size(key, value);
+        self.values.insert(key.to_owned(), value.to_owned());
+        self.exact
+            .entry(value.to_owned())
+            .or_default()
+            .insert(key.to_owned());
+        if let Some(number) = parse_number(value) {
+            self.numbers
+                .entry(IndexedNumber(number))
+                .or_default()
+                .insert(key.to_owned());
+        }
+        for tag in parse_tags(value) {
+            self.tags.entry(tag).or_default().insert(key.to_owned());
+        }
+    }
+
+    /// Forgets every key, the index stays declared.
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.exact.clear();
+        self.numbers.clear();
+        self.tags.clear();
+        self.size = 0;
+    }
+
+    /// Gets the keys whose indexed value matches the query, sorted by key.
+    pub fn query(&self, query: &IndexQuery) -> BTreeSet<&String> {
+        match query {
+            IndexQuery::Exact(value) => self
+                .exact
+                .get(value)
+                .map(|keys| keys.iter().collect())
+                .unwrap_or_default(),
+            IndexQuery::Range(min, max) => {
+                if min.is_nan() || max.is_nan() || min > max {
+                    return BTreeSet::new();
+                }
+                self.numbers
+                    .range(IndexedNumber(min + 0.0)..=IndexedNumber(max + 0.0))
+                    .flat_map(|(_, keys)| keys.iter())
+                    .collect()
+            }
+            IndexQuery::Tags(tags) => tags
+                .iter()
+                .filter_map(|tag| self.tags.get(&tag.trim().to_lowercase()))
+                .flat_map(|keys| keys.iter())
+                .collect(),
+        }
+    }
+
+    /// Rough estimate (in bytes) of the memory held by the index.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::{BTreeSet, HashMap};
+
+/// Bits used for each coordinate of a geohash, the geohash itself taking 52 bits.
+const GEOHASH_STEP: u32 = 26;
+const LONGITUDE_RANGE: (f64, f64) = (-180.0, 180.0);
+/// Latitudes are limited to the range of the Web Mercator projection.
+const LATITUDE_RANGE: (f64, f64) = (-85.05112878, 85.05112878);
+const EARTH_RADIUS_IN_METERS: f64 = 6372797.560856;
+
+/// Longitude and latitude, in degrees.
+pub type GeoPosition = (f64, f64);
+
+/// Unit of a distance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GeoUnit {
+    Meters,
+    Kilometers,
+    Miles,
+    Feet,
+}
+
+impl GeoUnit {
+    /// Length of one unit, in meters.
+    pub fn meters(&self) -> f64 {
+        match self {
+            GeoUnit::Meters => 1.0,
+            GeoUnit::Kilometers => 1000.0,
+            GeoUnit::Miles => 1609.34,
+            GeoUnit::Feet => 0.3048,
+        }
+    }
+}
+
+/// Where a search is centered.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GeoCenter {
+    Member(String),
+    /// Longitude and latitude.
+    Point(f64, f64),
+}
+
+/// Area of a search, in the unit of the search.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GeoShape {
+    Radius(f64),
+    /// Width and height.
+    Box(f64, f64),
+}
+
+/// Order of the results of a search, by distance to the center.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GeoSort {
+    Ascending,
+    Descending,
+}
+
+/// A member found by a search.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeoMatch {
+    pub member: String,
+    /// Distance to the center, in the unit of the search.
+    pub distance: f64,
+    pub longitude: f64,
+    pub latitude: f64,
+}
+
+/// Check whether a point can be indexed.
+pub fn is_valid_position(longitude: f64, latitude: f64) -> bool {
+    (LONGITUDE_RANGE.0..=LONGITUDE_RANGE.1).contains(&longitude)
+        && (LATITUDE_RANGE.0..=LATITUDE_RANGE.1).contains(&latitude)
+}
+
+/// Great-circle distance (in meters) between two points, given as (longitude, latitude).
+pub fn distance(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lon1, lat1) = (from.0.to_radians(), from.1.to_radians());
+    let (lon2, lat2) = (to.0.to_radians(), to.1.to_radians());
+    let u = ((lat2 - lat1) / 2.0).sin();
+    let v = ((lon2 - lon1) / 2.0).sin();
+    2.0 * EARTH_RADIUS_IN_METERS * (u * u + lat1.cos() * lat2.cos() * v * v).sqrt().asin()
+}
+
+fn _cell_index(value: f64, range: (f64, f64)) -> u64 {
+    let cells = (1u64 << GEOHASH_STEP) as f64;
+    (((value - range.0) / (range.1 - range.0) * cells) as u64).min((1 << GEOHASH_STEP) - 1)
+}
+
+/// Spreads the bits of a 32-bit value to the even bits of a 64-bit value.
+fn _spread(value: u64) -> u64 {
+    let mut value = value & 0xFFFFFFFF;
+    value = (value | (value << 16)) & 0x0000FFFF0000FFFF;
+    value = (value | (value << 8)) & 0x00FF00FF00FF00FF;
+    value = (value | (value << 4)) & 0x0F0F0F0F0F0F0F0F;
+    value = (value | (value << 2)) & 0x3333333333333333;
+    (value | (value << 1)) & 0x5555555555555555
+}
+
+/// Gathers the even bits of a 64-bit value.
+fn _squash(value: u64) -> u64 {
+    let mut value = value & 0x5555555555555555;
+    value = (value | (value >> 1)) & 0x3333333333333333;
+    value = (value | (value >> 2)) & 0x0F0F0F0F0F0F0F0F;
+    value = (value | (value >> 4)) & 0x00FF00FF00FF00FF;
+    value = (value | (value >> 8)) & 0x0000FFFF0000FFFF;
+    (value | (value >> 16)) & 0xFFFFFFFF
+}
+
+/// Interleaves the cell indexes, longitude bits first.
+fn _interleave(longitude_index: u64, latitude_index: u64) -> u64 {
+    _spread(latitude_index) | (_spread(longitude_index) << 1)
+}
+
+/// 52-bit geohash of a point, used as its score.
+pub fn encode(longitude: f64, latitude: f64) -> u64 {
+    _interleave(
+        _cell_index(longitude, LONGITUDE_RANGE),
+        _cell_index(latitude, LATITUDE_RANGE),
+    )
+}
+
+/// Center of the cell of a geohash, as (longitude, latitude).
+pub fn decode(geohash: u64) -> (f64, f64) {
+    let cells = (1u64 << GEOHASH_STEP) as f64;
+    let center = |index: u64, range: (f64, f64)| {
+        range.0 + (index as f64 + 0.5) / cells * (range.1 - range.0)
+    };
+    (
+        center(_squash(geohash >> 1), LONGITUDE_RANGE),
+        center(_squash(geohash), LATITUDE_RANGE),
+    )
+}
+
+/// Ranges of geohashes covering every point within `radius` meters of the center: the cell
+/// containing the center and its 8 neighbours, at a precision where cells are larger than
+/// the radius.
+fn _covering_ranges(center: (f64, f64), radius: f64) -> Vec<(u64, u64)> {
+    let (longitude, latitude) = center;
+    let cell_height =
+        |step: u32| (LATITUDE_RANGE.1 - LATITUDE_RANGE.0) / (1u64 << step) as f64 * 111_320.0;
+    // Cells shrink with the latitude, use the latitude of the edge closest to a pole.
+    let widest_latitude = (latitude.abs().to_radians() + radius / EARTH_RADIUS_IN_METERS)
+        .min(std::f64::consts::FRAC_PI_2);
+    let cell_width = |step: u32| {
+        (LONGITUDE_RANGE.1 - LONGITUDE_RANGE.0) / (1u64 << step) as f64
+            * 111_320.0
+            * widest_latitude.cos()
+    };
+    let mut step = GEOHASH_STEP;
+    while step > 1 && (cell_height(step) < radius || cell_width(step) < radius) {
+        step -= 1;
+    }
+
+    let shift = GEOHASH_STEP - step;
+    let cells = 1i64 << step;
+    let longitude_index = (_cell_index(longitude, LONGITUDE_RANGE) >> shift) as i64;
+    let latitude_index = (_cell_index(latitude, LATITUDE_RANGE) >> shift) as i64;
+    let mut ranges = vec![];
+    for latitude_delta in -1..=1 {
+        let neighbour_latitude = latitude_index + latitude_delta;
+        if !(0..cells).contains(&neighbour_latitude) {
+            continue;
+        }
+        for longitude_delta in -1..=1 {
+            // Longitudes wrap around the antimeridian.
+            let neighbour_longitude = (longitude_index + longitude_delta).rem_euclid(cells);
+            let prefix = _interleave(neighbour_longitude as u64, neighbour_latitude as u64);
+            let range = (prefix << (2 * shift), (prefix + 1) << (2 * shift));
+            if !ranges.contains(&range) {
+                ranges.push(range);
+            }
+        }
+    }
+    ranges
+}
+
+/// Sorted set of members scored by the geohash of their position.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GeoSet {
+    scores: HashMap<String, u64>,
+    by_score: BTreeSet<(u64, String)>,
+    /// Bytes held by the members and scores, see `size`.
+    size: usize,
+}
+
+impl GeoSet {
+    pub fn new() -> Self {
+        GeoSet::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    /// Rough estimate (in bytes) of the memory held by the members.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    fn _member_size(member: &str) -> usize {
+        2 * (member.len() + std::mem::size_of::<u64>())
+    }
+
+    /// Adds or moves a member, and returns whether it is new.
+    /// The position must be valid, see `is_valid_position`.
+    pub fn insert(&mut self, member: String, longitude: f64, latitude: f64) -> bool {
+        let score = encode(longitude, latitude);
+        let old_score = self.scores.insert(member.to_owned(), score);
+        match old_score {
+            Some(old_score) => {
+                self.by_score.remove(&(old_score, member.to_owned()));
+            }
+            None => self.size += GeoSet::_member_size(&member),
+        }
+        self.by_score.insert((score, member));
+        old_score.is_none()
+    }
+
+    /// Removes a member, and returns whether it existed.
+    pub fn remove(&mut self, member: &str) -> bool {
+        match self.scores.remove(member) {
+            Some(score) => {
+                self.size -= GeoSet::_member_size(member);
+                self.by_score.remove(&(score, member.to_owned()))
+            }
+            None => false,
+        }
+    }
+
+    /// Position of a member, at the center of the cell of its geohash.
+    pub fn position(&self, member: &str) -> Option<GeoPosition> {
+        self.scores.get(member).map(|score| decode(*score))
+    }
+
+    /// Finds the members within `shape` (in meters) of the center, with their distance to it
+    /// in meters.
+    pub fn search(&self, center: (f64, f64), shape: GeoShape) -> Vec<GeoMatch> {
+        let radius = match shape {
+            GeoShape::Radius(radius) => radius,
+            GeoShape::Box(width, height) => (width * width + height * height).sqrt() / 2.0,
+        };
+        let mut matches = vec![];
+        for (start, end) in _covering_ranges(center, radius) {
+            let candidates = self
+                .by_score
+                .range((start, String::new())..(end, String::new()));
+            for (score, member) in candidates {
+                let position = decode(*score);
+                let center_distance = distance(center, position);
+                let is_inside = match shape {
+                    GeoShape::Radius(radius) => center_distance <= radius,
+                    GeoShape::Box(width, height) => {
+                        // Compare the distances along the meridian and along the parallel
+                        // of the member, as the sides of the box follow them.
+                        distance((center.0, position.1), (center.0, center.1)) <= height / 2.0
+                            && distance(position, (center.0, position.1)) <= width / 2.0
+                    }
+                };
+                if is_inside {
+                    matches.push(GeoMatch {
+                        member: member.to_owned(),
+                        distance: center_distance,
+                        longitude: position.0,
+                        latitude: position.1,
+                    });
+                }
+            }
+        }
+        matches
+    }
+}
@@ -0,0 +1,28 @@
+/// MurmurHash3 finalizer, spreads the bits of a 64-bit value.
+pub fn mix(mut value: u64) -> u64 {
+    value ^= value >> 33;
+    value = value.wrapping_mul(0xff51afd7ed558ccd);
+    value ^= value >> 33;
+    value = value.wrapping_mul(0xc4ceb9fe1a85ec53);
+    value ^ (value >> 33)
+}
+
+/// Stable 64-bit hash of an element: FNV-1a, followed by the MurmurHash3 finalizer.
+/// Unlike the std hasher it doesn't change between runs, so structures built from it
+/// can be merged.
+pub fn hash(element: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in element.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    mix(hash)
+}
+
+/// Pair of independent hashes of an element, to derive any number of hashes from
+/// as `first + i * second` (Kirsch-Mitzenmacher).
+pub fn double_hash(element: &str) -> (u64, u64) {
+    let first = hash(element);
+    // The second hash is odd so it never degenerates to a single hash.
+    (first, mix(first ^ 0x9e3779b97f4a7c15) | 1)
+}
@@ -0,0 +1,95 @@
+use super::hashing::hash;
+
+/// Number of bits of the hash used to pick a register.
+const PRECISION: u32 = 14;
+const REGISTER_COUNT: usize = 1 << PRECISION;
+/// Registers hold values up to 64 - PRECISION + 1 = 51, which fit in 6 bits.
+const REGISTER_BITS: usize = 6;
+const REGISTER_MASK: u16 = (1 << REGISTER_BITS) - 1;
+
+/// Probabilistic counter of distinct elements: 16384 registers of 6 bits (12KB),
+/// with a standard error of 0.81%.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        HyperLogLog {
+            registers: vec![0; REGISTER_COUNT * REGISTER_BITS / 8],
+        }
+    }
+}
+
+impl HyperLogLog {
+    pub fn new() -> Self {
+        HyperLogLog::default()
+    }
+
+    /// Size (in bytes) of the registers.
+    pub fn size(&self) -> usize {
+        self.registers.len()
+    }
+
+    fn _get_register(&self, index: usize) -> u8 {
+        let (byte, shift) = (index * REGISTER_BITS / 8, index * REGISTER_BITS % 8);
+        let low = self.registers[byte] as u16;
+        let high = self.registers.get(byte + 1).copied().unwrap_or(0) as u16;
+        (((high << 8 | low) >> shift) & REGISTER_MASK) as u8
+    }
+
+    fn _set_register(&mut self, index: usize, value: u8) {
+        let (byte, shift) = (index * REGISTER_BITS / 8, index * REGISTER_BITS % 8);
+        let low = self.registers[byte] as u16;
+        let high = self.registers.get(byte + 1).copied().unwrap_or(0) as u16;
+        let word = (high << 8 | low) & !(REGISTER_MASK << shift) | (value as u16) << shift;
+        self.registers[byte] = word as u8;
+        if let Some(high) = self.registers.get_mut(byte + 1) {
+            *high = (word >> 8) as u8;
+        }
+    }
+
+    /// Adds an element, and returns whether the estimated cardinality may have changed.
+    pub fn add(&mut self, element: &str) -> bool {
+        let hash = hash(element);
+        let index = (hash >> (64 - PRECISION)) as usize;
+        // A guard bit bounds the rank when the remaining bits are all 0.
+        let rank = ((hash << PRECISION) | (1 << (PRECISION - 1))).leading_zeros() as u8 + 1;
+        if rank > self._get_register(index) {
+            self._set_register(index, rank);
+            return true;
+        }
+        false
+    }
+
+    /// Merges another HyperLogLog in this one, which then counts the union of both.
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        for index in 0..REGISTER_COUNT {
+            let rank = other._get_register(index);
+            if rank > self._get_register(index) {
+                self._set_register(index, rank);
+            }
+        }
+    }
+
+    /// Estimated number of distinct elements added.
+    pub fn count(&self) -> u64 {
+        let m = REGISTER_COUNT as f64;
+        let (mut sum, mut zeros) = (0.0, 0);
+        for index in 0..REGISTER_COUNT {
+            let rank = self._get_register(index);
+            sum += 1.0 / (1u64 << rank) as f64;
+            if rank == 0 {
+                zeros += 1;
+            }
+        }
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let estimate = alpha * m * m / sum;
+        if estimate <= 2.5 * m && zeros > 0 {
+            // Linear counting is more accurate for small cardinalities.
+            return (m * (m / zeros as f64).ln()).round() as u64;
+        }
+        estimate.round() as u64
+    }
+}
@@ -0,0 +1,337 @@
+use crate::stores::{
+    errors::{TypeConversionError, TypeConversionImpossible, ValueError},
+    store::KeyValueStore,
+    types::{BitOp, BitUnit, BitfieldOverflow, BitfieldType, ValueType},
+    value_entry::ValueEntry,
+};
+
+/// Bitmaps can't grow past 512MB (2^32 bits).
+const MAX_BIT_OFFSET: u64 = (1 << 32) - 1;
+
+/// Bits are numbered from the most significant bit of the first byte.
+fn _get_bit(bytes: &[u8], offset: u64) -> bool {
+    match bytes.get((offset / 8) as usize) {
+        Some(byte) => byte & (0x80 >> (offset % 8)) != 0,
+        None => false,
+    }
+}
+
+fn _set_bit(bytes: &mut Vec<u8>, offset: u64, bit: bool) {
+    let index = (offset / 8) as usize;
+    if bytes.len() <= index {
+        bytes.resize(index + 1, 0);
+    }
+    let mask = 0x80 >> (offset % 8);
+    if bit {
+        bytes[index] |= mask;
+    } else {
+        bytes[index] &= !mask;
+    }
+}
+
+/// Resolves an inclusive range whose negative offsets count from the end (-1 being the last
+/// unit), into the bit offsets it covers. None if the range is empty.
+fn _bit_range(
+    length_in_bytes: usize,
+    range: Option<(i64, i64)>,
+    unit: BitUnit,
+) -> Option<(u64, u64)> {
+    let length = match unit {
+        BitUnit::Byte => length_in_bytes as i64,
+        BitUnit::Bit => length_in_bytes as i64 * 8,
+    };
+    let (start, end) = range.unwrap_or((0, -1));
+    let start = if start < 0 { length + start } else { start }.max(0);
+    let end = if end < 0 { length + end } else { end }.min(length - 1);
+    if start > end {
+        return None;
+    }
+    match unit {
+        BitUnit::Byte => Some((start as u64 * 8, end as u64 * 8 + 7)),
+        BitUnit::Bit => Some((start as u64, end as u64)),
+    }
+}
+
+fn _check_offset(offset: u64) -> Result<(), ValueError> {
+    if offset > MAX_BIT_OFFSET {
+        return Err(ValueError::TypeConversionError(
+            TypeConversionError::BitOffsetOutOfRange,
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that a bitfield of the given type starting at `offset` fits in a bitmap.
+fn _check_bitfield_offset(field_type: BitfieldType, offset: u64) -> Result<(), ValueError> {
+    match offset.checked_add(field_type.bits() as u64 - 1) {
+        Some(last_offset) => _check_offset(last_offset),
+        None => Err(ValueError::TypeConversionError(
+            TypeConversionError::BitOffsetOutOfRange,
+        )),
+    }
+}
+
+impl KeyValueStore {
+    /// Borrows the bytes of a Bytes (or String) value.
+    fn _get_bitmap(&self, key: &str) -> Option<Result<&[u8], ValueError>> {
+        match self._get_or_none_if_expired(key) {
+            Some(value_entry) => match &value_entry.value {
+                ValueType::Bytes(bytes) => Some(Ok(bytes)),
+                ValueType::String(string) => Some(Ok(string.as_bytes())),
+                _ => Some(Err(ValueError::TypeConversionImpossible(
+                    TypeConversionImpossible::IncompatibleTypes,
+                ))),
+            },
+            None => None,
+        }
+    }
+
+    /// Gets the bytes of a key to modify them, turning String values into Bytes values, and
+    /// creating an empty Bytes value (with the default time to live of the store) if needed.
+    fn _get_or_create_mut_bitmap(&mut self, key: &str) -> Result<&mut Vec<u8>, ValueError> {
+        if self._get_or_none_if_expired(key).is_none() {
+            let expiration = self._get_expiration_instant(None);
+            self._insert(key, &ValueEntry::from_bytes(vec![], expiration));
+        }
+        if let Some(value_entry) = self._get_mut_or_none_if_expired(key) {
+            if let ValueType::String(string) = &mut value_entry.value {
+                value_entry.value = ValueType::Bytes(std::mem::take(string).into_bytes());
+                // The key isn't a String anymore, so it leaves the full-text indexes.
+                self._update_indexes(key);
+            }
+        }
+        let Some(value_entry) = self._get_mut_or_none_if_expired(key) else {
+            unreachable!("the bitmap was just created");
+        };
+        match &mut value_entry.value {
+            ValueType::Bytes(bytes) => Ok(bytes),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::AsMutable,
+            )),
+        }
+    }
+
+    /// Sets the bit at `offset` (extending the bitmap with zeros if needed)
+    /// and returns its previous value.
+    pub fn bitmap_set_bit(
+        &mut self,
+        key: &str,
+        offset: u64,
+        bit: bool,
+    ) -> Result<bool, ValueError> {
+        _check_offset(offset)?;
+        let bytes = self._get_or_create_mut_bitmap(key)?;
+        let old_bit = _get_bit(bytes, offset);
+        _set_bit(bytes, offset, bit);
+        Ok(old_bit)
+    }
+
+    /// Gets the bit at `offset`, bits past the end of the bitmap are 0.
+    pub fn bitmap_get_bit(&self, key: &str, offset: u64) -> Option<Result<bool, ValueError>> {
+        match self._get_bitmap(key)? {
+            Ok(bytes) => Some(Ok(_get_bit(bytes, offset))),
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    /// Counts the bits set to 1, in the whole bitmap or in an inclusive range of bytes or bits.
+    pub fn bitmap_count(
+        &self,
+        key: &str,
+        range: Option<(i64, i64)>,
+        unit: BitUnit,
+    ) -> Option<Result<u64, ValueError>> {
+        let bytes = match self._get_bitmap(key)? {
+            Ok(bytes) => bytes,
+            Err(e) => return Some(Err(e)),
+        };
+        let Some((start, end)) = _bit_range(bytes.len(), range, unit) else {
+            return Some(Ok(0));
+        };
+        let (first, last) = ((start / 8) as usize, (end / 8) as usize);
+        let count = (first..=last)
+            .map(|index| {
+                let mut byte = bytes[index];
+                if index == first {
+                    byte &= 0xFF >> (start % 8);
+                }
+                if index == last {
+                    byte &= 0xFF << (7 - end % 8);
+                }
+                byte.count_ones() as u64
+            })
+            .sum();
+        Some(Ok(count))
+    }
+
+    /// Finds the offset of the first bit set to `bit`, in the whole bitmap or in an inclusive
+    /// range of bytes or bits. When looking for a 0 without a range, the bits past the end of
+    /// the bitmap count as 0, so the length of the bitmap (in bits) is returned if all its bits
+    /// are set.
+    pub fn bitmap_position(
+        &self,
+        key: &str,
+        bit: bool,
+        range: Option<(i64, i64)>,
+        unit: BitUnit,
+    ) -> Option<Result<Option<u64>, ValueError>> {
+        let bytes = match self._get_bitmap(key)? {
+            Ok(bytes) => bytes,
+            Err(e) => return Some(Err(e)),
+        };
+        let skipped_byte = if bit { 0x00 } else { 0xFF };
+        if let Some((start, end)) = _bit_range(bytes.len(), range, unit) {
+            let mut offset = start;
+            while offset <= end {
+                if offset % 8 == 0
+                    && offset + 7 <= end
+                    && bytes[(offset / 8) as usize] == skipped_byte
+                {
+                    offset += 8;
+                    continue;
+                }
+                if _get_bit(bytes, offset) == bit {
+                    return Some(Ok(Some(offset)));
+                }
+                offset += 1;
+            }
+        }
+        if !bit && range.is_none() {
+            return Some(Ok(Some(bytes.len() as u64 * 8)));
+        }
+        Some(Ok(None))
+    }
+
+    /// Combines the bitmaps of the given keys with `op` and stores the result into the
+    /// destination key (replacing it). Shorter and missing bitmaps are padded with zeros.
+    /// Returns the length (in bytes) of the result.
+    pub fn bitmap_op(
+        &mut self,
+        op: BitOp,
+        destination: &str,
+        keys: &[&str],
+    ) -> Result<usize, ValueError> {
+        let mut bitmaps = vec![];
+        for key in keys {
+            match self._get_bitmap(key) {
+                Some(Ok(bytes)) => bitmaps.push(bytes),
+                Some(Err(e)) => return Err(e),
+                None => bitmaps.push(&[]),
+            }
+        }
+        let length = bitmaps.iter().map(|bytes| bytes.len()).max().unwrap_or(0);
+        let result: Vec<u8> = (0..length)
+            .map(|index| {
+                let mut bytes = bitmaps
+                    .iter()
+                    .map(|bytes| bytes.get(index).copied().unwrap_or(0));
+                let first = bytes.next().unwrap_or(0);
+                bytes.fold(first, |result, byte| match op {
+                    BitOp::And => result & byte,
+                    BitOp::Or => result | byte,
+                    BitOp::Xor => result ^ byte,
+                })
+            })
+            .collect();
+        self.set_bytes(destination, result, None);
+        Ok(length)
+    }
+
+    /// Stores the inverted bitmap of the key into the destination key (replacing it).
+    /// Returns the length (in bytes) of the result.
+    pub fn bitmap_not(&mut self, destination: &str, key: &str) -> Result<usize, ValueError> {
+        let result: Vec<u8> = match self._get_bitmap(key) {
+            Some(Ok(bytes)) => bytes.iter().map(|byte| !byte).collect(),
+            Some(Err(e)) => return Err(e),
+            None => vec![],
+        };
+        let length = result.len();
+        self.set_bytes(destination, result, None);
+        Ok(length)
+    }
+
+    /// The bitfield must fit in a bitmap, see `_check_bitfield_offset`.
+    fn _read_bitfield(bytes: &[u8], field_type: BitfieldType, offset: u64) -> i64 {
+        let raw = (0..field_type.bits() as u64).fold(0u64, |raw, bit| {
+            (raw << 1) | _get_bit(bytes, offset + bit) as u64
+        });
+        field_type.decode(raw)
+    }
+
+    fn _write_bitfield(bytes: &mut Vec<u8>, field_type: BitfieldType, offset: u64, value: i64) {
+        let bits = field_type.bits() as u64;
+        for bit in 0..bits {
+            _set_bit(bytes, offset + bit, (value >> (bits - 1 - bit)) & 1 == 1);
+        }
+    }
+
+    /// Reads the integer of the given type stored at the bit `offset`.
+    pub fn bitfield_get(
+        &self,
+        key: &str,
+        field_type: BitfieldType,
+        offset: u64,
+    ) -> Option<Result<i64, ValueError>> {
+        if let Err(e) = _check_bitfield_offset(field_type, offset) {
+            return Some(Err(e));
+        }
+        match self._get_bitmap(key)? {
+            Ok(bytes) => Some(Ok(Self::_read_bitfield(bytes, field_type, offset))),
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    /// Writes an integer of the given type at the bit `offset` and returns the previous one.
+    /// Returns None (and writes nothing) if the value doesn't fit and `overflow` is `Fail`.
+    pub fn bitfield_set(
+        &mut self,
+        key: &str,
+        field_type: BitfieldType,
+        offset: u64,
+        value: i64,
+        overflow: BitfieldOverflow,
+    ) -> Result<Option<i64>, ValueError> {
+        self._update_bitfield(key, field_type, offset, overflow, |_| value as i128)
+            .map(|result| result.map(|(old_value, _)| old_value))
+    }
+
+    /// Adds `delta` to the integer of the given type at the bit `offset` and returns the result.
+    /// Returns None (and writes nothing) if the result doesn't fit and `overflow` is `Fail`.
+    pub fn bitfield_incr(
+        &mut self,
+        key: &str,
+        field_type: BitfieldType,
+        offset: u64,
+        delta: i64,
+        overflow: BitfieldOverflow,
+    ) -> Result<Option<i64>, ValueError> {
+        self._update_bitfield(key, field_type, offset, overflow, |old_value| {
+            old_value as i128 + delta as i128
+        })
+        .map(|result| result.map(|(_, new_value)| new_value))
+    }
+
+    /// Replaces the integer at the bit `offset` with `update(old_value)` fitted in the type,
+    /// and returns the old and new values. Missing keys are only created if a value is written.
+    fn _update_bitfield(
+        &mut self,
+        key: &str,
+        field_type: BitfieldType,
+        offset: u64,
+        overflow: BitfieldOverflow,
+        update: impl FnOnce(i64) -> i128,
+    ) -> Result<Option<(i64, i64)>, ValueError> {
+        _check_bitfield_offset(field_type, offset)?;
+        let old_value = match self._get_bitmap(key) {
+            Some(Ok(bytes)) => Self::_read_bitfield(bytes, field_type, offset),
+            Some(Err(e)) => return Err(e),
+            None => 0,
+        };
+        let Some(new_value) = field_type.fit(update(old_value), overflow) else {
+            return Ok(None);
+        };
+        let bytes = self._get_or_create_mut_bitmap(key)?;
+        Self::_write_bitfield(bytes, field_type, offset, new_value);
+        Ok(Some((old_value, new_value)))
+    }
+}
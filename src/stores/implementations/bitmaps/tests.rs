@@ -0,0 +1,204 @@
+use crate::stores::{
+    store::KeyValueStore,
+    types::{BitOp, BitUnit, BitfieldOverflow, BitfieldType},
+};
+
+#[test]
+fn test_set_get_bit() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert!(!store.bitmap_set_bit("ABC", 7, true).unwrap());
+    assert!(store.bitmap_set_bit("ABC", 7, true).unwrap());
+    assert!(!store.bitmap_set_bit("ABC", 20, true).unwrap());
+    assert_eq!(
+        store.get_bytes("ABC").unwrap().unwrap(),
+        vec![0b0000_0001, 0, 0b0000_1000]
+    );
+
+    assert!(store.bitmap_get_bit("ABC", 7).unwrap().unwrap());
+    assert!(!store.bitmap_get_bit("ABC", 6).unwrap().unwrap());
+    assert!(!store.bitmap_get_bit("ABC", 1000).unwrap().unwrap());
+    assert!(store.bitmap_get_bit("nope", 0).is_none());
+    assert!(store.bitmap_set_bit("ABC", 1 << 32, true).is_err());
+
+    // Strings are bitmaps as well.
+    store.set_string("DEF", "a".to_string(), None);
+    assert!(store.bitmap_get_bit("DEF", 1).unwrap().unwrap());
+    assert!(!store.bitmap_set_bit("DEF", 6, true).unwrap());
+    assert_eq!(store.get_string("DEF").unwrap().unwrap(), "c");
+
+    store.set_list("list", vec![], None);
+    assert!(store.bitmap_set_bit("list", 0, true).is_err());
+}
+
+#[test]
+fn test_bit_count_and_position() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.set_bytes("ABC", vec![0xFF, 0xF0, 0x00, 0x01], None);
+
+    assert_eq!(
+        store
+            .bitmap_count("ABC", None, BitUnit::Byte)
+            .unwrap()
+            .unwrap(),
+        13
+    );
+    assert_eq!(
+        store
+            .bitmap_count("ABC", Some((1, -1)), BitUnit::Byte)
+            .unwrap()
+            .unwrap(),
+        5
+    );
+    assert_eq!(
+        store
+            .bitmap_count("ABC", Some((4, 11)), BitUnit::Bit)
+            .unwrap()
+            .unwrap(),
+        8
+    );
+    assert_eq!(
+        store
+            .bitmap_count("ABC", Some((3, 1)), BitUnit::Byte)
+            .unwrap()
+            .unwrap(),
+        0
+    );
+
+    let position = |bit, range, unit| {
+        store
+            .bitmap_position("ABC", bit, range, unit)
+            .unwrap()
+            .unwrap()
+    };
+    assert_eq!(position(false, None, BitUnit::Byte), Some(12));
+    assert_eq!(position(true, Some((2, -1)), BitUnit::Byte), Some(31));
+    assert_eq!(position(true, Some((2, 2)), BitUnit::Byte), None);
+    assert_eq!(position(true, Some((5, 20)), BitUnit::Bit), Some(5));
+
+    store.set_bytes("full", vec![0xFF], None);
+    assert_eq!(
+        store
+            .bitmap_position("full", false, None, BitUnit::Byte)
+            .unwrap()
+            .unwrap(),
+        Some(8)
+    );
+    assert_eq!(
+        store
+            .bitmap_position("full", false, Some((0, 0)), BitUnit::Byte)
+            .unwrap()
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_bit_operations() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.set_bytes("ABC", vec![0b1100, 0xFF], None);
+    store.set_bytes("DEF", vec![0b1010], None);
+
+    assert_eq!(
+        store.bitmap_op(BitOp::And, "and", &["ABC", "DEF"]).unwrap(),
+        2
+    );
+    assert_eq!(store.get_bytes("and").unwrap().unwrap(), vec![0b1000, 0]);
+    store
+        .bitmap_op(BitOp::Or, "or", &["ABC", "DEF", "nope"])
+        .unwrap();
+    assert_eq!(store.get_bytes("or").unwrap().unwrap(), vec![0b1110, 0xFF]);
+    store.bitmap_op(BitOp::Xor, "xor", &["ABC", "DEF"]).unwrap();
+    assert_eq!(store.get_bytes("xor").unwrap().unwrap(), vec![0b0110, 0xFF]);
+    assert_eq!(store.bitmap_not("not", "DEF").unwrap(), 1);
+    assert_eq!(store.get_bytes("not").unwrap().unwrap(), vec![0b1111_0101]);
+
+    store.set_i64("int", 1, None);
+    assert!(store.bitmap_op(BitOp::And, "and", &["ABC", "int"]).is_err());
+}
+
+#[test]
+fn test_bitfields() {
+    let mut store = KeyValueStore::new("new_store", None);
+    let u8_type = BitfieldType::parse("u8").unwrap();
+    let i5_type = BitfieldType::parse("i5").unwrap();
+    assert!(BitfieldType::parse("u64").is_none());
+    assert!(BitfieldType::parse("i0").is_none());
+    assert!(BitfieldType::parse("x8").is_none());
+
+    assert_eq!(
+        store
+            .bitfield_set("ABC", u8_type, 4, 255, BitfieldOverflow::Wrap)
+            .unwrap(),
+        Some(0)
+    );
+    assert_eq!(store.get_bytes("ABC").unwrap().unwrap(), vec![0x0F, 0xF0]);
+    assert_eq!(store.bitfield_get("ABC", u8_type, 4).unwrap().unwrap(), 255);
+    assert_eq!(store.bitfield_get("ABC", i5_type, 4).unwrap().unwrap(), -1);
+
+    assert_eq!(
+        store
+            .bitfield_incr("ABC", u8_type, 4, 1, BitfieldOverflow::Wrap)
+            .unwrap(),
+        Some(0)
+    );
+    assert_eq!(
+        store
+            .bitfield_incr("ABC", u8_type, 4, -1, BitfieldOverflow::Sat)
+            .unwrap(),
+        Some(0)
+    );
+    assert_eq!(
+        store
+            .bitfield_incr("ABC", i5_type, 20, 100, BitfieldOverflow::Sat)
+            .unwrap(),
+        Some(15)
+    );
+    assert_eq!(
+        store
+            .bitfield_incr("ABC", i5_type, 20, 1, BitfieldOverflow::Fail)
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        store
+            .bitfield_incr("ABC", i5_type, 20, 1, BitfieldOverflow::Wrap)
+            .unwrap(),
+        Some(-16)
+    );
+
+    let i64_type = BitfieldType::parse("i64").unwrap();
+    store
+        .bitfield_set("DEF", i64_type, 3, i64::MIN, BitfieldOverflow::Fail)
+        .unwrap();
+    assert_eq!(
+        store.bitfield_get("DEF", i64_type, 3).unwrap().unwrap(),
+        i64::MIN
+    );
+    assert_eq!(
+        store
+            .bitfield_incr("DEF", i64_type, 3, -1, BitfieldOverflow::Wrap)
+            .unwrap(),
+        Some(i64::MAX)
+    );
+
+    // Offsets past the end of a bitmap are rejected, even those that would overflow.
+    assert!(store
+        .bitfield_set("DEF", u8_type, u64::MAX, 1, BitfieldOverflow::Wrap)
+        .is_err());
+    assert!(store
+        .bitfield_get("DEF", u8_type, u64::MAX)
+        .unwrap()
+        .is_err());
+    assert!(store
+        .bitfield_incr("DEF", u8_type, (1 << 32) - 4, 1, BitfieldOverflow::Wrap)
+        .is_err());
+
+    // Failing to write doesn't create the key.
+    assert_eq!(
+        store
+            .bitfield_incr("GHI", u8_type, 0, 256, BitfieldOverflow::Fail)
+            .unwrap(),
+        None
+    );
+    assert!(store.get_kind("GHI").is_none());
+}
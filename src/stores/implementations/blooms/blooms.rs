@@ -0,0 +1,56 @@
+use crate::stores::{bloom::BloomFilter, errors::ValueError, store::KeyValueStore};
+
+/// Parameters of the Bloom filters created on first add.
+const DEFAULT_CAPACITY: u64 = 100;
+const DEFAULT_ERROR_RATE: f64 = 0.01;
+const DEFAULT_EXPANSION: u32 = 2;
+
+impl KeyValueStore {
+    /// Creates an empty Bloom filter (with the default time to live of the store),
+    /// and returns false if the key already exists. See `BloomFilter::new` for the parameters.
+    pub fn bloom_reserve(
+        &mut self,
+        key: &str,
+        capacity: u64,
+        error_rate: f64,
+        expansion: u32,
+    ) -> Result<bool, ValueError> {
+        self._sketch_reserve(key, BloomFilter::new(capacity, error_rate, expansion))
+    }
+
+    /// Adds elements to the Bloom filter of a key (creating it with the default parameters
+    /// if needed), and returns for each element whether it was added, false if it seemed
+    /// already present.
+    pub fn bloom_add(&mut self, key: &str, elements: &[&str]) -> Result<Vec<bool>, ValueError> {
+        let filter = self._get_or_create_mut_sketch(key, || {
+            BloomFilter::new(DEFAULT_CAPACITY, DEFAULT_ERROR_RATE, DEFAULT_EXPANSION)
+        })?;
+        Ok(elements.iter().map(|element| filter.add(element)).collect())
+    }
+
+    /// Checks for each element whether it may be in the Bloom filter of a key.
+    /// False positives are possible, false negatives aren't.
+    pub fn bloom_contains(
+        &self,
+        key: &str,
+        elements: &[&str],
+    ) -> Option<Result<Vec<bool>, ValueError>> {
+        Some(
+            self._get_or_none_if_expired(key)?
+                .get_value_as_bloom()
+                .map(|filter| {
+                    elements
+                        .iter()
+                        .map(|element| filter.contains(element))
+                        .collect()
+                }),
+        )
+    }
+
+    /// Merges the Bloom filters of the given keys into the Bloom filter of the destination key
+    /// (created from the first of them if needed), which then contains the elements of all of
+    /// them. Missing keys are ignored, and all the filters must have the same parameters.
+    pub fn bloom_merge(&mut self, destination: &str, keys: &[&str]) -> Result<(), ValueError> {
+        self._sketch_merge::<BloomFilter>(destination, keys)
+    }
+}
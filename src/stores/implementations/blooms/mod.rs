@@ -0,0 +1,2 @@
+mod blooms;
+mod tests;
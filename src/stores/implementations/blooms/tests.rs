@@ -0,0 +1,84 @@
+use crate::stores::{store::KeyValueStore, types::ValueKind};
+
+#[test]
+fn test_bloom_add_contains() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert!(store.bloom_reserve("seen", 1000, 0.01, 2).unwrap());
+    assert!(!store.bloom_reserve("seen", 1000, 0.01, 2).unwrap());
+    assert!(store.bloom_reserve("bad", 0, 0.01, 2).is_err());
+    assert!(store.bloom_reserve("bad", 1000, 1.5, 2).is_err());
+    assert!(store.bloom_reserve("bad", u64::MAX, 0.01, 2).is_err());
+    assert!(store.bloom_reserve("bad", 1_000_000_000, 1e-10, 2).is_err());
+    assert_eq!(store.get_kind("seen"), Some(ValueKind::Bloom));
+
+    assert_eq!(
+        store.bloom_add("seen", &["a", "b", "a"]).unwrap(),
+        vec![true, true, false]
+    );
+    assert_eq!(
+        store
+            .bloom_contains("seen", &["a", "b", "c"])
+            .unwrap()
+            .unwrap(),
+        vec![true, true, false]
+    );
+    assert!(store.bloom_contains("nope", &["a"]).is_none());
+
+    store.set_string("string", "abc".to_string(), None);
+    assert!(store.bloom_add("string", &["a"]).is_err());
+}
+
+#[test]
+fn test_bloom_scaling_error_rate() {
+    let mut store = KeyValueStore::new("new_store", None);
+    // The filter is created on first add with a small capacity, and has to grow.
+    for idx in 0..10_000 {
+        store
+            .bloom_add("events", &[&format!("event:{}", idx)])
+            .unwrap();
+    }
+    let added: Vec<String> = (0..10_000).map(|idx| format!("event:{}", idx)).collect();
+    let added: Vec<&str> = added.iter().map(String::as_str).collect();
+    assert!(store
+        .bloom_contains("events", &added)
+        .unwrap()
+        .unwrap()
+        .into_iter()
+        .all(|contains| contains));
+
+    let others: Vec<String> = (0..10_000).map(|idx| format!("other:{}", idx)).collect();
+    let others: Vec<&str> = others.iter().map(String::as_str).collect();
+    let false_positives = store
+        .bloom_contains("events", &others)
+        .unwrap()
+        .unwrap()
+        .into_iter()
+        .filter(|contains| *contains)
+        .count();
+    assert!(false_positives < 150, "{} false positives", false_positives);
+}
+
+#[test]
+fn test_bloom_merge() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.bloom_add("monday", &["a", "b"]).unwrap();
+    store.bloom_add("tuesday", &["c"]).unwrap();
+    store
+        .bloom_merge("week", &["monday", "tuesday", "nope"])
+        .unwrap();
+    assert_eq!(
+        store
+            .bloom_contains("week", &["a", "b", "c", "d"])
+            .unwrap()
+            .unwrap(),
+        vec![true, true, true, false]
+    );
+    // The sources are untouched.
+    assert_eq!(
+        store.bloom_contains("monday", &["c"]).unwrap().unwrap(),
+        vec![false]
+    );
+
+    store.bloom_reserve("other", 10, 0.1, 2).unwrap();
+    assert!(store.bloom_merge("week", &["other"]).is_err());
+}
@@ -0,0 +1,70 @@
+use crate::stores::{countmin::CountMinSketch, errors::ValueError, store::KeyValueStore};
+
+/// Dimensions of the Count-Min sketches created on first increment.
+const DEFAULT_WIDTH: usize = 1000;
+const DEFAULT_DEPTH: usize = 5;
+
+impl KeyValueStore {
+    /// Creates an empty Count-Min sketch of the given dimensions (with the default time to live
+    /// of the store), and returns false if the key already exists.
+    pub fn countmin_reserve(
+        &mut self,
+        key: &str,
+        width: usize,
+        depth: usize,
+    ) -> Result<bool, ValueError> {
+        self._sketch_reserve(key, CountMinSketch::new(width, depth))
+    }
+
+    /// Same as `countmin_reserve`, with the dimensions derived from the accuracy,
+    /// see `CountMinSketch::with_error`.
+    pub fn countmin_reserve_with_error(
+        &mut self,
+        key: &str,
+        error: f64,
+        probability: f64,
+    ) -> Result<bool, ValueError> {
+        self._sketch_reserve(key, CountMinSketch::with_error(error, probability))
+    }
+
+    /// Counts elements more times in the Count-Min sketch of a key (creating it with the
+    /// default dimensions if needed), and returns their new estimated counts.
+    pub fn countmin_incr(
+        &mut self,
+        key: &str,
+        increments: Vec<(String, u64)>,
+    ) -> Result<Vec<u64>, ValueError> {
+        let sketch = self
+            ._get_or_create_mut_sketch(key, || CountMinSketch::new(DEFAULT_WIDTH, DEFAULT_DEPTH))?;
+        Ok(increments
+            .iter()
+            .map(|(element, increment)| sketch.increment(element, *increment))
+            .collect())
+    }
+
+    /// Estimates how many times elements were counted in the Count-Min sketch of a key,
+    /// never less than the actual numbers.
+    pub fn countmin_query(
+        &self,
+        key: &str,
+        elements: &[&str],
+    ) -> Option<Result<Vec<u64>, ValueError>> {
+        Some(
+            self._get_or_none_if_expired(key)?
+                .get_value_as_countmin()
+                .map(|sketch| {
+                    elements
+                        .iter()
+                        .map(|element| sketch.query(element))
+                        .collect()
+                }),
+        )
+    }
+
+    /// Adds the counts of the Count-Min sketches of the given keys to the Count-Min sketch of
+    /// the destination key (created from the first of them if needed). Missing keys are ignored,
+    /// and all the sketches must have the same dimensions.
+    pub fn countmin_merge(&mut self, destination: &str, keys: &[&str]) -> Result<(), ValueError> {
+        self._sketch_merge::<CountMinSketch>(destination, keys)
+    }
+}
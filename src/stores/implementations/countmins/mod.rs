@@ -0,0 +1,2 @@
+mod countmins;
+mod tests;
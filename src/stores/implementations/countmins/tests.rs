@@ -0,0 +1,83 @@
+use crate::stores::{store::KeyValueStore, types::ValueKind};
+
+#[test]
+fn test_countmin_incr_query() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert!(store.countmin_reserve("clicks", 100, 4).unwrap());
+    assert!(!store
+        .countmin_reserve_with_error("clicks", 0.01, 0.01)
+        .unwrap());
+    assert!(store.countmin_reserve("bad", 0, 4).is_err());
+    assert!(store.countmin_reserve_with_error("bad", 0.0, 0.01).is_err());
+    assert!(store.countmin_reserve("bad", usize::MAX, 4).is_err());
+    assert!(store
+        .countmin_reserve_with_error("bad", 1e-300, 0.01)
+        .is_err());
+    assert_eq!(store.get_kind("clicks"), Some(ValueKind::CountMin));
+
+    assert_eq!(
+        store
+            .countmin_incr("clicks", vec![("a".to_string(), 3), ("b".to_string(), 1)])
+            .unwrap(),
+        vec![3, 1]
+    );
+    assert_eq!(
+        store
+            .countmin_incr("clicks", vec![("a".to_string(), 2)])
+            .unwrap(),
+        vec![5]
+    );
+    assert_eq!(
+        store
+            .countmin_query("clicks", &["a", "b", "c"])
+            .unwrap()
+            .unwrap(),
+        vec![5, 1, 0]
+    );
+    assert!(store.countmin_query("nope", &["a"]).is_none());
+
+    // Estimates never undercount, and stay close with a sketch sized for the error.
+    store
+        .countmin_reserve_with_error("pages", 0.001, 0.01)
+        .unwrap();
+    let increments = (0..5000)
+        .map(|idx| (format!("page:{}", idx % 500), 1 + idx % 3))
+        .collect();
+    store.countmin_incr("pages", increments).unwrap();
+    let mut exact = vec![0; 500];
+    for idx in 0..5000 {
+        exact[idx % 500] += 1 + idx as u64 % 3;
+    }
+    for (page, count) in exact.iter().enumerate() {
+        let estimate = store
+            .countmin_query("pages", &[&format!("page:{}", page)])
+            .unwrap()
+            .unwrap()[0];
+        assert!(estimate >= *count && estimate <= count + 10_000 / 1000 * 3);
+    }
+}
+
+#[test]
+fn test_countmin_merge() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store
+        .countmin_incr("monday", vec![("a".to_string(), 3)])
+        .unwrap();
+    store
+        .countmin_incr("tuesday", vec![("a".to_string(), 2), ("b".to_string(), 1)])
+        .unwrap();
+    store
+        .countmin_merge("week", &["monday", "tuesday"])
+        .unwrap();
+    // The destination isn't counted twice when listed in the keys.
+    store.countmin_merge("week", &["week"]).unwrap();
+    assert_eq!(
+        store.countmin_query("week", &["a", "b"]).unwrap().unwrap(),
+        vec![5, 1]
+    );
+
+    store.countmin_reserve("other", 10, 2).unwrap();
+    assert!(store.countmin_merge("week", &["other"]).is_err());
+    store.set_string("string", "abc".to_string(), None);
+    assert!(store.countmin_merge("week", &["string"]).is_err());
+}
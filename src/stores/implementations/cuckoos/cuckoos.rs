@@ -0,0 +1,86 @@
+use crate::stores::{
+    cuckoo::CuckooFilter, errors::ValueError, implementations::sketches::cuckoo_error,
+    store::KeyValueStore,
+};
+
+/// Capacity of the Cuckoo filters created on first add.
+const DEFAULT_CAPACITY: u64 = 1024;
+
+impl KeyValueStore {
+    /// Creates an empty Cuckoo filter (with the default time to live of the store),
+    /// and returns false if the key already exists. See `CuckooFilter::new` for the capacity.
+    pub fn cuckoo_reserve(&mut self, key: &str, capacity: u64) -> Result<bool, ValueError> {
+        self._sketch_reserve(key, CuckooFilter::new(capacity))
+    }
+
+    /// Adds elements to the Cuckoo filter of a key (creating it if needed),
+    /// even the ones already present. Stops with a `FilterFull` error at the first element
+    /// the filter can't take, the previous ones staying added.
+    pub fn cuckoo_add(&mut self, key: &str, elements: &[&str]) -> Result<(), ValueError> {
+        let filter = self._get_or_create_mut_sketch(key, || CuckooFilter::new(DEFAULT_CAPACITY))?;
+        for element in elements {
+            filter.add(element).map_err(cuckoo_error)?;
+        }
+        Ok(())
+    }
+
+    /// Adds the elements that don't seem present to the Cuckoo filter of a key (creating it
+    /// if needed), and returns for each element whether it was added.
+    pub fn cuckoo_add_if_absent(
+        &mut self,
+        key: &str,
+        elements: &[&str],
+    ) -> Result<Vec<bool>, ValueError> {
+        let filter = self._get_or_create_mut_sketch(key, || CuckooFilter::new(DEFAULT_CAPACITY))?;
+        elements
+            .iter()
+            .map(|element| filter.add_if_absent(element).map_err(cuckoo_error))
+            .collect()
+    }
+
+    /// Checks for each element whether it may be in the Cuckoo filter of a key.
+    /// False positives are possible, false negatives aren't.
+    pub fn cuckoo_contains(
+        &self,
+        key: &str,
+        elements: &[&str],
+    ) -> Option<Result<Vec<bool>, ValueError>> {
+        Some(
+            self._get_or_none_if_expired(key)?
+                .get_value_as_cuckoo()
+                .map(|filter| {
+                    elements
+                        .iter()
+                        .map(|element| filter.contains(element))
+                        .collect()
+                }),
+        )
+    }
+
+    /// Estimates how many times an element was added to the Cuckoo filter of a key,
+    /// never less than the actual number.
+    pub fn cuckoo_count(&self, key: &str, element: &str) -> Option<Result<usize, ValueError>> {
+        Some(
+            self._get_or_none_if_expired(key)?
+                .get_value_as_cuckoo()
+                .map(|filter| filter.count(element)),
+        )
+    }
+
+    /// Removes one copy of an element from the Cuckoo filter of a key,
+    /// and returns whether it was present.
+    pub fn cuckoo_remove(&mut self, key: &str, element: &str) -> Option<Result<bool, ValueError>> {
+        Some(
+            self._get_mut_or_none_if_expired(key)?
+                .get_value_as_mut_cuckoo()
+                .map(|filter| filter.remove(element)),
+        )
+    }
+
+    /// Merges the Cuckoo filters of the given keys into the Cuckoo filter of the destination
+    /// key (created from the first of them if needed), which then contains the elements of all
+    /// of them. Missing keys are ignored, and all the filters must have the same capacity.
+    pub fn cuckoo_merge(&mut self, destination: &str, keys: &[&str]) -> Result<(), ValueError> {
+        self._sketch_merge::<CuckooFilter>(destination, keys)
+    }
+}
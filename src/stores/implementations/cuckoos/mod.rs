@@ -0,0 +1,2 @@
+mod cuckoos;
+mod tests;
@@ -0,0 +1,98 @@
+use crate::stores::{store::KeyValueStore, types::ValueKind};
+
+#[test]
+fn test_cuckoo_add_contains_remove() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert!(store.cuckoo_reserve("seen", 100).unwrap());
+    assert!(!store.cuckoo_reserve("seen", 100).unwrap());
+    assert!(store.cuckoo_reserve("bad", 0).is_err());
+    assert!(store.cuckoo_reserve("bad", u64::MAX).is_err());
+    assert_eq!(store.get_kind("seen"), Some(ValueKind::Cuckoo));
+
+    store.cuckoo_add("seen", &["a", "b", "a"]).unwrap();
+    assert_eq!(store.cuckoo_count("seen", "a").unwrap().unwrap(), 2);
+    assert_eq!(
+        store.cuckoo_add_if_absent("seen", &["a", "c"]).unwrap(),
+        vec![false, true]
+    );
+    assert_eq!(
+        store
+            .cuckoo_contains("seen", &["a", "b", "c", "d"])
+            .unwrap()
+            .unwrap(),
+        vec![true, true, true, false]
+    );
+
+    assert!(store.cuckoo_remove("seen", "a").unwrap().unwrap());
+    assert!(store.cuckoo_remove("seen", "a").unwrap().unwrap());
+    assert!(!store.cuckoo_remove("seen", "a").unwrap().unwrap());
+    assert_eq!(
+        store.cuckoo_contains("seen", &["a", "b"]).unwrap().unwrap(),
+        vec![false, true]
+    );
+    assert!(store.cuckoo_remove("nope", "a").is_none());
+
+    store.set_string("string", "abc".to_string(), None);
+    assert!(store.cuckoo_add("string", &["a"]).is_err());
+}
+
+#[test]
+fn test_cuckoo_growth_and_merge() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.cuckoo_reserve("first", 256).unwrap();
+    store.cuckoo_reserve("second", 256).unwrap();
+    // Far more elements than the capacity, new layers are added as needed.
+    let elements: Vec<String> = (0..1000).map(|idx| format!("element:{}", idx)).collect();
+    let elements: Vec<&str> = elements.iter().map(String::as_str).collect();
+    store.cuckoo_add("first", &elements[..500]).unwrap();
+    store.cuckoo_add("second", &elements[500..]).unwrap();
+
+    store.cuckoo_merge("first", &["second"]).unwrap();
+    assert!(store
+        .cuckoo_contains("first", &elements)
+        .unwrap()
+        .unwrap()
+        .into_iter()
+        .all(|contains| contains));
+    for element in elements.iter() {
+        assert!(store.cuckoo_remove("first", element).unwrap().unwrap());
+    }
+    assert!(store
+        .cuckoo_contains("first", &elements)
+        .unwrap()
+        .unwrap()
+        .into_iter()
+        .all(|contains| !contains));
+
+    store.cuckoo_reserve("other", 1000).unwrap();
+    assert!(store.cuckoo_merge("first", &["other"]).is_err());
+
+    // A full filter refuses elements instead of growing, and keeps the ones it holds.
+    store.cuckoo_reserve("small", 4).unwrap();
+    store.cuckoo_add("small", &elements[..32]).unwrap();
+    assert!(store.cuckoo_add("small", &elements[32..33]).is_err());
+    assert!(store
+        .cuckoo_contains("small", &elements[..32])
+        .unwrap()
+        .unwrap()
+        .into_iter()
+        .all(|contains| contains));
+}
+
+#[test]
+fn test_cuckoo_repeated_adds() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.cuckoo_reserve("seen", 1024).unwrap();
+    let size = store.estimated_memory_usage();
+    store.cuckoo_add("seen", &["a"; 8]).unwrap();
+    assert!(store.cuckoo_add("seen", &["a"]).is_err());
+    for _ in 0..800 {
+        assert!(store.cuckoo_add("seen", &["a"]).is_err());
+    }
+    assert_eq!(store.cuckoo_count("seen", "a").unwrap().unwrap(), 8);
+    assert_eq!(store.estimated_memory_usage(), size);
+
+    // Removing a copy makes room for another one.
+    assert!(store.cuckoo_remove("seen", "a").unwrap().unwrap());
+    store.cuckoo_add("seen", &["a"]).unwrap();
+}
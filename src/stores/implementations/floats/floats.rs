@@ -0,0 +1,53 @@
+use crate::stores::{
+    errors::{TypeConversionError, ValueError},
+    store::KeyValueStore,
+    types::ValueType,
+    value_entry::ValueEntry,
+};
+
+impl KeyValueStore {
+    /// Inserts a Key-Value(in f64 type) pair in the KeyValueStore
+    pub fn set_f64(&mut self, key: &str, value: f64, ttl: Option<u64>) {
+        let expiration = self._get_expiration_instant(ttl);
+        let value_entry = ValueEntry::from_f64(value, expiration);
+        self._insert(key, &value_entry);
+    }
+
+    /// Gets a Value (converted to f64 type) associated to the Key in the KeyValueStore
+    pub fn get_f64(&self, key: &str) -> Option<Result<f64, ValueError>> {
+        self._get_or_none_if_expired(key)
+            .map(|value_entry| value_entry.get_value_as_f64())
+    }
+
+    /// Removes the Key-Value pair for the given Key in the KeyValueStore
+    /// and returns the Value (converted to f64 type)
+    pub fn pop_f64(&mut self, key: &str) -> Option<Result<f64, ValueError>> {
+        self._remove_and_none_if_expired(key)
+            .map(|value_entry| value_entry.get_value_as_f64())
+    }
+
+    /// Increments the value associated to a key by `delta` (which may be negative), turning it
+    /// into a float counter. A missing key starts at 0, with the default time to live of the store.
+    /// Fails if the result is NaN or infinite.
+    pub fn incr_by_float(&mut self, key: &str, delta: f64) -> Result<f64, ValueError> {
+        let old_value = match self._get_or_none_if_expired(key) {
+            Some(value_entry) => value_entry.get_value_as_f64()?,
+            None => 0.0,
+        };
+        let updated_value = old_value + delta;
+        if !updated_value.is_finite() {
+            return Err(ValueError::TypeConversionError(
+                TypeConversionError::NonFiniteFloat,
+            ));
+        }
+        match self._get_mut_or_none_if_expired(key) {
+            Some(value_entry) => {
+                value_entry.value = ValueType::Float64(updated_value);
+                // A String key isn't one anymore, so it leaves the full-text indexes.
+                self._update_indexes(key);
+            }
+            None => self.set_f64(key, updated_value, None),
+        }
+        Ok(updated_value)
+    }
+}
@@ -0,0 +1,2 @@
+mod floats;
+mod tests;
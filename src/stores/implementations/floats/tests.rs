@@ -0,0 +1,39 @@
+use crate::stores::{store::KeyValueStore, types::ValueKind};
+
+#[test]
+fn test_get_set_f64() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.set_f64("ABC", 1.5, Some(5000));
+    assert_eq!(store.get_f64("ABC").unwrap().unwrap(), 1.5);
+    assert_eq!(store.get_string("ABC").unwrap().unwrap(), "1.5".to_string());
+    assert_eq!(store.get_kind("ABC"), Some(ValueKind::Float64));
+
+    store.set_string("DEF", "2.25".to_string(), None);
+    assert_eq!(store.get_f64("DEF").unwrap().unwrap(), 2.25);
+    store.set_i64("GHI", 3, None);
+    assert_eq!(store.get_f64("GHI").unwrap().unwrap(), 3.0);
+
+    assert_eq!(store.pop_f64("ABC").unwrap().unwrap(), 1.5);
+    assert!(store.get_f64("ABC").is_none());
+}
+
+#[test]
+fn test_incr_by_float() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert_eq!(store.incr_by_float("ABC", 0.5).unwrap(), 0.5);
+    assert_eq!(store.incr_by_float("ABC", -2.0).unwrap(), -1.5);
+    assert_eq!(store.get_kind("ABC"), Some(ValueKind::Float64));
+
+    // Integer counters become float counters.
+    store.set_i64("DEF", 10, None);
+    assert_eq!(store.incr_by_float("DEF", 0.25).unwrap(), 10.25);
+    assert_eq!(
+        store.get_string("DEF").unwrap().unwrap(),
+        "10.25".to_string()
+    );
+
+    assert!(store.incr_by_float("ABC", f64::MAX).is_ok());
+    assert!(store.incr_by_float("ABC", f64::MAX).is_err());
+    store.set_string("text", "abc".to_string(), None);
+    assert!(store.incr_by_float("text", 1.0).is_err());
+}
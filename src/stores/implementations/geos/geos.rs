@@ -0,0 +1,161 @@
+use std::cmp::Ordering;
+
+use crate::stores::{
+    errors::{TypeConversionError, ValueError},
+    geo::{
+        distance, is_valid_position, GeoCenter, GeoMatch, GeoPosition, GeoSet, GeoShape, GeoSort,
+        GeoUnit,
+    },
+    store::KeyValueStore,
+    value_entry::ValueEntry,
+};
+
+impl KeyValueStore {
+    /// Adds members to the geo index of a key (creating it if needed), or moves them if they
+    /// already exist, and returns how many members were added.
+    /// Nothing is added if any position is out of the range that can be indexed.
+    /// Arguments:
+    /// * `members` - members with their longitude and latitude (in degrees).
+    pub fn geo_add(
+        &mut self,
+        key: &str,
+        members: Vec<(String, f64, f64)>,
+    ) -> Result<usize, ValueError> {
+        if members
+            .iter()
+            .any(|(_, longitude, latitude)| !is_valid_position(*longitude, *latitude))
+        {
+            return Err(ValueError::TypeConversionError(
+                TypeConversionError::InvalidCoordinates,
+            ));
+        }
+        if self._get_or_none_if_expired(key).is_none() {
+            let expiration = self._get_expiration_instant(None);
+            self._insert(key, &ValueEntry::from_geo(GeoSet::new(), expiration));
+        }
+        let geo = match self._get_mut_or_none_if_expired(key) {
+            Some(value_entry) => value_entry.get_value_as_mut_geo()?,
+            None => unreachable!("the geo index was just created"),
+        };
+        Ok(members
+            .into_iter()
+            .filter(|(member, longitude, latitude)| {
+                geo.insert(member.to_owned(), *longitude, *latitude)
+            })
+            .count())
+    }
+
+    /// Removes members from the geo index of a key, and returns how many were removed.
+    /// The key is removed once its last member is.
+    pub fn geo_remove(&mut self, key: &str, members: &[&str]) -> Option<Result<usize, ValueError>> {
+        let geo = match self
+            ._get_mut_or_none_if_expired(key)?
+            .get_value_as_mut_geo()
+        {
+            Ok(geo) => geo,
+            Err(e) => return Some(Err(e)),
+        };
+        let removed = members.iter().filter(|member| geo.remove(member)).count();
+        if geo.len() == 0 {
+            self._data_remove(key);
+        }
+        Some(Ok(removed))
+    }
+
+    /// Gets the positions (longitude, latitude) of members of the geo index of a key,
+    /// None for the members that don't exist.
+    /// Positions are stored as geohashes, so they are only precise to a fraction of a meter.
+    pub fn geo_position(
+        &self,
+        key: &str,
+        members: &[&str],
+    ) -> Option<Result<Vec<Option<GeoPosition>>, ValueError>> {
+        Some(
+            self._get_or_none_if_expired(key)?
+                .get_value_as_geo()
+                .map(|geo| members.iter().map(|member| geo.position(member)).collect()),
+        )
+    }
+
+    /// Gets the distance between two members of the geo index of a key,
+    /// None if either member doesn't exist.
+    pub fn geo_distance(
+        &self,
+        key: &str,
+        member: &str,
+        other_member: &str,
+        unit: GeoUnit,
+    ) -> Option<Result<Option<f64>, ValueError>> {
+        Some(
+            self._get_or_none_if_expired(key)?
+                .get_value_as_geo()
+                .map(|geo| {
+                    let from = geo.position(member)?;
+                    let to = geo.position(other_member)?;
+                    Some(distance(from, to) / unit.meters())
+                }),
+        )
+    }
+
+    /// Finds the members of the geo index of a key within a radius or box around a member
+    /// or a point, along with their distance to the center and their position.
+    /// Returns an empty result if the center is a member that doesn't exist.
+    /// Arguments:
+    /// * `shape` - the radius, or width and height of the box, in `unit`.
+    /// * `unit` - unit of the shape and of the distances returned.
+    /// * `sort` - order of the results by distance, unordered if None.
+    /// * `limit` - maximum number of results, the closest ones if the results are sorted.
+    pub fn geo_search(
+        &self,
+        key: &str,
+        center: &GeoCenter,
+        shape: GeoShape,
+        unit: GeoUnit,
+        sort: Option<GeoSort>,
+        limit: Option<usize>,
+    ) -> Option<Result<Vec<GeoMatch>, ValueError>> {
+        let geo = match self._get_or_none_if_expired(key)?.get_value_as_geo() {
+            Ok(geo) => geo,
+            Err(e) => return Some(Err(e)),
+        };
+        let center = match center {
+            GeoCenter::Member(member) => match geo.position(member) {
+                Some(position) => position,
+                None => return Some(Ok(vec![])),
+            },
+            GeoCenter::Point(longitude, latitude) => {
+                if !is_valid_position(*longitude, *latitude) {
+                    return Some(Err(ValueError::TypeConversionError(
+                        TypeConversionError::InvalidCoordinates,
+                    )));
+                }
+                (*longitude, *latitude)
+            }
+        };
+        let shape = match shape {
+            GeoShape::Radius(radius) => GeoShape::Radius(radius * unit.meters()),
+            GeoShape::Box(width, height) => {
+                GeoShape::Box(width * unit.meters(), height * unit.meters())
+            }
+        };
+
+        let mut matches = geo.search(center, shape);
+        let by_distance = |a: &GeoMatch, b: &GeoMatch| {
+            a.distance
+                .partial_cmp(&b.distance)
+                .unwrap_or(Ordering::Equal)
+        };
+        match sort {
+            Some(GeoSort::Ascending) => matches.sort_by(by_distance),
+            Some(GeoSort::Descending) => matches.sort_by(|a, b| by_distance(b, a)),
+            None => {}
+        }
+        if let Some(limit) = limit {
+            matches.truncate(limit);
+        }
+        for geo_match in matches.iter_mut() {
+            geo_match.distance /= unit.meters();
+        }
+        Some(Ok(matches))
+    }
+}
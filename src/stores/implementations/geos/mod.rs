@@ -0,0 +1,2 @@
+mod geos;
+mod tests;
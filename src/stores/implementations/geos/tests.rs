@@ -0,0 +1,210 @@
+use crate::stores::{
+    geo::{distance, GeoCenter, GeoShape, GeoSort, GeoUnit},
+    store::KeyValueStore,
+    types::ValueKind,
+};
+
+fn sicily() -> KeyValueStore {
+    let mut store = KeyValueStore::new("new_store", None);
+    let added = store
+        .geo_add(
+            "Sicily",
+            vec![
+                ("Palermo".to_string(), 13.361389, 38.115556),
+                ("Catania".to_string(), 15.087269, 37.502669),
+                ("Agrigento".to_string(), 13.583333, 37.316667),
+            ],
+        )
+        .unwrap();
+    assert_eq!(added, 3);
+    store
+}
+
+#[test]
+fn test_geo_add_position_distance() {
+    let mut store = sicily();
+    assert_eq!(store.get_kind("Sicily"), Some(ValueKind::Geo));
+
+    let positions = store
+        .geo_position("Sicily", &["Palermo", "Nope"])
+        .unwrap()
+        .unwrap();
+    let (longitude, latitude) = positions[0].unwrap();
+    assert!((longitude - 13.361389).abs() < 1e-5 && (latitude - 38.115556).abs() < 1e-5);
+    assert_eq!(positions[1], None);
+    assert!(store.geo_position("nope", &["Palermo"]).is_none());
+
+    let distance = store
+        .geo_distance("Sicily", "Palermo", "Catania", GeoUnit::Kilometers)
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!((distance - 166.2742).abs() < 0.01);
+    let distance = store
+        .geo_distance("Sicily", "Palermo", "Catania", GeoUnit::Miles)
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!((distance - 103.3182).abs() < 0.01);
+    assert_eq!(
+        store
+            .geo_distance("Sicily", "Palermo", "Nope", GeoUnit::Meters)
+            .unwrap()
+            .unwrap(),
+        None
+    );
+
+    // Moving a member doesn't add it again.
+    assert_eq!(
+        store
+            .geo_add("Sicily", vec![("Palermo".to_string(), 13.4, 38.1)])
+            .unwrap(),
+        0
+    );
+    assert!(store
+        .geo_add("Sicily", vec![("Pole".to_string(), 0.0, 89.0)])
+        .is_err());
+    assert!(store
+        .geo_add("Sicily", vec![("Nowhere".to_string(), 181.0, 0.0)])
+        .is_err());
+
+    assert_eq!(
+        store
+            .geo_remove("Sicily", &["Palermo", "Catania", "Nope"])
+            .unwrap()
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        store.geo_remove("Sicily", &["Agrigento"]).unwrap().unwrap(),
+        1
+    );
+    assert!(!store.contains_key("Sicily"));
+
+    store.set_string("string", "abc".to_string(), None);
+    assert!(store
+        .geo_add("string", vec![("a".to_string(), 0.0, 0.0)])
+        .is_err());
+    assert!(store.geo_position("string", &["a"]).unwrap().is_err());
+}
+
+#[test]
+fn test_geo_search() {
+    let store = sicily();
+    let search = |center: GeoCenter, shape: GeoShape, sort: Option<GeoSort>, limit| {
+        store
+            .geo_search("Sicily", &center, shape, GeoUnit::Kilometers, sort, limit)
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|geo_match| (geo_match.member, geo_match.distance.round() as u64))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        search(
+            GeoCenter::Point(15.0, 37.0),
+            GeoShape::Radius(200.0),
+            Some(GeoSort::Ascending),
+            None
+        ),
+        vec![
+            ("Catania".to_string(), 56),
+            ("Agrigento".to_string(), 130),
+            ("Palermo".to_string(), 190),
+        ]
+    );
+    assert_eq!(
+        search(
+            GeoCenter::Point(15.0, 37.0),
+            GeoShape::Radius(200.0),
+            Some(GeoSort::Descending),
+            Some(1)
+        ),
+        vec![("Palermo".to_string(), 190)]
+    );
+    let near_palermo = search(
+        GeoCenter::Member("Palermo".to_string()),
+        GeoShape::Radius(100.0),
+        Some(GeoSort::Ascending),
+        None,
+    );
+    assert_eq!(near_palermo[0], ("Palermo".to_string(), 0));
+    assert_eq!(near_palermo[1].0, "Agrigento");
+    assert_eq!(near_palermo.len(), 2);
+    assert!(search(
+        GeoCenter::Member("Nope".to_string()),
+        GeoShape::Radius(100.0),
+        None,
+        None
+    )
+    .is_empty());
+
+    assert_eq!(
+        search(
+            GeoCenter::Point(15.0, 37.0),
+            GeoShape::Box(400.0, 400.0),
+            None,
+            None
+        )
+        .len(),
+        3
+    );
+    assert_eq!(
+        search(
+            GeoCenter::Point(15.0, 37.0),
+            GeoShape::Box(100.0, 400.0),
+            None,
+            None
+        ),
+        vec![("Catania".to_string(), 56)]
+    );
+}
+
+#[test]
+fn test_geo_search_matches_brute_force() {
+    let mut store = KeyValueStore::new("new_store", None);
+    let mut members = vec![];
+    for idx in 0..2000 {
+        // Spread the points over a grid crossing the antimeridian.
+        let longitude = ((idx % 50) as f64 * 0.37 + 170.0 + 180.0) % 360.0 - 180.0;
+        let latitude = (idx / 50) as f64 * 0.41 - 8.0;
+        members.push((format!("point:{}", idx), longitude, latitude));
+    }
+    store.geo_add("points", members.to_owned()).unwrap();
+
+    for (center, radius) in [
+        ((179.9, 0.0), 150.0),
+        ((-178.0, 5.0), 40.0),
+        ((175.0, -7.5), 600.0),
+    ] {
+        let mut found: Vec<String> = store
+            .geo_search(
+                "points",
+                &GeoCenter::Point(center.0, center.1),
+                GeoShape::Radius(radius),
+                GeoUnit::Kilometers,
+                None,
+                None,
+            )
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|geo_match| geo_match.member)
+            .collect();
+        found.sort();
+        let mut expected: Vec<String> = members
+            .iter()
+            .filter(|(member, _, _)| {
+                let distance = store.geo_position("points", &[member]).unwrap().unwrap()[0]
+                    .map(|position| distance(center, position))
+                    .unwrap();
+                distance <= radius * 1000.0
+            })
+            .map(|(member, _, _)| member.to_owned())
+            .collect();
+        expected.sort();
+        assert!(!expected.is_empty());
+        assert_eq!(found, expected);
+    }
+}
@@ -1,5 +1,106 @@
-use crate::stores::{errors::ValueError, store::KeyValueStore, value_entry::ValueEntry};
-use std::collections::HashMap;
+use crate::stores::{
+    cursor::{CursorMap, ScanBatch},
+    errors::{TypeConversionError, TypeConversionImpossible, ValueError},
+    store::{KeyValueStore, DEFAULT_SCAN_COUNT},
+    types::{FieldTtl, ValueType},
+    value_entry::ValueEntry,
+};
+use crate::utils::glob::glob_match;
+use rand::seq::IteratorRandom;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Read-only view over the fields of a hmap that aren't expired.
+struct LiveHmap<'a> {
+    hmap: Cow<'a, CursorMap>,
+    field_expirations: Option<&'a HashMap<String, Instant>>,
+    now: Instant,
+}
+
+impl LiveHmap<'_> {
+    fn is_live(&self, hmap_key: &str) -> bool {
+        match self
+            .field_expirations
+            .and_then(|expirations| expirations.get(hmap_key))
+        {
+            Some(expiration) => self.now < *expiration,
+            None => true,
+        }
+    }
+
+    fn get(&self, hmap_key: &str) -> Option<&String> {
+        self.hmap.get(hmap_key).filter(|_| self.is_live(hmap_key))
+    }
+
+    fn contains_key(&self, hmap_key: &str) -> bool {
+        self.get(hmap_key).is_some()
+    }
+
+    fn len(&self) -> usize {
+        let expired = match self.field_expirations {
+            Some(expirations) => expirations
+                .values()
+                .filter(|expiration| self.now >= **expiration)
+                .count(),
+            None => 0,
+        };
+        self.hmap.len() - expired
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.hmap.iter().filter(|(k, _)| self.is_live(k))
+    }
+
+    fn into_owned(self) -> HashMap<String, String> {
+        match self.field_expirations {
+            Some(expirations) if !expirations.is_empty() => self
+                .iter()
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .collect(),
+            _ => (**self.hmap).to_owned(),
+        }
+    }
+}
+
+/// Mutable view over a hmap whose expired fields were removed.
+struct LiveHmapMut<'a> {
+    hmap: &'a mut CursorMap,
+    field_expirations: &'a mut HashMap<String, Instant>,
+}
+
+impl LiveHmapMut<'_> {
+    /// Sets the value of a hmap_key, dropping its time to live.
+    fn insert(&mut self, hmap_key: String, value: String) {
+        self.field_expirations.remove(&hmap_key);
+        self.hmap.insert(hmap_key, value);
+    }
+
+    /// Sets the value of a hmap_key, keeping its time to live.
+    fn update(&mut self, hmap_key: String, value: String) {
+        self.hmap.insert(hmap_key, value);
+    }
+
+    fn remove_entry(&mut self, hmap_key: &str) -> Option<(String, String)> {
+        self.field_expirations.remove(hmap_key);
+        self.hmap.remove_entry(hmap_key)
+    }
+
+    /// Time to live of a hmap_key, the expired fields being already cleared.
+    fn field_ttl(&self, hmap_key: &str, now: Instant) -> FieldTtl {
+        if !self.hmap.contains_key(hmap_key) {
+            return FieldTtl::NoField;
+        }
+        match self.field_expirations.get(hmap_key) {
+            Some(expiration) => {
+                FieldTtl::Expires(expiration.saturating_duration_since(now).as_millis() as u64)
+            }
+            None => FieldTtl::Persistent,
+        }
+    }
+}
 
 impl KeyValueStore {
     /// Inserts a Key-Value(in HashMap<(String, String)> type) pair in the KeyValueStore
@@ -11,42 +112,187 @@ impl KeyValueStore {
 
     /// Gets a Value (converted to set<String> type) associated to the Key in the KeyValueStore
     pub fn get_hmap(&self, key: &str) -> Option<Result<HashMap<String, String>, ValueError>> {
+        self._get_hmap_ref(key)
+            .map(|result| result.map(|hmap| hmap.into_owned()))
+    }
+
+    /// Borrows the hmap associated to the Key, only values of other types get converted (and copied).
+    fn _get_hmap_ref(&self, key: &str) -> Option<Result<LiveHmap<'_>, ValueError>> {
         match self._get_or_none_if_expired(key) {
-            Some(value_entry) => Some(value_entry.get_value_as_hmap()),
-            _ => None,
+            Some(ValueEntry {
+                value: ValueType::HashMap(hmap),
+                field_expirations,
+                ..
+            }) => Some(Ok(LiveHmap {
+                hmap: Cow::Borrowed(hmap),
+                field_expirations: Some(field_expirations),
+                now: Instant::now(),
+            })),
+            Some(value_entry) => Some(value_entry.get_value_as_hmap().map(|hmap| LiveHmap {
+                hmap: Cow::Owned(hmap.into_iter().collect()),
+                field_expirations: None,
+                now: Instant::now(),
+            })),
+            None => None,
         }
     }
 
-    fn _get_mut_hmap(
-        &mut self,
-        key: &str,
-    ) -> Option<Result<&mut HashMap<String, String>, ValueError>> {
+    fn _get_mut_hmap(&mut self, key: &str) -> Option<Result<LiveHmapMut<'_>, ValueError>> {
         match self._get_mut_or_none_if_expired(key) {
-            Some(value_entry) => Some(value_entry.get_value_as_mut_hmap()),
+            Some(value_entry) => {
+                value_entry.clear_expired_fields(Instant::now());
+                let ValueEntry {
+                    value,
+                    field_expirations,
+                    ..
+                } = value_entry;
+                match value {
+                    ValueType::HashMap(hmap) => Some(Ok(LiveHmapMut {
+                        hmap,
+                        field_expirations,
+                    })),
+                    _ => Some(Err(ValueError::TypeConversionImpossible(
+                        TypeConversionImpossible::AsMutable,
+                    ))),
+                }
+            }
             None => None,
         }
     }
 
-    /// Set a key-value pair in the hash map associated with a key
+    /// Gets the hmap associated with a key, creating an empty one (with the default
+    /// time to live of the store) if the key doesn't exist. Only call it to write a field,
+    /// so that no empty hmap is left behind.
+    fn _get_or_create_mut_hmap(&mut self, key: &str) -> Result<LiveHmapMut<'_>, ValueError> {
+        if self._get_or_none_if_expired(key).is_none() {
+            let expiration = self._get_expiration_instant(None);
+            self._insert(key, &ValueEntry::from_hashmap(HashMap::new(), expiration));
+        }
+        match self._get_mut_hmap(key) {
+            Some(result) => result,
+            None => unreachable!("the hmap was just created"),
+        }
+    }
+
+    /// Set a key-value pair in the hash map associated with a key, creating the hmap if needed.
     /// Then return the size of the hmap.
     pub fn hmap_insert(
         &mut self,
         key: &str,
         kv_pair: (String, String),
-    ) -> Option<Result<usize, ValueError>> {
-        match self._get_mut_hmap(key) {
-            Some(Ok(hmap)) => {
-                hmap.insert(kv_pair.0, kv_pair.1);
-                Some(Ok(hmap.len()))
+    ) -> Result<usize, ValueError> {
+        let result = self._get_or_create_mut_hmap(key).map(|mut hmap| {
+            hmap.insert(kv_pair.0, kv_pair.1);
+            hmap.hmap.len()
+        });
+        self._update_indexes(key);
+        result
+    }
+
+    /// Set several key-value pairs in the hash map associated with a key, creating the hmap if needed.
+    /// Then return the size of the hmap.
+    pub fn hmap_insert_many(
+        &mut self,
+        key: &str,
+        kv_pairs: Vec<(String, String)>,
+    ) -> Result<usize, ValueError> {
+        if kv_pairs.is_empty() {
+            // Nothing to write, don't create the key.
+            return Ok(self.hmap_size(key).transpose()?.unwrap_or(0));
+        }
+        let result = self._get_or_create_mut_hmap(key).map(|mut hmap| {
+            for (hmap_key, value) in kv_pairs {
+                hmap.insert(hmap_key, value);
             }
-            Some(Err(e)) => Some(Err(e)),
-            None => None,
+            hmap.hmap.len()
+        });
+        self._update_indexes(key);
+        result
+    }
+
+    /// Set a key-value pair in the hash map associated with a key, only if the hmap_key is absent.
+    /// Returns whether the pair was inserted.
+    pub fn hmap_insert_if_absent(
+        &mut self,
+        key: &str,
+        kv_pair: (String, String),
+    ) -> Result<bool, ValueError> {
+        let result = self._get_or_create_mut_hmap(key).map(|mut hmap| {
+            if hmap.hmap.contains_key(&kv_pair.0) {
+                return false;
+            }
+            hmap.insert(kv_pair.0, kv_pair.1);
+            true
+        });
+        self._update_indexes(key);
+        result
+    }
+
+    /// Increments the integer value of a hmap_key by `by` (which may be negative),
+    /// a missing hmap_key (or hmap) starts at 0. Returns the updated value.
+    pub fn hmap_incr(&mut self, key: &str, hmap_key: String, by: i64) -> Result<i64, ValueError> {
+        let result = self._hmap_incr(key, hmap_key, by);
+        self._update_indexes(key);
+        result
+    }
+
+    fn _hmap_incr(&mut self, key: &str, hmap_key: String, by: i64) -> Result<i64, ValueError> {
+        // The hmap is only created once the updated value is known to be valid.
+        let old_value = match self.hmap_get(key, hmap_key.to_owned()).transpose()? {
+            Some(value) => value.parse::<i64>().map_err(|e| {
+                ValueError::TypeConversionError(TypeConversionError::ParseIntError(e))
+            })?,
+            None => 0,
+        };
+        let updated_value = old_value
+            .checked_add(by)
+            .ok_or(ValueError::TypeConversionError(
+                TypeConversionError::IntegerOverflow,
+            ))?;
+        self._get_or_create_mut_hmap(key)?
+            .update(hmap_key, updated_value.to_string());
+        Ok(updated_value)
+    }
+
+    /// Increments the float value of a hmap_key by `by` (which may be negative),
+    /// a missing hmap_key (or hmap) starts at 0. Returns the updated value.
+    pub fn hmap_incr_float(
+        &mut self,
+        key: &str,
+        hmap_key: String,
+        by: f64,
+    ) -> Result<f64, ValueError> {
+        let result = self._hmap_incr_float(key, hmap_key, by);
+        self._update_indexes(key);
+        result
+    }
+
+    fn _hmap_incr_float(
+        &mut self,
+        key: &str,
+        hmap_key: String,
+        by: f64,
+    ) -> Result<f64, ValueError> {
+        let old_value = match self.hmap_get(key, hmap_key.to_owned()).transpose()? {
+            Some(value) => value.parse::<f64>().map_err(|e| {
+                ValueError::TypeConversionError(TypeConversionError::ParseFloatError(e))
+            })?,
+            None => 0.0,
+        };
+        let updated_value = old_value + by;
+        if !updated_value.is_finite() {
+            return Err(ValueError::TypeConversionError(
+                TypeConversionError::NonFiniteFloat,
+            ));
         }
+        self._get_or_create_mut_hmap(key)?
+            .update(hmap_key, updated_value.to_string());
+        Ok(updated_value)
     }
 
     /// Gets a value for a provided hmap_key from the hash map associated with the storage key
     pub fn hmap_get(&self, key: &str, hmap_key: String) -> Option<Result<String, ValueError>> {
-        match self.get_hmap(key) {
+        match self._get_hmap_ref(key) {
             Some(Ok(hmap)) => {
                 if let Some(value) = hmap.get(&hmap_key) {
                     Some(Ok(value.to_owned()))
@@ -59,6 +305,52 @@ impl KeyValueStore {
         }
     }
 
+    /// Gets the values for several hmap_keys, None for the hmap_keys that don't exist.
+    pub fn hmap_get_many(
+        &self,
+        key: &str,
+        hmap_keys: &[&str],
+    ) -> Option<Result<Vec<Option<String>>, ValueError>> {
+        match self._get_hmap_ref(key) {
+            Some(Ok(hmap)) => Some(Ok(hmap_keys
+                .iter()
+                .map(|hmap_key| hmap.get(hmap_key).map(|value| value.to_owned()))
+                .collect())),
+            Some(Err(e)) => Some(Err(e)),
+            None => None,
+        }
+    }
+
+    /// Gets the length (in bytes) of the value for a provided hmap_key,
+    /// None if the hmap_key doesn't exist.
+    pub fn hmap_value_len(
+        &self,
+        key: &str,
+        hmap_key: &str,
+    ) -> Option<Result<Option<usize>, ValueError>> {
+        match self._get_hmap_ref(key) {
+            Some(Ok(hmap)) => Some(Ok(hmap.get(hmap_key).map(|value| value.len()))),
+            Some(Err(e)) => Some(Err(e)),
+            None => None,
+        }
+    }
+
+    /// Samples up to `count` distinct (hmap_key, hmap_value) pairs at random.
+    pub fn hmap_random_items(
+        &self,
+        key: &str,
+        count: usize,
+    ) -> Option<Result<Vec<(String, String)>, ValueError>> {
+        match self._get_hmap_ref(key) {
+            Some(Ok(hmap)) => Some(Ok(hmap
+                .iter()
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .choose_multiple(&mut rand::thread_rng(), count))),
+            Some(Err(e)) => Some(Err(e)),
+            None => None,
+        }
+    }
+
     /// Removes a key-value pair for a provided hmap_key from the hash map associated with the storage key
     /// also returns the pair
     pub fn hmap_remove(
@@ -66,17 +358,13 @@ impl KeyValueStore {
         key: &str,
         hmap_key: String,
     ) -> Option<Result<(String, String), ValueError>> {
-        match self._get_mut_hmap(key) {
-            Some(Ok(hmap)) => {
-                if let Some(value) = hmap.remove_entry(&hmap_key) {
-                    Some(Ok(value))
-                } else {
-                    None
-                }
-            }
+        let result = match self._get_mut_hmap(key) {
+            Some(Ok(mut hmap)) => hmap.remove_entry(&hmap_key).map(Ok),
             Some(Err(e)) => Some(Err(e)),
             None => None,
-        }
+        };
+        self._update_indexes(key);
+        result
     }
 
     /// Checks if a hmap_key is present in the hmap associated with a key
@@ -85,7 +373,7 @@ impl KeyValueStore {
         key: &str,
         hmap_key: String,
     ) -> Option<Result<bool, ValueError>> {
-        match self.get_hmap(key) {
+        match self._get_hmap_ref(key) {
             Some(Ok(hmap)) => Some(Ok(hmap.contains_key(&hmap_key))),
             Some(Err(e)) => Some(Err(e)),
             None => None,
@@ -94,7 +382,7 @@ impl KeyValueStore {
 
     /// Gets the size of the hmap associated with a key
     pub fn hmap_size(&self, key: &str) -> Option<Result<usize, ValueError>> {
-        match self.get_hmap(key) {
+        match self._get_hmap_ref(key) {
             Some(Ok(hmap)) => Some(Ok(hmap.len())),
             Some(Err(e)) => Some(Err(e)),
             None => None,
@@ -103,8 +391,8 @@ impl KeyValueStore {
 
     // Gets all the hmap_keys of the hmap associated with a key
     pub fn hmap_keys(&self, key: &str) -> Option<Result<Vec<String>, ValueError>> {
-        match self.get_hmap(key) {
-            Some(Ok(hmap)) => Some(Ok(Vec::from_iter(hmap.keys().map(|item| item.to_owned())))),
+        match self._get_hmap_ref(key) {
+            Some(Ok(hmap)) => Some(Ok(Vec::from_iter(hmap.iter().map(|(k, _)| k.to_owned())))),
             Some(Err(e)) => Some(Err(e)),
             None => None,
         }
@@ -112,10 +400,8 @@ impl KeyValueStore {
 
     // Gets all the hmap_values of the hmap associated with a key
     pub fn hmap_values(&self, key: &str) -> Option<Result<Vec<String>, ValueError>> {
-        match self.get_hmap(key) {
-            Some(Ok(hmap)) => Some(Ok(Vec::from_iter(
-                hmap.values().map(|item| item.to_owned()),
-            ))),
+        match self._get_hmap_ref(key) {
+            Some(Ok(hmap)) => Some(Ok(Vec::from_iter(hmap.iter().map(|(_, v)| v.to_owned())))),
             Some(Err(e)) => Some(Err(e)),
             None => None,
         }
@@ -123,7 +409,7 @@ impl KeyValueStore {
 
     // Gets all the (hmap_keys, hmap_values) pairs of the hmap associated with a key
     pub fn hmap_items(&self, key: &str) -> Option<Result<Vec<(String, String)>, ValueError>> {
-        match self.get_hmap(key) {
+        match self._get_hmap_ref(key) {
             Some(Ok(hmap)) => Some(Ok(Vec::from_iter(
                 hmap.iter().map(|(k, v)| (k.to_owned(), v.to_owned())),
             ))),
@@ -131,4 +417,103 @@ impl KeyValueStore {
             None => None,
         }
     }
+
+    /// Iterates over the fields of the hmap in batches, the same way `scan` iterates over keys.
+    /// Returns the next cursor (0 once the iteration is complete) and the (field, value) pairs
+    /// of the batch whose field matches the glob `pattern`.
+    pub fn hmap_scan(
+        &self,
+        key: &str,
+        cursor: u64,
+        pattern: Option<&str>,
+        count: Option<usize>,
+    ) -> Option<Result<ScanBatch<(String, String)>, ValueError>> {
+        match self._get_hmap_ref(key) {
+            Some(Ok(hmap)) => {
+                let (next_cursor, batch) =
+                    hmap.hmap.scan(cursor, count.unwrap_or(DEFAULT_SCAN_COUNT));
+                let items = batch
+                    .into_iter()
+                    .filter(|(k, _)| hmap.is_live(k))
+                    .filter(|(k, _)| pattern.is_none_or(|pattern| glob_match(pattern, k)))
+                    .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                    .collect();
+                Some(Ok((next_cursor, items)))
+            }
+            Some(Err(e)) => Some(Err(e)),
+            None => None,
+        }
+    }
+
+    /// Sets the time to live (in milliseconds) of a hmap_key, the field is removed once it expires.
+    /// Returns the previous time to live of the hmap_key, nothing is set for `FieldTtl::NoField`.
+    pub fn hmap_set_field_ttl(
+        &mut self,
+        key: &str,
+        hmap_key: &str,
+        ttl: u64,
+    ) -> Option<Result<FieldTtl, ValueError>> {
+        let result = match self._get_mut_hmap(key) {
+            Some(Ok(hmap)) => {
+                let now = Instant::now();
+                let previous = hmap.field_ttl(hmap_key, now);
+                if previous != FieldTtl::NoField {
+                    hmap.field_expirations
+                        .insert(hmap_key.to_owned(), now + Duration::from_millis(ttl));
+                }
+                Some(Ok(previous))
+            }
+            Some(Err(e)) => Some(Err(e)),
+            None => None,
+        };
+        // Expired fields were just cleared, and a zero time to live expires the field.
+        self._update_indexes(key);
+        result
+    }
+
+    /// Gets the remaining time to live (in milliseconds) of a hmap_key.
+    pub fn hmap_get_field_ttl(
+        &self,
+        key: &str,
+        hmap_key: &str,
+    ) -> Option<Result<FieldTtl, ValueError>> {
+        match self._get_hmap_ref(key) {
+            Some(Ok(hmap)) => {
+                if !hmap.contains_key(hmap_key) {
+                    return Some(Ok(FieldTtl::NoField));
+                }
+                let expiration = hmap
+                    .field_expirations
+                    .and_then(|expirations| expirations.get(hmap_key));
+                Some(Ok(match expiration {
+                    Some(expiration) => {
+                        FieldTtl::Expires(expiration.duration_since(hmap.now).as_millis() as u64)
+                    }
+                    None => FieldTtl::Persistent,
+                }))
+            }
+            Some(Err(e)) => Some(Err(e)),
+            None => None,
+        }
+    }
+
+    /// Removes the time to live of a hmap_key, and returns the one it had.
+    pub fn hmap_remove_field_ttl(
+        &mut self,
+        key: &str,
+        hmap_key: &str,
+    ) -> Option<Result<FieldTtl, ValueError>> {
+        let result = match self._get_mut_hmap(key) {
+            Some(Ok(hmap)) => {
+                let previous = hmap.field_ttl(hmap_key, Instant::now());
+                hmap.field_expirations.remove(hmap_key);
+                Some(Ok(previous))
+            }
+            Some(Err(e)) => Some(Err(e)),
+            None => None,
+        };
+        // Expired fields were just cleared.
+        self._update_indexes(key);
+        result
+    }
 }
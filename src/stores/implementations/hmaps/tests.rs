@@ -1,4 +1,4 @@
-use crate::stores::store::KeyValueStore;
+use crate::stores::{store::KeyValueStore, types::FieldTtl};
 
 #[test]
 fn test_insert_get_hmap() {
@@ -8,7 +8,6 @@ fn test_insert_get_hmap() {
     assert_eq!(
         store
             .hmap_insert("ABC", ("A".to_owned(), "B".to_owned()))
-            .unwrap()
             .unwrap(),
         1
     );
@@ -17,7 +16,6 @@ fn test_insert_get_hmap() {
     assert_eq!(
         store
             .hmap_insert("ABC", ("B".to_owned(), "C".to_owned()))
-            .unwrap()
             .unwrap(),
         2
     );
@@ -44,7 +42,6 @@ fn test_hmap_keys_values_items() {
     assert_eq!(
         store
             .hmap_insert("ABC", ("A".to_owned(), "B".to_owned()))
-            .unwrap()
             .unwrap(),
         1
     );
@@ -53,7 +50,6 @@ fn test_hmap_keys_values_items() {
     assert_eq!(
         store
             .hmap_insert("ABC", ("B".to_owned(), "C".to_owned()))
-            .unwrap()
             .unwrap(),
         2
     );
@@ -62,7 +58,6 @@ fn test_hmap_keys_values_items() {
     assert_eq!(
         store
             .hmap_insert("ABC", ("C".to_owned(), "A".to_owned()))
-            .unwrap()
             .unwrap(),
         3
     );
@@ -101,7 +96,6 @@ fn test_remove_hmap() {
     assert_eq!(
         store
             .hmap_insert("ABC", ("A".to_owned(), "B".to_owned()))
-            .unwrap()
             .unwrap(),
         1
     );
@@ -110,7 +104,6 @@ fn test_remove_hmap() {
     assert_eq!(
         store
             .hmap_insert("ABC", ("B".to_owned(), "C".to_owned()))
-            .unwrap()
             .unwrap(),
         2
     );
@@ -139,3 +132,264 @@ fn test_remove_hmap() {
         false
     );
 }
+
+#[test]
+fn test_hmap_scan() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert!(store.hmap_scan("ABC", 0, None, None).is_none());
+
+    store.set_hmap(
+        "ABC",
+        (0..50)
+            .map(|idx| (format!("field:{}", idx), idx.to_string()))
+            .chain([("other".to_owned(), "value".to_owned())])
+            .collect(),
+        None,
+    );
+
+    let mut scanned = vec![];
+    let mut cursor = 0;
+    loop {
+        let (next_cursor, items) = store
+            .hmap_scan("ABC", cursor, Some("field:*"), Some(7))
+            .unwrap()
+            .unwrap();
+        scanned.extend(items);
+        cursor = next_cursor;
+        if cursor == 0 {
+            break;
+        }
+    }
+    scanned.sort();
+    scanned.dedup();
+    assert_eq!(scanned.len(), 50);
+    assert!(scanned.contains(&("field:7".to_owned(), "7".to_owned())));
+
+    store.set_string("DEF", "XYZ".to_owned(), None);
+    assert!(store.hmap_scan("DEF", 0, None, None).unwrap().is_err());
+}
+
+#[test]
+fn test_hmap_multi_and_conditional_operations() {
+    let mut store = KeyValueStore::new("new_store", None);
+
+    // The hmap is created on first insert.
+    assert!(store
+        .hmap_insert_if_absent("ABC", ("A".to_owned(), "1".to_owned()))
+        .unwrap());
+    assert!(!store
+        .hmap_insert_if_absent("ABC", ("A".to_owned(), "2".to_owned()))
+        .unwrap());
+    assert_eq!(
+        store
+            .hmap_insert_many(
+                "ABC",
+                vec![
+                    ("B".to_owned(), "hello".to_owned()),
+                    ("C".to_owned(), "3".to_owned()),
+                ],
+            )
+            .unwrap(),
+        3
+    );
+    assert_eq!(
+        store
+            .hmap_get_many("ABC", &["A", "nope", "B"])
+            .unwrap()
+            .unwrap(),
+        vec![Some("1".to_owned()), None, Some("hello".to_owned())]
+    );
+    assert!(store.hmap_get_many("nope", &["A"]).is_none());
+
+    assert_eq!(store.hmap_value_len("ABC", "B").unwrap().unwrap(), Some(5));
+    assert_eq!(store.hmap_value_len("ABC", "nope").unwrap().unwrap(), None);
+    assert!(store.hmap_value_len("nope", "B").is_none());
+
+    // Writing nothing, or failing to, doesn't create the hmap.
+    assert_eq!(store.hmap_insert_many("GHI", vec![]).unwrap(), 0);
+    assert!(store
+        .hmap_incr_float("GHI", "A".to_owned(), f64::INFINITY)
+        .is_err());
+    assert!(!store.contains_key("GHI"));
+
+    let mut items = store.hmap_random_items("ABC", 10).unwrap().unwrap();
+    items.sort();
+    assert_eq!(items.len(), 3);
+    assert_eq!(store.hmap_random_items("ABC", 2).unwrap().unwrap().len(), 2);
+
+    store.set_string("DEF", "XYZ".to_owned(), None);
+    assert!(store
+        .hmap_insert("DEF", ("A".to_owned(), "B".to_owned()))
+        .is_err());
+}
+
+#[test]
+fn test_hmap_increments() {
+    let mut store = KeyValueStore::new("new_store", None);
+
+    assert_eq!(store.hmap_incr("ABC", "count".to_owned(), 5).unwrap(), 5);
+    assert_eq!(store.hmap_incr("ABC", "count".to_owned(), -7).unwrap(), -2);
+    assert_eq!(
+        store.hmap_get("ABC", "count".to_owned()).unwrap().unwrap(),
+        "-2"
+    );
+
+    store
+        .hmap_insert("ABC", ("max".to_owned(), i64::MAX.to_string()))
+        .unwrap();
+    assert!(store.hmap_incr("ABC", "max".to_owned(), 1).is_err());
+    store
+        .hmap_insert("ABC", ("text".to_owned(), "abc".to_owned()))
+        .unwrap();
+    assert!(store.hmap_incr("ABC", "text".to_owned(), 1).is_err());
+
+    assert_eq!(
+        store
+            .hmap_incr_float("ABC", "float".to_owned(), 1.5)
+            .unwrap(),
+        1.5
+    );
+    assert_eq!(
+        store
+            .hmap_incr_float("ABC", "count".to_owned(), 0.5)
+            .unwrap(),
+        -1.5
+    );
+    assert_eq!(
+        store.hmap_get("ABC", "float".to_owned()).unwrap().unwrap(),
+        "1.5"
+    );
+    assert!(store
+        .hmap_incr_float("ABC", "float".to_owned(), f64::INFINITY)
+        .is_err());
+    assert!(store
+        .hmap_incr_float("ABC", "text".to_owned(), 1.0)
+        .is_err());
+}
+
+#[test]
+fn test_hmap_field_expiration() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.set_hmap(
+        "ABC",
+        vec![
+            ("A".to_owned(), "1".to_owned()),
+            ("B".to_owned(), "2".to_owned()),
+            ("C".to_owned(), "3".to_owned()),
+        ],
+        None,
+    );
+
+    assert_eq!(
+        store.hmap_set_field_ttl("ABC", "A", 0).unwrap().unwrap(),
+        FieldTtl::Persistent
+    );
+    assert_eq!(
+        store.hmap_set_field_ttl("ABC", "B", 5000).unwrap().unwrap(),
+        FieldTtl::Persistent
+    );
+    assert_eq!(
+        store
+            .hmap_set_field_ttl("ABC", "nope", 5000)
+            .unwrap()
+            .unwrap(),
+        FieldTtl::NoField
+    );
+    assert!(store.hmap_set_field_ttl("nope", "A", 5000).is_none());
+
+    // Expired fields are hidden from reads.
+    assert!(store.hmap_get("ABC", "A".to_owned()).is_none());
+    assert!(!store
+        .hmap_contains_key("ABC", "A".to_owned())
+        .unwrap()
+        .unwrap());
+    assert_eq!(store.hmap_size("ABC").unwrap().unwrap(), 2);
+    let mut keys = store.hmap_keys("ABC").unwrap().unwrap();
+    keys.sort();
+    assert_eq!(keys, vec!["B", "C"]);
+    assert_eq!(store.hmap_items("ABC").unwrap().unwrap().len(), 2);
+    assert_eq!(store.get_hmap("ABC").unwrap().unwrap().len(), 2);
+
+    let FieldTtl::Expires(ttl) = store.hmap_get_field_ttl("ABC", "B").unwrap().unwrap() else {
+        panic!("the field should expire");
+    };
+    assert!(ttl > 4000 && ttl <= 5000);
+    assert_eq!(
+        store.hmap_get_field_ttl("ABC", "C").unwrap().unwrap(),
+        FieldTtl::Persistent
+    );
+    // Expired and missing fields are the same, unlike missing keys.
+    assert_eq!(
+        store.hmap_get_field_ttl("ABC", "A").unwrap().unwrap(),
+        FieldTtl::NoField
+    );
+    assert_eq!(
+        store.hmap_get_field_ttl("ABC", "nope").unwrap().unwrap(),
+        FieldTtl::NoField
+    );
+    assert!(store.hmap_get_field_ttl("nope", "A").is_none());
+
+    // Increments keep the time to live, inserts drop it.
+    store.hmap_incr("ABC", "B".to_owned(), 1).unwrap();
+    assert!(matches!(
+        store.hmap_get_field_ttl("ABC", "B").unwrap().unwrap(),
+        FieldTtl::Expires(_)
+    ));
+    store
+        .hmap_insert("ABC", ("B".to_owned(), "5".to_owned()))
+        .unwrap();
+    assert_eq!(
+        store.hmap_get_field_ttl("ABC", "B").unwrap().unwrap(),
+        FieldTtl::Persistent
+    );
+
+    store.hmap_set_field_ttl("ABC", "C", 5000);
+    assert!(matches!(
+        store.hmap_set_field_ttl("ABC", "C", 5000).unwrap().unwrap(),
+        FieldTtl::Expires(_)
+    ));
+    assert!(matches!(
+        store.hmap_remove_field_ttl("ABC", "C").unwrap().unwrap(),
+        FieldTtl::Expires(_)
+    ));
+    assert_eq!(
+        store.hmap_remove_field_ttl("ABC", "C").unwrap().unwrap(),
+        FieldTtl::Persistent
+    );
+    assert_eq!(
+        store.hmap_remove_field_ttl("ABC", "nope").unwrap().unwrap(),
+        FieldTtl::NoField
+    );
+    assert!(store.hmap_remove_field_ttl("nope", "C").is_none());
+
+    // An expired field can be set again.
+    assert!(store
+        .hmap_insert_if_absent("ABC", ("A".to_owned(), "new".to_owned()))
+        .unwrap());
+    assert_eq!(
+        store.hmap_get("ABC", "A".to_owned()).unwrap().unwrap(),
+        "new"
+    );
+}
+
+#[test]
+fn test_hmap_field_active_expiry() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.set_hmap(
+        "ABC",
+        vec![
+            ("A".to_owned(), "1".to_owned()),
+            ("B".to_owned(), "2".to_owned()),
+        ],
+        None,
+    );
+    store.set_hmap("DEF", vec![("A".to_owned(), "1".to_owned())], None);
+    store.hmap_set_field_ttl("ABC", "A", 0);
+    store.hmap_set_field_ttl("DEF", "A", 0);
+
+    store.clear_all_expired_keys();
+
+    assert_eq!(store.hmap_keys("ABC").unwrap().unwrap(), vec!["B"]);
+    // Hmaps whose fields all expired are removed.
+    assert!(!store.contains_key("DEF"));
+}
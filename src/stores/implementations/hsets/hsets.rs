@@ -1,5 +1,13 @@
-use crate::stores::{errors::ValueError, store::KeyValueStore, value_entry::ValueEntry};
-use std::collections::HashSet;
+use crate::stores::{
+    cursor::{CursorSet, ScanBatch},
+    errors::ValueError,
+    store::{KeyValueStore, DEFAULT_SCAN_COUNT},
+    types::ValueType,
+    value_entry::ValueEntry,
+};
+use crate::utils::glob::glob_match;
+use rand::seq::{IteratorRandom, SliceRandom};
+use std::{borrow::Cow, collections::HashSet};
 
 impl KeyValueStore {
     /// Inserts a Key-Value(in HashSet<String> type) pair in the KeyValueStore
@@ -11,13 +19,27 @@ impl KeyValueStore {
 
     /// Gets a Value (converted to set<String> type) associated to the Key in the KeyValueStore
     pub fn get_hset(&self, key: &str) -> Option<Result<HashSet<String>, ValueError>> {
-        match self._get_or_none_if_expired(&key) {
-            Some(value_entry) => Some(value_entry.get_value_as_hset()),
-            _ => None,
+        self._get_hset_ref(key)
+            .map(|result| result.map(|hset| (**hset).to_owned()))
+    }
+
+    /// Borrows the set associated to the Key, only values of other types get converted (and copied).
+    fn _get_hset_ref(&self, key: &str) -> Option<Result<Cow<'_, CursorSet>, ValueError>> {
+        match self._get_or_none_if_expired(key) {
+            Some(ValueEntry {
+                value: ValueType::Set(hset),
+                ..
+            }) => Some(Ok(Cow::Borrowed(hset))),
+            Some(value_entry) => Some(
+                value_entry
+                    .get_value_as_hset()
+                    .map(|hset| Cow::Owned(hset.into_iter().collect())),
+            ),
+            None => None,
         }
     }
 
-    fn _get_mut_hset(&mut self, key: &str) -> Option<Result<&mut HashSet<String>, ValueError>> {
+    fn _get_mut_hset(&mut self, key: &str) -> Option<Result<&mut CursorSet, ValueError>> {
         match self._get_mut_or_none_if_expired(&key) {
             Some(value_entry) => Some(value_entry.get_value_as_mut_hset()),
             None => None,
@@ -59,7 +81,7 @@ impl KeyValueStore {
 
     /// Checks if the item exists in the given hset
     pub fn hset_contains(&self, key: &str, value: String) -> Option<Result<bool, ValueError>> {
-        match self.get_hset(key) {
+        match self._get_hset_ref(key) {
             Some(Ok(hset)) => Some(Ok(hset.contains(&value))),
             Some(Err(e)) => Some(Err(e)),
             None => None,
@@ -72,54 +94,12 @@ impl KeyValueStore {
         key1: &str,
         key2: &str,
     ) -> Option<Result<Vec<String>, ValueError>> {
-        let opt_res_h1 = self.get_hset(key1);
-        let opt_res_h2 = self.get_hset(key2);
-
-        let h1 = match opt_res_h1 {
-            Some(Ok(hset1)) => hset1.to_owned(),
-            Some(Err(e)) => {
-                return Some(Err(e));
-            }
-            None => HashSet::new(),
-        };
-
-        let h2 = match opt_res_h2 {
-            Some(Ok(hset2)) => hset2.to_owned(),
-            Some(Err(e)) => {
-                return Some(Err(e));
-            }
-            None => HashSet::new(),
-        };
-
-        Some(Ok(Vec::from_iter(
-            h1.intersection(&h2).map(|item| item.to_owned()),
-        )))
+        Some(self.hset_intersection_many(&[key1, key2]))
     }
 
-    /// Get the intersection between two sets in the data store
+    /// Get the union between two sets in the data store
     pub fn hset_union(&self, key1: &str, key2: &str) -> Option<Result<Vec<String>, ValueError>> {
-        let opt_res_h1 = self.get_hset(key1);
-        let opt_res_h2 = self.get_hset(key2);
-
-        let h1 = match opt_res_h1 {
-            Some(Ok(hset1)) => hset1.to_owned(),
-            Some(Err(e)) => {
-                return Some(Err(e));
-            }
-            None => HashSet::new(),
-        };
-
-        let h2 = match opt_res_h2 {
-            Some(Ok(hset2)) => hset2.to_owned(),
-            Some(Err(e)) => {
-                return Some(Err(e));
-            }
-            None => HashSet::new(),
-        };
-
-        Some(Ok(Vec::from_iter(
-            h1.union(&h2).map(|item| item.to_owned()),
-        )))
+        Some(self.hset_union_many(&[key1, key2]))
     }
 
     /// Get the difference between two sets in the data store
@@ -128,38 +108,258 @@ impl KeyValueStore {
         key1: &str,
         key2: &str,
     ) -> Option<Result<Vec<String>, ValueError>> {
-        let opt_res_h1 = self.get_hset(key1);
-        let opt_res_h2 = self.get_hset(key2);
+        Some(self.hset_difference_many(&[key1, key2]))
+    }
 
-        let h1 = match opt_res_h1 {
-            Some(Ok(hset1)) => hset1.to_owned(),
-            Some(Err(e)) => {
-                return Some(Err(e));
-            }
-            None => HashSet::new(),
-        };
+    /// Borrows the sets associated to the keys, missing keys being empty sets.
+    fn _get_hset_refs(&self, keys: &[&str]) -> Result<Vec<Cow<'_, CursorSet>>, ValueError> {
+        keys.iter()
+            .map(|key| match self._get_hset_ref(key) {
+                Some(result) => result,
+                None => Ok(Cow::Owned(CursorSet::default())),
+            })
+            .collect()
+    }
+
+    /// Members that are in all the sets, found by iterating over the smallest set.
+    fn _intersection_members<'a>(
+        hsets: &'a [Cow<'a, CursorSet>],
+    ) -> impl Iterator<Item = &'a String> {
+        let smallest = hsets.iter().min_by_key(|hset| hset.len());
+        smallest
+            .into_iter()
+            .flat_map(|hset| hset.iter())
+            .filter(move |member| hsets.iter().all(|hset| hset.contains(*member)))
+    }
+
+    /// Members of the first set that aren't in any of the other sets.
+    fn _difference_members<'a>(
+        hsets: &'a [Cow<'a, CursorSet>],
+    ) -> impl Iterator<Item = &'a String> {
+        hsets
+            .first()
+            .into_iter()
+            .flat_map(|hset| hset.iter())
+            .filter(move |member| !hsets[1..].iter().any(|hset| hset.contains(*member)))
+    }
+
+    /// Members of any of the sets, each one returned once.
+    fn _union_members<'a>(hsets: &'a [Cow<'a, CursorSet>]) -> HashSet<&'a String> {
+        hsets.iter().flat_map(|hset| hset.iter()).collect()
+    }
 
-        let h2 = match opt_res_h2 {
-            Some(Ok(hset2)) => hset2.to_owned(),
-            Some(Err(e)) => {
-                return Some(Err(e));
+    /// Get the intersection between all the given sets, missing keys being empty sets.
+    pub fn hset_intersection_many(&self, keys: &[&str]) -> Result<Vec<String>, ValueError> {
+        let hsets = self._get_hset_refs(keys)?;
+        Ok(Self::_intersection_members(&hsets)
+            .map(|member| member.to_owned())
+            .collect())
+    }
+
+    /// Get the union between all the given sets, missing keys being empty sets.
+    pub fn hset_union_many(&self, keys: &[&str]) -> Result<Vec<String>, ValueError> {
+        let hsets = self._get_hset_refs(keys)?;
+        Ok(Self::_union_members(&hsets)
+            .into_iter()
+            .map(|member| member.to_owned())
+            .collect())
+    }
+
+    /// Get the members of the first set that aren't in any of the other sets,
+    /// missing keys being empty sets.
+    pub fn hset_difference_many(&self, keys: &[&str]) -> Result<Vec<String>, ValueError> {
+        let hsets = self._get_hset_refs(keys)?;
+        Ok(Self::_difference_members(&hsets)
+            .map(|member| member.to_owned())
+            .collect())
+    }
+
+    /// Cardinality of the intersection between all the given sets, without building it.
+    pub fn hset_intersection_size(&self, keys: &[&str]) -> Result<usize, ValueError> {
+        let hsets = self._get_hset_refs(keys)?;
+        Ok(Self::_intersection_members(&hsets).count())
+    }
+
+    /// Cardinality of the union between all the given sets, without copying its members.
+    pub fn hset_union_size(&self, keys: &[&str]) -> Result<usize, ValueError> {
+        let hsets = self._get_hset_refs(keys)?;
+        Ok(Self::_union_members(&hsets).len())
+    }
+
+    /// Cardinality of the difference between the first set and the others, without building it.
+    pub fn hset_difference_size(&self, keys: &[&str]) -> Result<usize, ValueError> {
+        let hsets = self._get_hset_refs(keys)?;
+        Ok(Self::_difference_members(&hsets).count())
+    }
+
+    /// Stores the intersection between all the given sets into the destination key
+    /// (replacing it) and returns its cardinality.
+    pub fn hset_intersection_store(
+        &mut self,
+        destination: &str,
+        keys: &[&str],
+        ttl: Option<u64>,
+    ) -> Result<usize, ValueError> {
+        let members = self.hset_intersection_many(keys)?;
+        Ok(self._store_hset(destination, members, ttl))
+    }
+
+    /// Stores the union between all the given sets into the destination key
+    /// (replacing it) and returns its cardinality.
+    pub fn hset_union_store(
+        &mut self,
+        destination: &str,
+        keys: &[&str],
+        ttl: Option<u64>,
+    ) -> Result<usize, ValueError> {
+        let members = self.hset_union_many(keys)?;
+        Ok(self._store_hset(destination, members, ttl))
+    }
+
+    /// Stores the difference between the first set and the others into the destination key
+    /// (replacing it) and returns its cardinality.
+    pub fn hset_difference_store(
+        &mut self,
+        destination: &str,
+        keys: &[&str],
+        ttl: Option<u64>,
+    ) -> Result<usize, ValueError> {
+        let members = self.hset_difference_many(keys)?;
+        Ok(self._store_hset(destination, members, ttl))
+    }
+
+    fn _store_hset(&mut self, destination: &str, members: Vec<String>, ttl: Option<u64>) -> usize {
+        let size = members.len();
+        self.set_hset(destination, members, ttl);
+        size
+    }
+
+    /// Moves a member from the source set to the destination set, creating the destination
+    /// (with the default time to live of the store) if needed. The source key is removed
+    /// along with its last member. Returns whether the member was in the source set.
+    pub fn hset_move(
+        &mut self,
+        source: &str,
+        destination: &str,
+        member: String,
+    ) -> Option<Result<bool, ValueError>> {
+        // Check the destination first, so nothing is removed from the source on error.
+        if let Some(Err(e)) = self._get_mut_hset(destination) {
+            return Some(Err(e));
+        }
+        match self._get_mut_hset(source)? {
+            Ok(hset) => {
+                if !hset.remove(&member) {
+                    return Some(Ok(false));
+                }
+                // When both sets are the same one, the member is put back below.
+                if hset.is_empty() && source != destination {
+                    self._data_remove(source);
+                }
+            }
+            Err(e) => return Some(Err(e)),
+        }
+        match self._get_mut_hset(destination) {
+            Some(Ok(hset)) => {
+                hset.insert(member);
             }
-            None => HashSet::new(),
+            _ => {
+                let expiration = self._get_expiration_instant(None);
+                let value_entry = ValueEntry::from_hset(HashSet::from([member]), expiration);
+                self._insert(destination, &value_entry);
+            }
+        }
+        Some(Ok(true))
+    }
+
+    /// Removes up to `count` random members from the set and returns them.
+    /// The key is removed along with the last member.
+    pub fn hset_pop_random(
+        &mut self,
+        key: &str,
+        count: usize,
+    ) -> Option<Result<Vec<String>, ValueError>> {
+        let hset = match self._get_mut_hset(key)? {
+            Ok(hset) => hset,
+            Err(e) => return Some(Err(e)),
         };
+        let members: Vec<String> = hset
+            .iter()
+            .choose_multiple(&mut rand::thread_rng(), count)
+            .into_iter()
+            .cloned()
+            .collect();
+        for member in members.iter() {
+            hset.remove(member);
+        }
+        if hset.is_empty() {
+            self._data_remove(key);
+        }
+        Some(Ok(members))
+    }
 
-        Some(Ok(Vec::from_iter(
-            h1.difference(&h2).map(|item| item.to_owned()),
-        )))
+    /// Samples random members of the set: up to `count` distinct members, or exactly
+    /// `count` members (possibly repeated) when `with_replacement` is true.
+    pub fn hset_random_members(
+        &self,
+        key: &str,
+        count: usize,
+        with_replacement: bool,
+    ) -> Option<Result<Vec<String>, ValueError>> {
+        match self._get_hset_ref(key) {
+            Some(Ok(hset)) => {
+                let mut rng = rand::thread_rng();
+                let members = if with_replacement {
+                    let candidates: Vec<&String> = hset.iter().collect();
+                    (0..count)
+                        .filter_map(|_| candidates.choose(&mut rng))
+                        .map(|member| (*member).to_owned())
+                        .collect()
+                } else {
+                    hset.iter()
+                        .map(|member| member.to_owned())
+                        .choose_multiple(&mut rng, count)
+                };
+                Some(Ok(members))
+            }
+            Some(Err(e)) => Some(Err(e)),
+            None => None,
+        }
     }
 
     /// cardinality of the set
     pub fn hset_size(&self, key: &str) -> Option<Result<usize, ValueError>> {
-        match self.get_hset(key) {
+        match self._get_hset_ref(key) {
             Some(Ok(hset)) => Some(Ok(hset.len())),
             Some(Err(e)) => Some(Err(e)),
             None => None,
         }
     }
+
+    /// Iterates over the members of the set in batches, the same way `scan` iterates over keys.
+    /// Returns the next cursor (0 once the iteration is complete) and the members of the batch
+    /// matching the glob `pattern`.
+    pub fn hset_scan(
+        &self,
+        key: &str,
+        cursor: u64,
+        pattern: Option<&str>,
+        count: Option<usize>,
+    ) -> Option<Result<ScanBatch<String>, ValueError>> {
+        match self._get_hset_ref(key) {
+            Some(Ok(hset)) => {
+                let (next_cursor, batch) = hset.scan(cursor, count.unwrap_or(DEFAULT_SCAN_COUNT));
+                let members = batch
+                    .into_iter()
+                    .filter(|member| pattern.is_none_or(|pattern| glob_match(pattern, member)))
+                    .map(|member| member.to_owned())
+                    .collect();
+                Some(Ok((next_cursor, members)))
+            }
+            Some(Err(e)) => Some(Err(e)),
+            None => None,
+        }
+    }
 }
 
 pub mod hsets_tests {}
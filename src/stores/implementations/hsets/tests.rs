@@ -125,3 +125,207 @@ fn test_hset_union_intersection_difference() {
     assert_eq!(union, vec!["A", "B", "C", "D", "E", "X", "Y", "Z"]);
     assert_eq!(difference, vec!["X", "Y", "Z"]);
 }
+
+#[test]
+fn test_hset_scan() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert!(store.hset_scan("ABC", 0, None, None).is_none());
+
+    store.set_hset(
+        "ABC",
+        (0..30).map(|idx| format!("member:{}", idx)).collect(),
+        None,
+    );
+
+    let (cursor, members) = store.hset_scan("ABC", 0, None, Some(100)).unwrap().unwrap();
+    assert_eq!(cursor, 0);
+    assert_eq!(members.len(), 30);
+
+    let mut scanned = HashSet::new();
+    let mut cursor = 0;
+    loop {
+        let (next_cursor, members) = store
+            .hset_scan("ABC", cursor, Some("member:1?"), Some(4))
+            .unwrap()
+            .unwrap();
+        scanned.extend(members);
+        cursor = next_cursor;
+        if cursor == 0 {
+            break;
+        }
+    }
+    assert_eq!(scanned.len(), 10);
+
+    // Each batch only holds about `count` members, and members added or removed
+    // during the iteration don't disturb the others.
+    let mut scanned = vec![];
+    let mut cursor = 0;
+    loop {
+        let (next_cursor, members) = store
+            .hset_scan("ABC", cursor, None, Some(4))
+            .unwrap()
+            .unwrap();
+        assert!(members.len() <= 5);
+        scanned.extend(members);
+        if cursor == 0 {
+            store.hset_add("ABC", "added".to_string());
+            store.hset_remove("ABC", "member:29".to_string());
+        }
+        cursor = next_cursor;
+        if cursor == 0 {
+            break;
+        }
+    }
+    scanned.retain(|member| member != "added" && member != "member:29");
+    scanned.sort();
+    let mut expected: Vec<String> = (0..29).map(|idx| format!("member:{}", idx)).collect();
+    expected.sort();
+    assert_eq!(scanned, expected);
+
+    // Point operations still work on values converted to sets.
+    store.set_string("DEF", "XYZ".to_string(), None);
+    assert!(store
+        .hset_contains("DEF", "Y".to_string())
+        .unwrap()
+        .unwrap());
+    assert_eq!(store.hset_size("DEF").unwrap().unwrap(), 3);
+}
+
+#[test]
+fn test_hset_many_and_store() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.set_hset(
+        "ABC",
+        vec!["A", "B", "C", "D"]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        None,
+    );
+    store.set_hset(
+        "DEF",
+        vec!["B", "C", "D", "E"]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        None,
+    );
+    store.set_hset(
+        "GHI",
+        vec!["C", "D", "F"].into_iter().map(String::from).collect(),
+        None,
+    );
+
+    let mut intersection = store
+        .hset_intersection_many(&["ABC", "DEF", "GHI"])
+        .unwrap();
+    intersection.sort();
+    assert_eq!(intersection, vec!["C", "D"]);
+    assert!(store
+        .hset_intersection_many(&["ABC", "nope"])
+        .unwrap()
+        .is_empty());
+
+    let mut union = store
+        .hset_union_many(&["ABC", "DEF", "GHI", "nope"])
+        .unwrap();
+    union.sort();
+    assert_eq!(union, vec!["A", "B", "C", "D", "E", "F"]);
+
+    let difference = store.hset_difference_many(&["ABC", "DEF", "GHI"]).unwrap();
+    assert_eq!(difference, vec!["A"]);
+
+    assert_eq!(store.hset_intersection_size(&["ABC", "DEF"]).unwrap(), 3);
+    assert_eq!(store.hset_union_size(&["ABC", "GHI"]).unwrap(), 5);
+    assert_eq!(store.hset_difference_size(&["DEF", "GHI"]).unwrap(), 2);
+
+    assert_eq!(
+        store
+            .hset_union_store("dest", &["ABC", "DEF"], Some(5000))
+            .unwrap(),
+        5
+    );
+    assert_eq!(store.hset_size("dest").unwrap().unwrap(), 5);
+    assert!(!store.is_expired("dest").unwrap());
+    assert_eq!(
+        store
+            .hset_intersection_store("dest", &["dest", "GHI"], None)
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        store
+            .hset_difference_store("dest", &["GHI", "dest"], None)
+            .unwrap(),
+        1
+    );
+    assert_eq!(store.get_list("dest").unwrap().unwrap(), vec!["F"]);
+
+    store.set_list("list", vec!["A".to_string()], None);
+    assert!(store.hset_union_many(&["ABC", "list"]).is_ok());
+    store.set_i64("int", 1, None);
+    assert!(store.hset_union_many(&["ABC", "int"]).is_err());
+    assert!(store.hset_union_store("dest", &["int"], None).is_err());
+}
+
+#[test]
+fn test_hset_move_pop_and_sample() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.set_hset(
+        "ABC",
+        vec!["A", "B", "C"].into_iter().map(String::from).collect(),
+        None,
+    );
+
+    assert!(store
+        .hset_move("ABC", "DEF", "A".to_string())
+        .unwrap()
+        .unwrap());
+    assert!(!store
+        .hset_move("ABC", "DEF", "A".to_string())
+        .unwrap()
+        .unwrap());
+    assert!(store.hset_move("nope", "DEF", "A".to_string()).is_none());
+    assert!(store
+        .hset_contains("DEF", "A".to_string())
+        .unwrap()
+        .unwrap());
+    store.set_string("string", "XYZ".to_string(), None);
+    assert!(store
+        .hset_move("ABC", "string", "B".to_string())
+        .unwrap()
+        .is_err());
+    assert_eq!(store.hset_size("ABC").unwrap().unwrap(), 2);
+
+    let sample = store.hset_random_members("ABC", 5, false).unwrap().unwrap();
+    assert_eq!(sample.len(), 2);
+    let sample = store.hset_random_members("ABC", 5, true).unwrap().unwrap();
+    assert_eq!(sample.len(), 5);
+    assert!(sample.iter().all(|member| member == "B" || member == "C"));
+    assert!(store.hset_random_members("nope", 5, true).is_none());
+
+    let popped = store.hset_pop_random("ABC", 1).unwrap().unwrap();
+    assert_eq!(popped.len(), 1);
+    assert_eq!(store.hset_size("ABC").unwrap().unwrap(), 1);
+    assert!(!store
+        .hset_contains("ABC", popped[0].to_owned())
+        .unwrap()
+        .unwrap());
+    assert_eq!(store.hset_pop_random("ABC", 5).unwrap().unwrap().len(), 1);
+    assert!(!store.contains_key("ABC"));
+
+    // Moving the last member removes the source, unless it is also the destination.
+    store.set_hset("single", vec!["A".to_string()], Some(100));
+    assert!(store
+        .hset_move("single", "single", "A".to_string())
+        .unwrap()
+        .unwrap());
+    assert_eq!(store.hset_size("single").unwrap().unwrap(), 1);
+    assert!(store.get_ttl("single").unwrap().is_some());
+    assert!(store
+        .hset_move("single", "moved", "A".to_string())
+        .unwrap()
+        .unwrap());
+    assert!(!store.contains_key("single"));
+    assert_eq!(store.hset_size("moved").unwrap().unwrap(), 1);
+}
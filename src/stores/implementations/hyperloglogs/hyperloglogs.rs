@@ -0,0 +1,71 @@
+use crate::stores::{
+    errors::ValueError, hyperloglog::HyperLogLog, store::KeyValueStore, value_entry::ValueEntry,
+};
+
+impl KeyValueStore {
+    /// Gets the HyperLogLog of a key to modify it, creating an empty one
+    /// (with the default time to live of the store) if needed.
+    fn _get_or_create_mut_hyperloglog(
+        &mut self,
+        key: &str,
+    ) -> Result<&mut HyperLogLog, ValueError> {
+        if self._get_or_none_if_expired(key).is_none() {
+            let expiration = self._get_expiration_instant(None);
+            self._insert(
+                key,
+                &ValueEntry::from_hyperloglog(HyperLogLog::new(), expiration),
+            );
+        }
+        match self._get_mut_or_none_if_expired(key) {
+            Some(value_entry) => value_entry.get_value_as_mut_hyperloglog(),
+            None => unreachable!("the hyperloglog was just created"),
+        }
+    }
+
+    /// Adds elements to the HyperLogLog of a key (creating it if needed),
+    /// and returns whether its estimated cardinality may have changed.
+    pub fn hyperloglog_add(&mut self, key: &str, elements: &[&str]) -> Result<bool, ValueError> {
+        let hyperloglog = self._get_or_create_mut_hyperloglog(key)?;
+        let mut changed = false;
+        for element in elements {
+            changed |= hyperloglog.add(element);
+        }
+        Ok(changed)
+    }
+
+    /// Merges the HyperLogLogs of the given keys, ignoring missing keys.
+    fn _merge_hyperloglogs(&self, keys: &[&str]) -> Result<HyperLogLog, ValueError> {
+        let mut merged = HyperLogLog::new();
+        for key in keys {
+            if let Some(value_entry) = self._get_or_none_if_expired(key) {
+                merged.merge(value_entry.get_value_as_hyperloglog()?);
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Estimates the number of distinct elements added to the HyperLogLogs of the given keys
+    /// (to their union, if there are several keys). Missing keys count as empty.
+    pub fn hyperloglog_count(&self, keys: &[&str]) -> Result<u64, ValueError> {
+        match keys {
+            [key] => match self._get_or_none_if_expired(key) {
+                Some(value_entry) => Ok(value_entry.get_value_as_hyperloglog()?.count()),
+                None => Ok(0),
+            },
+            _ => Ok(self._merge_hyperloglogs(keys)?.count()),
+        }
+    }
+
+    /// Merges the HyperLogLogs of the given keys into the HyperLogLog of the destination key
+    /// (creating it if needed), which then counts the union of all of them.
+    pub fn hyperloglog_merge(
+        &mut self,
+        destination: &str,
+        keys: &[&str],
+    ) -> Result<(), ValueError> {
+        let merged = self._merge_hyperloglogs(keys)?;
+        self._get_or_create_mut_hyperloglog(destination)?
+            .merge(&merged);
+        Ok(())
+    }
+}
@@ -0,0 +1,2 @@
+mod hyperloglogs;
+mod tests;
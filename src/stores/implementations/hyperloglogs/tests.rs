@@ -0,0 +1,61 @@
+use crate::stores::{store::KeyValueStore, types::ValueKind};
+
+fn assert_close(estimate: u64, expected: u64) {
+    let error = (estimate as f64 - expected as f64).abs() / expected as f64;
+    assert!(
+        error < 0.03,
+        "estimate {} is too far from {}",
+        estimate,
+        expected
+    );
+}
+
+#[test]
+fn test_hyperloglog_add_count() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert_eq!(store.hyperloglog_count(&["ABC"]).unwrap(), 0);
+
+    assert!(store.hyperloglog_add("ABC", &["a", "b", "c"]).unwrap());
+    assert!(!store.hyperloglog_add("ABC", &["a", "b"]).unwrap());
+    assert_eq!(store.hyperloglog_count(&["ABC"]).unwrap(), 3);
+    assert_eq!(store.get_kind("ABC"), Some(ValueKind::HyperLogLog));
+
+    for idx in 0..100_000 {
+        store
+            .hyperloglog_add("visitors", &[&format!("visitor:{}", idx)])
+            .unwrap();
+    }
+    assert_close(store.hyperloglog_count(&["visitors"]).unwrap(), 100_000);
+    // Two HyperLogLogs of 12KB each, whatever the number of elements.
+    assert!(store.info().estimated_memory_usage < 2 * 13 * 1024);
+
+    store.set_string("string", "abc".to_string(), None);
+    assert!(store.hyperloglog_add("string", &["a"]).is_err());
+    assert!(store.hyperloglog_count(&["ABC", "string"]).is_err());
+}
+
+#[test]
+fn test_hyperloglog_merge() {
+    let mut store = KeyValueStore::new("new_store", None);
+    for idx in 0..20_000 {
+        let element = format!("visitor:{}", idx);
+        let key = if idx < 12_000 { "page:1" } else { "page:2" };
+        store.hyperloglog_add(key, &[&element]).unwrap();
+        if idx % 2 == 0 {
+            // Half of the visitors saw both pages.
+            store.hyperloglog_add("page:3", &[&element]).unwrap();
+        }
+    }
+
+    assert_close(
+        store
+            .hyperloglog_count(&["page:1", "page:2", "page:3", "nope"])
+            .unwrap(),
+        20_000,
+    );
+    store
+        .hyperloglog_merge("all", &["page:1", "page:2"])
+        .unwrap();
+    store.hyperloglog_merge("all", &["page:3"]).unwrap();
+    assert_close(store.hyperloglog_count(&["all"]).unwrap(), 20_000);
+}
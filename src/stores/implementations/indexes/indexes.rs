@@ -0,0 +1,128 @@
+use std::time::Instant;
+
+use crate::stores::{
+    field_index::{compare_values, FieldIndex, IndexPage, IndexQuery, IndexSort},
+    store::KeyValueStore,
+    types::ValueType,
+    value_entry::ValueEntry,
+};
+
+/// Gets the value of a field of a hmap, unless the key or the field is expired.
+fn _live_field<'a>(
+    value_entry: Option<&'a ValueEntry>,
+    field: &str,
+    now: Instant,
+) -> Option<&'a String> {
+    let value_entry = value_entry.filter(|value_entry| !value_entry.is_expired_entry(Some(now)))?;
+    let ValueType::HashMap(hmap) = &value_entry.value else {
+        return None;
+    };
+    if value_entry
+        .field_expirations
+        .get(field)
+        .is_some_and(|expiration| now >= *expiration)
+    {
+        return None;
+    }
+    hmap.get(field)
+}
+
+impl KeyValueStore {
+    /// Declares an index over the `field` of the hmaps whose key starts with `prefix`,
+    /// and indexes the existing keys. Returns false if an index with this name already exists.
+    /// The index is then kept up to date on every write, expiry and removal of these keys.
+    pub fn create_index(&mut self, name: &str, prefix: &str, field: &str) -> bool {
+        if self.indexes.contains_key(name) {
+            return false;
+        }
+        let now = Instant::now();
+        let mut index = FieldIndex::new(prefix, field);
+        for (key, value_entry) in self._data.iter() {
+            if !index.covers(key) {
+                continue;
+            }
+            if let Some(value) = _live_field(Some(value_entry), field, now) {
+                index.update(key, Some(value));
+            }
+        }
+        self.indexes.insert(name.to_owned(), index);
+        true
+    }
+
+    /// Drops an index, and returns whether it existed.
+    pub fn drop_index(&mut self, name: &str) -> bool {
+        self.indexes.remove(name).is_some()
+    }
+
+    /// Gets the (name, prefix, field) of every index, sorted by name.
+    pub fn list_indexes(&self) -> Vec<(String, String, String)> {
+        self.indexes
+            .iter()
+            .map(|(name, index)| {
+                (
+                    name.to_owned(),
+                    index.prefix.to_owned(),
+                    index.field.to_owned(),
+                )
+            })
+            .collect()
+    }
+
+    /// Finds the keys whose indexed field matches the query, sorted by key or by field value,
+    /// then skips `offset` of them and returns at most `limit`.
+    /// Returns None if the index doesn't exist.
+    pub fn index_query(
+        &self,
+        name: &str,
+        query: &IndexQuery,
+        sort: IndexSort,
+        descending: bool,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Option<IndexPage> {
+        let index = self.indexes.get(name)?;
+        let now = Instant::now();
+        // Expired keys and fields stay in the index until they are cleared, so skip them here.
+        let mut matches: Vec<(&String, &String)> = index
+            .query(query)
+            .into_iter()
+            .filter_map(|key| {
+                let value = index.value(key)?;
+                let live_value = _live_field(self._data.get(key.as_str()), &index.field, now)?;
+                (live_value == value).then_some((key, value))
+            })
+            .collect();
+        // Matches come sorted by key, which the (stable) sort keeps for equal values.
+        if sort == IndexSort::Value {
+            matches.sort_by(|(_, a), (_, b)| compare_values(a, b));
+        }
+        if descending {
+            matches.reverse();
+        }
+        let total = matches.len();
+        let keys = matches
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|(key, _)| key.to_owned())
+            .collect();
+        Some(IndexPage { total, keys })
+    }
+
+    /// Brings the indexes (full-text ones included) covering a key up to date
+    /// with its current value.
+    pub(in crate::stores) fn _update_indexes(&mut self, key: &str) {
+        self._update_text_indexes(key);
+        if self.indexes.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        let value_entry = self._data.get(key);
+        for index in self.indexes.values_mut() {
+            if index.covers(key) {
+                let value = _live_field(value_entry, &index.field, now);
+                index.update(key, value.map(|value| value.as_str()));
+            }
+        }
+    }
+}
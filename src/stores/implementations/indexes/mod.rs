@@ -0,0 +1,2 @@
+mod indexes;
+mod tests;
@@ -0,0 +1,210 @@
+use std::{thread, time::Duration};
+
+use crate::stores::{
+    field_index::{IndexQuery, IndexSort},
+    store::KeyValueStore,
+};
+
+fn user(country: &str, age: &str, tags: &str) -> Vec<(String, String)> {
+    vec![
+        ("country".to_string(), country.to_string()),
+        ("age".to_string(), age.to_string()),
+        ("tags".to_string(), tags.to_string()),
+    ]
+}
+
+fn query(store: &KeyValueStore, name: &str, query: IndexQuery) -> Vec<String> {
+    store
+        .index_query(name, &query, IndexSort::Key, false, 0, None)
+        .unwrap()
+        .keys
+}
+
+#[test]
+fn test_index_queries() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.set_hmap("user:1", user("IN", "31", "books,Music"), None);
+    assert!(store.create_index("by_country", "user:", "country"));
+    assert!(store.create_index("by_age", "user:", "age"));
+    assert!(store.create_index("by_tags", "user:", "tags"));
+    assert!(!store.create_index("by_age", "user:", "country"));
+    store.set_hmap("user:2", user("FR", "25", "music"), None);
+    store.set_hmap("user:3", user("IN", "-4.5", ""), None);
+    store.set_hmap("admin:1", user("IN", "40", "books"), None);
+
+    let exact = |value: &str| IndexQuery::Exact(value.to_string());
+    assert_eq!(
+        query(&store, "by_country", exact("IN")),
+        vec!["user:1", "user:3"]
+    );
+    assert_eq!(
+        query(&store, "by_country", exact("in")),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        query(&store, "by_age", IndexQuery::Range(-10.0, 30.0)),
+        vec!["user:2", "user:3"]
+    );
+    assert_eq!(
+        query(&store, "by_age", IndexQuery::Range(30.0, -10.0)),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        query(
+            &store,
+            "by_tags",
+            IndexQuery::Tags(vec!["MUSIC".to_string()])
+        ),
+        vec!["user:1", "user:2"]
+    );
+    assert_eq!(
+        query(
+            &store,
+            "by_tags",
+            IndexQuery::Tags(vec!["books".to_string(), "music".to_string()])
+        ),
+        vec!["user:1", "user:2"]
+    );
+    assert_eq!(
+        store.index_query("missing", &exact("IN"), IndexSort::Key, false, 0, None),
+        None
+    );
+
+    let page = store
+        .index_query(
+            "by_age",
+            &IndexQuery::Range(f64::MIN, f64::MAX),
+            IndexSort::Value,
+            true,
+            1,
+            Some(1),
+        )
+        .unwrap();
+    assert_eq!(page.total, 3);
+    assert_eq!(page.keys, vec!["user:2"]);
+    let page = store
+        .index_query("by_country", &exact("IN"), IndexSort::Key, true, 0, Some(5))
+        .unwrap();
+    assert_eq!(page.keys, vec!["user:3", "user:1"]);
+
+    assert_eq!(
+        store.list_indexes()[0],
+        ("by_age".to_string(), "user:".to_string(), "age".to_string())
+    );
+    assert!(store.drop_index("by_age"));
+    assert!(!store.drop_index("by_age"));
+    assert_eq!(store.list_indexes().len(), 2);
+}
+
+#[test]
+fn test_index_follows_mutations() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert!(store.create_index("by_country", "user:", "country"));
+    assert!(store.create_index("by_age", "user:", "age"));
+    let in_country =
+        |store: &KeyValueStore| query(store, "by_country", IndexQuery::Exact("IN".to_string()));
+
+    store
+        .hmap_insert("user:1", ("country".to_string(), "IN".to_string()))
+        .unwrap();
+    store
+        .hmap_insert_many("user:2", user("IN", "20", ""))
+        .unwrap();
+    assert_eq!(in_country(&store), vec!["user:1", "user:2"]);
+
+    store
+        .hmap_insert("user:1", ("country".to_string(), "FR".to_string()))
+        .unwrap();
+    assert_eq!(in_country(&store), vec!["user:2"]);
+    store.hmap_remove("user:2", "country".to_string());
+    assert_eq!(in_country(&store), Vec::<String>::new());
+    store
+        .hmap_insert_if_absent("user:2", ("country".to_string(), "IN".to_string()))
+        .unwrap();
+    assert_eq!(in_country(&store), vec!["user:2"]);
+
+    store.hmap_incr("user:2", "age".to_string(), 15).unwrap();
+    store
+        .hmap_incr_float("user:1", "age".to_string(), 50.5)
+        .unwrap();
+    assert_eq!(
+        query(&store, "by_age", IndexQuery::Range(30.0, 40.0)),
+        vec!["user:2"]
+    );
+
+    assert!(store.rename_key("user:2", "user:3"));
+    assert_eq!(in_country(&store), vec!["user:3"]);
+    assert!(store.copy_key("user:3", "user:4", false));
+    assert!(store.rename_key("user:4", "other:4"));
+    assert_eq!(in_country(&store), vec!["user:3"]);
+    store.set_string("user:3", "not a hmap".to_string(), None);
+    assert_eq!(in_country(&store), Vec::<String>::new());
+
+    store.set_hmap("user:5", user("IN", "1", ""), None);
+    let mut destination = KeyValueStore::new("destination", None);
+    assert!(destination.create_index("by_country", "user:", "country"));
+    assert!(store.move_key_to("user:5", &mut destination, false));
+    assert_eq!(in_country(&store), Vec::<String>::new());
+    assert_eq!(in_country(&destination), vec!["user:5"]);
+    assert!(destination.remove("user:5"));
+    assert_eq!(in_country(&destination), Vec::<String>::new());
+
+    store.set_hmap("user:6", user("IN", "1", ""), None);
+    store.clear();
+    assert_eq!(in_country(&store), Vec::<String>::new());
+    assert_eq!(store.list_indexes().len(), 2);
+}
+
+#[test]
+fn test_index_expiry() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert!(store.create_index("by_country", "user:", "country"));
+    store.set_hmap("user:1", user("IN", "1", ""), Some(50));
+    store.set_hmap("user:2", user("IN", "2", ""), None);
+    store.set_hmap("user:3", user("IN", "3", ""), None);
+    store
+        .hmap_set_field_ttl("user:2", "country", 50)
+        .unwrap()
+        .unwrap();
+    let in_country = |store: &KeyValueStore| {
+        store
+            .index_query(
+                "by_country",
+                &IndexQuery::Exact("IN".to_string()),
+                IndexSort::Key,
+                false,
+                0,
+                None,
+            )
+            .unwrap()
+    };
+    assert_eq!(in_country(&store).total, 3);
+
+    thread::sleep(Duration::from_millis(100));
+    let page = in_country(&store);
+    assert_eq!(page.total, 1);
+    assert_eq!(page.keys, vec!["user:3"]);
+
+    let memory_usage = store.estimated_memory_usage();
+    store.clear_all_expired_keys();
+    assert!(store.estimated_memory_usage() < memory_usage);
+    assert_eq!(in_country(&store).keys, vec!["user:3"]);
+
+    // Changing the time to live of a field clears the expired fields from the indexes too.
+    store
+        .hmap_set_field_ttl("user:3", "country", 0)
+        .unwrap()
+        .unwrap();
+    assert!(store.indexes["by_country"].value("user:3").is_none());
+    store.set_hmap("user:4", user("IN", "4", ""), None);
+    store
+        .hmap_set_field_ttl("user:4", "country", 50)
+        .unwrap()
+        .unwrap();
+    thread::sleep(Duration::from_millis(100));
+    store
+        .hmap_remove_field_ttl("user:4", "age")
+        .unwrap()
+        .unwrap();
+    assert!(store.indexes["by_country"].value("user:4").is_none());
+}
@@ -1,7 +1,7 @@
 use crate::stores::{
     errors::{TypeConversionError, ValueError},
     store::KeyValueStore,
-    types::ValueType,
+    types::{CounterLimit, ValueType},
     value_entry::ValueEntry,
 };
 
@@ -30,38 +30,83 @@ impl KeyValueStore {
         }
     }
 
-    fn _add(&mut self, key: &str, value: i64) -> Option<Result<i64, ValueError>> {
-        if let Some(value_entry) = self._get_mut_or_none_if_expired(&key) {
-            match value_entry.get_value_as_i64() {
-                Ok(old_value) => {
-                    let updated_integer_value = old_value + value;
-                    value_entry.value = ValueType::Integer64(updated_integer_value);
-                    Some(Ok(updated_integer_value))
-                }
-                Err(e) => Some(Err(e)),
+    /// Adds `delta` to the integer value of a key and returns the result, with `bounded`
+    /// deciding what to do with it. A missing key starts at 0, with the default time to live
+    /// of the store.
+    fn _add(
+        &mut self,
+        key: &str,
+        delta: i64,
+        bounded: impl FnOnce(i128) -> Result<i64, ValueError>,
+    ) -> Result<i64, ValueError> {
+        let old_value = match self._get_or_none_if_expired(key) {
+            Some(value_entry) => value_entry.get_value_as_i64()?,
+            None => 0,
+        };
+        // Computed on 128 bits, so bounded counters can be clamped instead of overflowing.
+        let updated_value = bounded(old_value as i128 + delta as i128)?;
+        match self._get_mut_or_none_if_expired(key) {
+            Some(value_entry) => {
+                value_entry.value = ValueType::Integer64(updated_value);
+                // A String key isn't one anymore, so it leaves the full-text indexes.
+                self._update_indexes(key);
             }
-        } else {
-            None
+            None => self.set_i64(key, updated_value, None),
         }
+        Ok(updated_value)
     }
 
-    /// decrement an existing value associated to key by a certain number.
-    pub fn decr(&mut self, key: &str, by: Option<u64>) -> Option<Result<i64, ValueError>> {
-        match i64::try_from(by.unwrap_or(1)) {
-            Ok(value) => self._add(key, -value),
-            Err(e) => Some(Err(ValueError::TypeConversionError(
-                TypeConversionError::TryFromIntError(e),
-            ))),
+    /// Decrements the value associated to a key by a certain number (1 by default),
+    /// creating the key at 0 if it doesn't exist. Fails if the result doesn't fit in an i64.
+    pub fn decr(&mut self, key: &str, by: Option<i64>) -> Result<i64, ValueError> {
+        match by.unwrap_or(1).checked_neg() {
+            Some(delta) => self._add(key, delta, _checked_i64),
+            None => Err(ValueError::TypeConversionError(
+                TypeConversionError::IntegerOverflow,
+            )),
         }
     }
 
-    /// increment an existing value associated to a key by a certain number.
-    pub fn incr(&mut self, key: &str, by: Option<u64>) -> Option<Result<i64, ValueError>> {
-        match i64::try_from(by.unwrap_or(1)) {
-            Ok(value) => self._add(key, value),
-            Err(e) => Some(Err(ValueError::TypeConversionError(
-                TypeConversionError::TryFromIntError(e),
-            ))),
+    /// Increments the value associated to a key by a certain number (1 by default),
+    /// creating the key at 0 if it doesn't exist. Fails if the result doesn't fit in an i64.
+    pub fn incr(&mut self, key: &str, by: Option<i64>) -> Result<i64, ValueError> {
+        self._add(key, by.unwrap_or(1), _checked_i64)
+    }
+
+    /// Increments the value associated to a key by `delta` (which may be negative), keeping
+    /// the counter within `min` and `max`: the result is clamped to the bounds if `on_limit`
+    /// is `CounterLimit::Clamp`, and the counter is left untouched with a `CounterOutOfBounds`
+    /// error if it is `CounterLimit::Fail`. Fails with `InvalidCounterBounds` if `min` is
+    /// above `max`.
+    pub fn incr_bounded(
+        &mut self,
+        key: &str,
+        delta: i64,
+        min: Option<i64>,
+        max: Option<i64>,
+        on_limit: CounterLimit,
+    ) -> Result<i64, ValueError> {
+        let (min, max) = (min.unwrap_or(i64::MIN), max.unwrap_or(i64::MAX));
+        if min > max {
+            return Err(ValueError::TypeConversionError(
+                TypeConversionError::InvalidCounterBounds,
+            ));
         }
+        self._add(key, delta, |value| {
+            if (min as i128..=max as i128).contains(&value) {
+                Ok(value as i64)
+            } else if on_limit == CounterLimit::Clamp {
+                Ok(value.clamp(min as i128, max as i128) as i64)
+            } else {
+                Err(ValueError::TypeConversionError(
+                    TypeConversionError::CounterOutOfBounds,
+                ))
+            }
+        })
     }
 }
+
+fn _checked_i64(value: i128) -> Result<i64, ValueError> {
+    i64::try_from(value)
+        .map_err(|_| ValueError::TypeConversionError(TypeConversionError::IntegerOverflow))
+}
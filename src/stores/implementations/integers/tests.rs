@@ -1,4 +1,8 @@
-use crate::stores::store::KeyValueStore;
+use crate::stores::{
+    errors::{TypeConversionError, ValueError},
+    store::KeyValueStore,
+    types::CounterLimit,
+};
 
 #[test]
 fn test_get_set_i64() {
@@ -20,12 +24,70 @@ fn test_get_set_i64() {
 fn test_incr_decr() {
     let mut store = KeyValueStore::new("new_store", None);
     store.set_string("ABC", "68".to_string(), None);
-    assert_eq!(store.incr("ABC", None).unwrap().unwrap(), 69);
+    assert_eq!(store.incr("ABC", None).unwrap(), 69);
     assert_eq!(store.get_i64("ABC").unwrap().unwrap(), 69);
 
-    assert_eq!(store.decr("ABC", Some(8)).unwrap().unwrap(), 61);
+    assert_eq!(store.decr("ABC", Some(8)).unwrap(), 61);
     assert_eq!(store.get_string("ABC").unwrap().unwrap(), "61".to_string());
 
     store.set_string("xyz", "ab123x".to_string(), None);
-    assert!(store.incr("xyz", Some(10)).unwrap().is_err())
+    assert!(store.incr("xyz", Some(10)).is_err())
+}
+
+#[test]
+fn test_checked_signed_increments() {
+    let mut store = KeyValueStore::new("new_store", None);
+    // Missing counters start at 0.
+    assert_eq!(store.incr("ABC", Some(-5)).unwrap(), -5);
+    assert_eq!(store.decr("DEF", None).unwrap(), -1);
+
+    store.set_i64("max", i64::MAX, None);
+    assert!(store.incr("max", None).is_err());
+    assert_eq!(store.get_i64("max").unwrap().unwrap(), i64::MAX);
+    assert!(store.decr("ABC", Some(i64::MIN)).is_err());
+}
+
+#[test]
+fn test_bounded_counters() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert_eq!(
+        store
+            .incr_bounded("ABC", 8, Some(0), Some(10), CounterLimit::Clamp)
+            .unwrap(),
+        8
+    );
+    assert_eq!(
+        store
+            .incr_bounded("ABC", 8, Some(0), Some(10), CounterLimit::Clamp)
+            .unwrap(),
+        10
+    );
+    assert!(matches!(
+        store.incr_bounded("ABC", -11, Some(0), Some(10), CounterLimit::Fail),
+        Err(ValueError::TypeConversionError(
+            TypeConversionError::CounterOutOfBounds
+        ))
+    ));
+    assert_eq!(store.get_i64("ABC").unwrap().unwrap(), 10);
+    assert_eq!(
+        store
+            .incr_bounded("ABC", -11, Some(0), None, CounterLimit::Clamp)
+            .unwrap(),
+        0
+    );
+
+    // Clamping also applies past the limits of an i64.
+    store.set_i64("max", i64::MAX, None);
+    assert_eq!(
+        store
+            .incr_bounded("max", 1, None, None, CounterLimit::Clamp)
+            .unwrap(),
+        i64::MAX
+    );
+    assert!(matches!(
+        store.incr_bounded("max", 1, Some(5), Some(1), CounterLimit::Clamp),
+        Err(ValueError::TypeConversionError(
+            TypeConversionError::InvalidCounterBounds
+        ))
+    ));
 }
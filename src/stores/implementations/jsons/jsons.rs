@@ -0,0 +1,232 @@
+use serde_json::{Number, Value};
+
+use crate::stores::{
+    errors::{TypeConversionError, ValueError},
+    json::{self, JsonPath},
+    store::KeyValueStore,
+    value_entry::ValueEntry,
+};
+
+fn _parse_path(path: &str) -> Result<JsonPath, ValueError> {
+    JsonPath::parse(path).ok_or(ValueError::TypeConversionError(
+        TypeConversionError::InvalidJsonPath,
+    ))
+}
+
+impl KeyValueStore {
+    /// Gets the JSON document of a key to modify it, along with the parsed path.
+    fn _get_mut_json(
+        &mut self,
+        key: &str,
+        path: &str,
+    ) -> Option<Result<(&mut Value, JsonPath), ValueError>> {
+        let path = match _parse_path(path) {
+            Ok(path) => path,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(
+            self._get_mut_or_none_if_expired(key)?
+                .get_value_as_mut_json()
+                .map(|document| (document, path)),
+        )
+    }
+
+    /// Gets the values matching a JSONPath in the JSON document of a key.
+    fn _json_matches(&self, key: &str, path: &str) -> Option<Result<Vec<&Value>, ValueError>> {
+        let path = match _parse_path(path) {
+            Ok(path) => path,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(
+            self._get_or_none_if_expired(key)?
+                .get_value_as_json()
+                .map(|document| {
+                    path.resolve(document)
+                        .iter()
+                        .filter_map(|location| json::get(document, location))
+                        .collect()
+                }),
+        )
+    }
+
+    /// Sets the values matching a JSONPath in the JSON document of a key, and returns whether
+    /// anything was set. If nothing matches and the path ends with a key (like `$.a.b`),
+    /// the key is added to the objects matching the rest of the path.
+    /// A new document is created (with the default time to live of the store) only when
+    /// setting the root path `$` of a key that doesn't exist.
+    pub fn json_set(&mut self, key: &str, path: &str, value: Value) -> Result<bool, ValueError> {
+        let path = _parse_path(path)?;
+        let Some(value_entry) = self._get_mut_or_none_if_expired(key) else {
+            if !path.is_root() {
+                return Ok(false);
+            }
+            let expiration = self._get_expiration_instant(None);
+            self._insert(key, &ValueEntry::from_json(value, expiration));
+            return Ok(true);
+        };
+        let document = value_entry.get_value_as_mut_json()?;
+
+        let locations = path.resolve(document);
+        if !locations.is_empty() {
+            for location in locations {
+                if let Some(target) = json::get_mut(document, &location) {
+                    *target = value.to_owned();
+                }
+            }
+            return Ok(true);
+        }
+        let Some((parent_path, new_key)) = path.parent_and_key() else {
+            return Ok(false);
+        };
+        let mut is_set = false;
+        for location in parent_path.resolve(document) {
+            if let Some(Value::Object(parent)) = json::get_mut(document, &location) {
+                parent.insert(new_key.to_owned(), value.to_owned());
+                is_set = true;
+            }
+        }
+        Ok(is_set)
+    }
+
+    /// Gets the values matching a JSONPath in the JSON document of a key.
+    pub fn json_get(&self, key: &str, path: &str) -> Option<Result<Vec<Value>, ValueError>> {
+        Some(
+            self._json_matches(key, path)?
+                .map(|matches| matches.into_iter().cloned().collect()),
+        )
+    }
+
+    /// Gets the values matching a JSONPath in the JSON documents of several keys,
+    /// None for the keys that don't exist or don't hold a JSON document.
+    pub fn json_get_many(
+        &self,
+        keys: &[&str],
+        path: &str,
+    ) -> Result<Vec<Option<Vec<Value>>>, ValueError> {
+        _parse_path(path)?;
+        Ok(keys
+            .iter()
+            .map(|key| self.json_get(key, path).and_then(|result| result.ok()))
+            .collect())
+    }
+
+    /// Deletes the values matching a JSONPath from the JSON document of a key,
+    /// and returns how many were deleted. Deleting the root path `$` removes the key.
+    pub fn json_delete(&mut self, key: &str, path: &str) -> Option<Result<usize, ValueError>> {
+        let (document, path) = match self._get_mut_json(key, path)? {
+            Ok(document_and_path) => document_and_path,
+            Err(e) => return Some(Err(e)),
+        };
+        if path.is_root() {
+            self._data_remove(key);
+            return Some(Ok(1));
+        }
+        let mut locations = path.resolve(document);
+        // Delete the last array items first, so the indexes of the others stay valid.
+        locations.sort();
+        Some(Ok(locations
+            .iter()
+            .rev()
+            .filter(|location| json::remove(document, location))
+            .count()))
+    }
+
+    /// Adds `delta` to the numbers matching a JSONPath in the JSON document of a key,
+    /// and returns their new values, None for the values that aren't numbers.
+    /// Integers stay integers as long as `delta` is an integer and the sums fit in an i64.
+    pub fn json_incr(
+        &mut self,
+        key: &str,
+        path: &str,
+        delta: Number,
+    ) -> Option<Result<Vec<Option<Number>>, ValueError>> {
+        let (document, path) = match self._get_mut_json(key, path)? {
+            Ok(document_and_path) => document_and_path,
+            Err(e) => return Some(Err(e)),
+        };
+        let locations = path.resolve(document);
+        let mut new_numbers = vec![];
+        for location in locations.iter() {
+            let new_number = match json::get(document, location) {
+                Some(Value::Number(number)) => match json::add_numbers(number, &delta) {
+                    Some(new_number) => Some(new_number),
+                    None => {
+                        return Some(Err(ValueError::TypeConversionError(
+                            TypeConversionError::NonFiniteFloat,
+                        )))
+                    }
+                },
+                _ => None,
+            };
+            new_numbers.push(new_number);
+        }
+        // Only update the document once every sum is known to be valid.
+        for (location, new_number) in locations.iter().zip(new_numbers.iter()) {
+            if let (Some(target), Some(new_number)) =
+                (json::get_mut(document, location), new_number)
+            {
+                *target = Value::Number(new_number.to_owned());
+            }
+        }
+        Some(Ok(new_numbers))
+    }
+
+    /// Appends values to the arrays matching a JSONPath in the JSON document of a key,
+    /// and returns their new lengths, None for the values that aren't arrays.
+    pub fn json_array_append(
+        &mut self,
+        key: &str,
+        path: &str,
+        values: Vec<Value>,
+    ) -> Option<Result<Vec<Option<usize>>, ValueError>> {
+        let (document, path) = match self._get_mut_json(key, path)? {
+            Ok(document_and_path) => document_and_path,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(Ok(path
+            .resolve(document)
+            .iter()
+            .map(|location| match json::get_mut(document, location) {
+                Some(Value::Array(array)) => {
+                    array.extend(values.iter().cloned());
+                    Some(array.len())
+                }
+                _ => None,
+            })
+            .collect()))
+    }
+
+    /// Gets the types of the values matching a JSONPath in the JSON document of a key:
+    /// `object`, `array`, `string`, `integer`, `number`, `boolean` or `null`.
+    pub fn json_type(
+        &self,
+        key: &str,
+        path: &str,
+    ) -> Option<Result<Vec<&'static str>, ValueError>> {
+        Some(
+            self._json_matches(key, path)?
+                .map(|matches| matches.into_iter().map(json::type_name).collect()),
+        )
+    }
+
+    /// Gets the lengths of the values matching a JSONPath in the JSON document of a key:
+    /// the number of items of arrays, of keys of objects and of characters of strings,
+    /// None for the other values.
+    pub fn json_len(
+        &self,
+        key: &str,
+        path: &str,
+    ) -> Option<Result<Vec<Option<usize>>, ValueError>> {
+        Some(self._json_matches(key, path)?.map(|matches| {
+            matches
+                .into_iter()
+                .map(|value| match value {
+                    Value::Array(array) => Some(array.len()),
+                    Value::Object(object) => Some(object.len()),
+                    Value::String(string) => Some(string.chars().count()),
+                    _ => None,
+                })
+                .collect()
+        }))
+    }
+}
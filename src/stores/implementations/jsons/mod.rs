@@ -0,0 +1,2 @@
+mod jsons;
+mod tests;
@@ -0,0 +1,178 @@
+use serde_json::{json, Number};
+
+use crate::stores::{store::KeyValueStore, types::ValueKind};
+
+fn store_with_document() -> KeyValueStore {
+    let mut store = KeyValueStore::new("new_store", None);
+    let document = json!({
+        "name": "Leonard",
+        "age": 30,
+        "address": {"city": "Pasadena", "zip": "91101"},
+        "friends": [
+            {"name": "Sheldon", "age": 31.5},
+            {"name": "Penny", "age": 29}
+        ]
+    });
+    assert!(store.json_set("user", "$", document).unwrap());
+    store
+}
+
+#[test]
+fn test_json_get_set() {
+    let mut store = store_with_document();
+    assert_eq!(store.get_kind("user"), Some(ValueKind::Json));
+
+    assert_eq!(
+        store.json_get("user", "$.name").unwrap().unwrap(),
+        vec![json!("Leonard")]
+    );
+    assert_eq!(
+        store
+            .json_get("user", "$.friends[*].name")
+            .unwrap()
+            .unwrap(),
+        vec![json!("Sheldon"), json!("Penny")]
+    );
+    assert_eq!(
+        store.json_get("user", "$..age").unwrap().unwrap(),
+        vec![json!(30), json!(31.5), json!(29)]
+    );
+    assert_eq!(
+        store
+            .json_get("user", "$['address'].city")
+            .unwrap()
+            .unwrap(),
+        vec![json!("Pasadena")]
+    );
+    assert_eq!(
+        store
+            .json_get("user", "$.friends[-1].name")
+            .unwrap()
+            .unwrap(),
+        vec![json!("Penny")]
+    );
+    assert!(store
+        .json_get("user", "$.nope")
+        .unwrap()
+        .unwrap()
+        .is_empty());
+    assert!(store.json_get("user", "name").unwrap().is_err());
+    assert!(store.json_get("nope", "$").is_none());
+
+    // Only the field changes, and missing keys are added to their parent object.
+    assert!(store
+        .json_set("user", "$.address.city", json!("Paris"))
+        .unwrap());
+    assert!(store
+        .json_set("user", "$.address.country", json!("FR"))
+        .unwrap());
+    assert!(!store
+        .json_set("user", "$.nope.country", json!("FR"))
+        .unwrap());
+    assert_eq!(
+        store.json_get("user", "$.address").unwrap().unwrap(),
+        vec![json!({"city": "Paris", "zip": "91101", "country": "FR"})]
+    );
+    assert!(store
+        .json_set("user", "$.friends[*].age", json!(40))
+        .unwrap());
+    assert_eq!(
+        store.json_get("user", "$.friends[*].age").unwrap().unwrap(),
+        vec![json!(40), json!(40)]
+    );
+    // New documents are only created at the root.
+    assert!(!store.json_set("other", "$.a", json!(1)).unwrap());
+    assert!(!store.contains_key("other"));
+
+    store.set_string("string", "abc".to_string(), None);
+    assert!(store.json_set("string", "$", json!(1)).is_err());
+    assert!(store.json_get("string", "$").unwrap().is_err());
+
+    assert_eq!(
+        store
+            .json_get_many(&["user", "nope", "string"], "$.name")
+            .unwrap(),
+        vec![Some(vec![json!("Leonard")]), None, None]
+    );
+    assert!(store.json_get_many(&["user"], "$.[").is_err());
+}
+
+#[test]
+fn test_json_delete() {
+    let mut store = store_with_document();
+    assert_eq!(
+        store.json_delete("user", "$.address.zip").unwrap().unwrap(),
+        1
+    );
+    assert_eq!(
+        store.json_delete("user", "$.friends[*]").unwrap().unwrap(),
+        2
+    );
+    assert_eq!(store.json_delete("user", "$.nope").unwrap().unwrap(), 0);
+    assert_eq!(
+        store.json_get("user", "$").unwrap().unwrap(),
+        vec![json!({"name": "Leonard", "age": 30, "address": {"city": "Pasadena"}, "friends": []})]
+    );
+    assert_eq!(store.json_delete("user", "$").unwrap().unwrap(), 1);
+    assert!(!store.contains_key("user"));
+    assert!(store.json_delete("user", "$").is_none());
+}
+
+#[test]
+fn test_json_incr_append_type_len() {
+    let mut store = store_with_document();
+    assert_eq!(
+        store
+            .json_incr("user", "$..age", Number::from(2))
+            .unwrap()
+            .unwrap(),
+        vec![
+            Some(Number::from(32)),
+            Number::from_f64(33.5),
+            Some(Number::from(31))
+        ]
+    );
+    assert_eq!(
+        store
+            .json_incr("user", "$.name", Number::from(1))
+            .unwrap()
+            .unwrap(),
+        vec![None]
+    );
+    assert!(store
+        .json_incr("user", "$.age", Number::from_f64(f64::MAX).unwrap())
+        .unwrap()
+        .is_ok());
+    assert!(store
+        .json_incr("user", "$.age", Number::from_f64(f64::MAX).unwrap())
+        .unwrap()
+        .is_err());
+
+    assert_eq!(
+        store
+            .json_array_append("user", "$.friends", vec![json!("Howard"), json!("Raj")])
+            .unwrap()
+            .unwrap(),
+        vec![Some(4)]
+    );
+    assert_eq!(
+        store
+            .json_array_append("user", "$.name", vec![json!(1)])
+            .unwrap()
+            .unwrap(),
+        vec![None]
+    );
+
+    assert_eq!(
+        store.json_type("user", "$.*").unwrap().unwrap(),
+        vec!["string", "number", "object", "array"]
+    );
+    assert_eq!(
+        store.json_type("user", "$.friends[*]").unwrap().unwrap(),
+        vec!["object", "object", "string", "string"]
+    );
+    assert_eq!(
+        store.json_len("user", "$.*").unwrap().unwrap(),
+        vec![Some(7), None, Some(2), Some(4)]
+    );
+}
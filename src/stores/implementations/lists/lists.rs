@@ -1,5 +1,6 @@
-use crate::stores::{errors::ValueError, store::KeyValueStore, value_entry::ValueEntry};
-use std::collections::VecDeque;
+use crate::stores::{
+    errors::ValueError, list::List, store::KeyValueStore, value_entry::ValueEntry,
+};
 
 impl KeyValueStore {
     /// Inserts a Key-Value(in Vec<String> type) pair in the KeyValueStore
@@ -26,7 +27,7 @@ impl KeyValueStore {
         }
     }
 
-    fn _get_deque(&self, key: &str) -> Option<Result<&VecDeque<String>, ValueError>> {
+    fn _get_deque(&self, key: &str) -> Option<Result<&List, ValueError>> {
         match self._get_or_none_if_expired(&key) {
             Some(value_entry) => match value_entry.get_value_as_deque() {
                 Ok(deque) => Some(Ok(deque)),
@@ -36,7 +37,7 @@ impl KeyValueStore {
         }
     }
 
-    fn _get_mut_deque(&mut self, key: &str) -> Option<Result<&mut VecDeque<String>, ValueError>> {
+    fn _get_mut_deque(&mut self, key: &str) -> Option<Result<&mut List, ValueError>> {
         match self._get_mut_or_none_if_expired(&key) {
             Some(value_entry) => match value_entry.get_value_as_mut_deque() {
                 Ok(deque) => Some(Ok(deque)),
@@ -1,6 +1,21 @@
+mod bitmaps;
+mod blooms;
 mod bytes;
+mod countmins;
+mod cuckoos;
+mod floats;
+mod geos;
 mod hmaps;
 mod hsets;
+mod hyperloglogs;
+mod indexes;
 mod integers;
+mod jsons;
 mod lists;
+mod sketches;
+mod sorts;
 mod strings;
+mod text_indexes;
+mod timeseries;
+mod topks;
+mod vectors;
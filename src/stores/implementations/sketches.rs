@@ -0,0 +1,180 @@
+use std::time::Instant;
+
+use crate::stores::{
+    bloom::BloomFilter,
+    countmin::CountMinSketch,
+    cuckoo::{CuckooError, CuckooFilter},
+    errors::{TypeConversionError, ValueError},
+    store::KeyValueStore,
+    topk::TopK,
+    value_entry::ValueEntry,
+};
+
+/// What the store needs of a probabilistic structure (Bloom and Cuckoo filters, Count-Min
+/// sketches and Top-Ks) to create, get and merge it the same way for all of them.
+pub(in crate::stores) trait Sketch: Clone + Sized {
+    fn from_entry(value_entry: &ValueEntry) -> Result<&Self, ValueError>;
+    fn from_entry_mut(value_entry: &mut ValueEntry) -> Result<&mut Self, ValueError>;
+    fn into_entry(self, expiration: Option<Instant>) -> ValueEntry;
+    /// Fails if the sketches can't be merged, see the `merge` of each of them.
+    fn merge_with(&mut self, other: &Self) -> Result<(), ValueError>;
+}
+
+fn _incompatible_sketches() -> ValueError {
+    ValueError::TypeConversionError(TypeConversionError::IncompatibleSketches)
+}
+
+pub(in crate::stores) fn cuckoo_error(error: CuckooError) -> ValueError {
+    ValueError::TypeConversionError(match error {
+        CuckooError::Incompatible => TypeConversionError::IncompatibleSketches,
+        CuckooError::Full => TypeConversionError::FilterFull,
+    })
+}
+
+impl Sketch for BloomFilter {
+    fn from_entry(value_entry: &ValueEntry) -> Result<&Self, ValueError> {
+        value_entry.get_value_as_bloom()
+    }
+
+    fn from_entry_mut(value_entry: &mut ValueEntry) -> Result<&mut Self, ValueError> {
+        value_entry.get_value_as_mut_bloom()
+    }
+
+    fn into_entry(self, expiration: Option<Instant>) -> ValueEntry {
+        ValueEntry::from_bloom(self, expiration)
+    }
+
+    fn merge_with(&mut self, other: &Self) -> Result<(), ValueError> {
+        self.merge(other)
+            .then_some(())
+            .ok_or_else(_incompatible_sketches)
+    }
+}
+
+impl Sketch for CuckooFilter {
+    fn from_entry(value_entry: &ValueEntry) -> Result<&Self, ValueError> {
+        value_entry.get_value_as_cuckoo()
+    }
+
+    fn from_entry_mut(value_entry: &mut ValueEntry) -> Result<&mut Self, ValueError> {
+        value_entry.get_value_as_mut_cuckoo()
+    }
+
+    fn into_entry(self, expiration: Option<Instant>) -> ValueEntry {
+        ValueEntry::from_cuckoo(self, expiration)
+    }
+
+    fn merge_with(&mut self, other: &Self) -> Result<(), ValueError> {
+        self.merge(other).map_err(cuckoo_error)
+    }
+}
+
+impl Sketch for CountMinSketch {
+    fn from_entry(value_entry: &ValueEntry) -> Result<&Self, ValueError> {
+        value_entry.get_value_as_countmin()
+    }
+
+    fn from_entry_mut(value_entry: &mut ValueEntry) -> Result<&mut Self, ValueError> {
+        value_entry.get_value_as_mut_countmin()
+    }
+
+    fn into_entry(self, expiration: Option<Instant>) -> ValueEntry {
+        ValueEntry::from_countmin(self, expiration)
+    }
+
+    fn merge_with(&mut self, other: &Self) -> Result<(), ValueError> {
+        self.merge(other)
+            .then_some(())
+            .ok_or_else(_incompatible_sketches)
+    }
+}
+
+impl Sketch for TopK {
+    fn from_entry(value_entry: &ValueEntry) -> Result<&Self, ValueError> {
+        value_entry.get_value_as_topk()
+    }
+
+    fn from_entry_mut(value_entry: &mut ValueEntry) -> Result<&mut Self, ValueError> {
+        value_entry.get_value_as_mut_topk()
+    }
+
+    fn into_entry(self, expiration: Option<Instant>) -> ValueEntry {
+        ValueEntry::from_topk(self, expiration)
+    }
+
+    fn merge_with(&mut self, other: &Self) -> Result<(), ValueError> {
+        self.merge(other)
+            .then_some(())
+            .ok_or_else(_incompatible_sketches)
+    }
+}
+
+impl KeyValueStore {
+    /// Stores a new sketch (with the default time to live of the store), and returns false if
+    /// the key already exists. The sketch is None when its parameters are invalid.
+    pub(in crate::stores) fn _sketch_reserve<S: Sketch>(
+        &mut self,
+        key: &str,
+        sketch: Option<S>,
+    ) -> Result<bool, ValueError> {
+        let sketch = sketch.ok_or(ValueError::TypeConversionError(
+            TypeConversionError::InvalidSketchParameters,
+        ))?;
+        if self._get_or_none_if_expired(key).is_some() {
+            return Ok(false);
+        }
+        let expiration = self._get_expiration_instant(None);
+        self._insert(key, &sketch.into_entry(expiration));
+        Ok(true)
+    }
+
+    /// Gets the sketch of a key to modify it, creating it with `default` if needed.
+    pub(in crate::stores) fn _get_or_create_mut_sketch<S: Sketch>(
+        &mut self,
+        key: &str,
+        default: impl FnOnce() -> Option<S>,
+    ) -> Result<&mut S, ValueError> {
+        if self._get_or_none_if_expired(key).is_none() {
+            self._sketch_reserve(key, default())?;
+        }
+        match self._get_mut_or_none_if_expired(key) {
+            Some(value_entry) => S::from_entry_mut(value_entry),
+            None => unreachable!("the sketch was just created"),
+        }
+    }
+
+    /// Merges the sketches of the given keys into the sketch of the destination key (created
+    /// from the first of them if needed). Missing keys are ignored.
+    pub(in crate::stores) fn _sketch_merge<S: Sketch>(
+        &mut self,
+        destination: &str,
+        keys: &[&str],
+    ) -> Result<(), ValueError> {
+        let mut merged = match self._get_or_none_if_expired(destination) {
+            Some(value_entry) => Some(S::from_entry(value_entry)?.to_owned()),
+            None => None,
+        };
+        // The destination is left out of the keys, its own elements or counts would be added twice.
+        for key in keys.iter().filter(|key| **key != destination) {
+            let Some(value_entry) = self._get_or_none_if_expired(key) else {
+                continue;
+            };
+            let sketch = S::from_entry(value_entry)?;
+            match merged.as_mut() {
+                Some(merged) => merged.merge_with(sketch)?,
+                None => merged = Some(sketch.to_owned()),
+            }
+        }
+        let Some(merged) = merged else {
+            return Ok(());
+        };
+        match self._get_mut_or_none_if_expired(destination) {
+            Some(value_entry) => *S::from_entry_mut(value_entry)? = merged,
+            None => {
+                let expiration = self._get_expiration_instant(None);
+                self._insert(destination, &merged.into_entry(expiration));
+            }
+        }
+        Ok(())
+    }
+}
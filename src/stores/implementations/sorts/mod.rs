@@ -0,0 +1,2 @@
+mod sorts;
+mod tests;
@@ -0,0 +1,142 @@
+use std::cmp::Ordering;
+
+use crate::stores::{
+    errors::{TypeConversionError, TypeConversionImpossible, ValueError},
+    store::KeyValueStore,
+    types::{SortOptions, ValueType},
+};
+
+/// Sorts the elements by weight, then lexically for equal weights.
+fn _sort_by_weight<W>(
+    mut weighted: Vec<(W, String)>,
+    compare: impl Fn(&W, &W) -> Ordering,
+    descending: bool,
+) -> Vec<String> {
+    weighted.sort_by(|(a_weight, a), (b_weight, b)| {
+        let ordering = compare(a_weight, b_weight).then_with(|| a.cmp(b));
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+    weighted.into_iter().map(|(_, element)| element).collect()
+}
+
+impl KeyValueStore {
+    /// Gets the value a `sort` pattern points to for an element: `#` is the element itself,
+    /// `prefix_*` the String value of a key and `prefix_*->field` a field of a hmap.
+    fn _sort_lookup(&self, pattern: &str, element: &str) -> Option<String> {
+        if pattern == "#" {
+            return Some(element.to_owned());
+        }
+        let (key_pattern, field) = match pattern.split_once("->") {
+            Some((key_pattern, field)) if !field.is_empty() => (key_pattern, Some(field)),
+            _ => (pattern, None),
+        };
+        let key = key_pattern.replacen('*', element, 1);
+        match field {
+            Some(field) => self.hmap_get(&key, field.to_owned())?.ok(),
+            None => self
+                ._get_or_none_if_expired(&key)?
+                .get_value_as_string()
+                .ok(),
+        }
+    }
+
+    /// Sorts the elements of a list or a set (numerically unless `alpha` is set), then skips
+    /// `offset` of them and keeps at most `limit`. See `SortOptions` for sorting by weights
+    /// stored in other keys, and returning values of other keys instead of the elements:
+    /// values that don't exist are None. A missing key has no elements.
+    pub fn sort(
+        &self,
+        key: &str,
+        options: &SortOptions,
+    ) -> Result<Vec<Option<String>>, ValueError> {
+        let elements = match self._get_or_none_if_expired(key) {
+            Some(value_entry) => match value_entry.value {
+                ValueType::Deque(_) | ValueType::Set(_) => value_entry.get_value_as_list()?,
+                _ => {
+                    return Err(ValueError::TypeConversionImpossible(
+                        TypeConversionImpossible::IncompatibleTypes,
+                    ))
+                }
+            },
+            None => vec![],
+        };
+
+        let sorted = match &options.by {
+            Some(by) if !by.contains('*') => elements,
+            by => {
+                let weights: Vec<Option<String>> = elements
+                    .iter()
+                    .map(|element| match by {
+                        Some(by) => self._sort_lookup(by, element),
+                        None => Some(element.to_owned()),
+                    })
+                    .collect();
+                if options.alpha {
+                    let weighted = weights
+                        .into_iter()
+                        .map(|weight| weight.unwrap_or_default())
+                        .zip(elements)
+                        .collect();
+                    _sort_by_weight(weighted, |a, b| a.cmp(b), options.descending)
+                } else {
+                    let weighted = weights
+                        .into_iter()
+                        .map(|weight| match weight {
+                            Some(weight) => weight.trim().parse::<f64>().map_err(|e| {
+                                ValueError::TypeConversionError(
+                                    TypeConversionError::ParseFloatError(e),
+                                )
+                            }),
+                            None => Ok(0.0),
+                        })
+                        .zip(elements)
+                        .map(|(weight, element)| weight.map(|weight| (weight, element)))
+                        .collect::<Result<_, _>>()?;
+                    _sort_by_weight(weighted, |a, b| a.total_cmp(b), options.descending)
+                }
+            }
+        };
+
+        let page = sorted
+            .into_iter()
+            .skip(options.offset)
+            .take(options.limit.unwrap_or(usize::MAX));
+        if options.get.is_empty() {
+            return Ok(page.map(Some).collect());
+        }
+        let mut values = vec![];
+        for element in page {
+            for pattern in options.get.iter() {
+                values.push(self._sort_lookup(pattern, &element));
+            }
+        }
+        Ok(values)
+    }
+
+    /// Sorts like `sort`, and stores the result in a list (with the default time to live of the
+    /// store) at `destination`, values that don't exist being empty strings.
+    /// The destination is removed if there is nothing to store. Returns the length of the list.
+    pub fn sort_and_store(
+        &mut self,
+        key: &str,
+        options: &SortOptions,
+        destination: &str,
+    ) -> Result<usize, ValueError> {
+        let values: Vec<String> = self
+            .sort(key, options)?
+            .into_iter()
+            .map(|value| value.unwrap_or_default())
+            .collect();
+        let length = values.len();
+        if values.is_empty() {
+            self.remove(destination);
+        } else {
+            self.set_list(destination, values, None);
+        }
+        Ok(length)
+    }
+}
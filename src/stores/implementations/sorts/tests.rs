@@ -0,0 +1,136 @@
+use crate::stores::{store::KeyValueStore, types::SortOptions};
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn sorted(store: &KeyValueStore, key: &str, options: &SortOptions) -> Vec<String> {
+    store
+        .sort(key, options)
+        .unwrap()
+        .into_iter()
+        .map(|value| value.unwrap_or("nil".to_string()))
+        .collect()
+}
+
+#[test]
+fn test_sort_elements() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.set_list("numbers", strings(&["10", "2", "-1.5", "2", "3"]), None);
+    store.set_hset("words", strings(&["pear", "apple", "Fig", "10"]), None);
+
+    let options = SortOptions::default();
+    assert_eq!(
+        sorted(&store, "numbers", &options),
+        vec!["-1.5", "2", "2", "3", "10"]
+    );
+    assert!(store.sort("words", &options).is_err());
+    assert_eq!(sorted(&store, "missing", &options), Vec::<String>::new());
+    store.set_string("string", "abc".to_string(), None);
+    assert!(store.sort("string", &options).is_err());
+
+    let options = SortOptions {
+        alpha: true,
+        ..SortOptions::default()
+    };
+    assert_eq!(
+        sorted(&store, "words", &options),
+        vec!["10", "Fig", "apple", "pear"]
+    );
+    assert_eq!(
+        sorted(&store, "numbers", &options),
+        vec!["-1.5", "10", "2", "2", "3"]
+    );
+
+    let options = SortOptions {
+        descending: true,
+        offset: 1,
+        limit: Some(3),
+        ..SortOptions::default()
+    };
+    assert_eq!(sorted(&store, "numbers", &options), vec!["3", "2", "2"]);
+    let options = SortOptions {
+        offset: 10,
+        ..SortOptions::default()
+    };
+    assert_eq!(sorted(&store, "numbers", &options), Vec::<String>::new());
+}
+
+#[test]
+fn test_sort_by_and_get_patterns() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.set_list("users", strings(&["1", "2", "3", "4"]), None);
+    for (id, name, age) in [("1", "zoe", "31"), ("2", "adam", "25"), ("3", "eve", "40")] {
+        store.set_hmap(
+            &format!("user:{}", id),
+            vec![
+                ("name".to_string(), name.to_string()),
+                ("age".to_string(), age.to_string()),
+            ],
+            None,
+        );
+        store.set_i64(
+            &format!("rank_{}", id),
+            age.parse::<i64>().unwrap() % 7,
+            None,
+        );
+    }
+
+    // Missing weights count as 0.
+    let options = SortOptions {
+        by: Some("user:*->age".to_string()),
+        ..SortOptions::default()
+    };
+    assert_eq!(sorted(&store, "users", &options), vec!["4", "2", "1", "3"]);
+    let options = SortOptions {
+        by: Some("user:*->name".to_string()),
+        alpha: true,
+        descending: true,
+        ..SortOptions::default()
+    };
+    assert_eq!(sorted(&store, "users", &options), vec!["1", "3", "2", "4"]);
+    // 31 % 7 = 3, 25 % 7 = 4, 40 % 7 = 5
+    let options = SortOptions {
+        by: Some("rank_*".to_string()),
+        ..SortOptions::default()
+    };
+    assert_eq!(sorted(&store, "users", &options), vec!["4", "1", "2", "3"]);
+    let options = SortOptions {
+        by: Some("user:*->name".to_string()),
+        ..SortOptions::default()
+    };
+    assert!(store.sort("users", &options).is_err());
+
+    // A pattern without `*` keeps the order of the list.
+    let options = SortOptions {
+        by: Some("nosort".to_string()),
+        get: strings(&["#", "user:*->name", "rank_*"]),
+        limit: Some(2),
+        ..SortOptions::default()
+    };
+    assert_eq!(
+        sorted(&store, "users", &options),
+        vec!["1", "zoe", "3", "2", "adam", "4"]
+    );
+
+    let options = SortOptions {
+        by: Some("user:*->age".to_string()),
+        get: strings(&["user:*->name"]),
+        descending: true,
+        ..SortOptions::default()
+    };
+    assert_eq!(
+        sorted(&store, "users", &options),
+        vec!["eve", "zoe", "adam", "nil"]
+    );
+    assert_eq!(store.sort_and_store("users", &options, "names").unwrap(), 4);
+    assert_eq!(
+        store.get_list("names").unwrap().unwrap(),
+        vec!["eve", "zoe", "adam", ""]
+    );
+    assert_eq!(
+        store.sort_and_store("missing", &options, "names").unwrap(),
+        0
+    );
+    assert!(store.get_list("names").is_none());
+}
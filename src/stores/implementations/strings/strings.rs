@@ -1,4 +1,23 @@
-use crate::stores::{errors::ValueError, store::KeyValueStore, value_entry::ValueEntry};
+use crate::stores::{
+    errors::{TypeConversionError, ValueError},
+    store::KeyValueStore,
+    types::ValueType,
+    value_entry::ValueEntry,
+};
+
+/// Strings can't grow past 512MB through APPEND or SETRANGE, to keep a single command from
+/// exhausting the memory.
+const MAX_STRING_LENGTH: usize = 512 * 1024 * 1024;
+
+/// Length of a String of `length` bytes extended by `extra` bytes, if it isn't too long.
+fn _checked_length(length: usize, extra: usize) -> Result<usize, ValueError> {
+    length
+        .checked_add(extra)
+        .filter(|length| *length <= MAX_STRING_LENGTH)
+        .ok_or(ValueError::TypeConversionError(
+            TypeConversionError::StringTooLong,
+        ))
+}
 
 impl KeyValueStore {
     /// Inserts a Key-Value(in String type) pair in the KeyValueStore
@@ -24,4 +43,171 @@ impl KeyValueStore {
             _ => None,
         }
     }
+
+    /// Replaces the String value of a key with the result of `update`, keeping its expiration.
+    /// A missing key starts as an empty String, with the default time to live of the store.
+    /// Integers, floats and UTF-8 Bytes are updated as Strings, and stay Strings.
+    /// Returns the length (in bytes) of the updated String.
+    fn _update_string(
+        &mut self,
+        key: &str,
+        update: impl FnOnce(String) -> Result<String, ValueError>,
+    ) -> Result<usize, ValueError> {
+        match self._get_mut_or_none_if_expired(key) {
+            Some(value_entry) => {
+                let value = update(value_entry.get_value_as_string()?)?;
+                let length = value.len();
+                value_entry.value = ValueType::String(value);
+                self._update_indexes(key);
+                Ok(length)
+            }
+            None => {
+                let value = update(String::new())?;
+                let length = value.len();
+                self.set_string(key, value, None);
+                Ok(length)
+            }
+        }
+    }
+
+    /// Appends to the String value of a key (creating it if needed) and returns the new length.
+    /// Appending to an integer or float appends to its text, e.g. appending "1" to the
+    /// integer 4 makes it the String "41" (which can still be incremented).
+    pub fn string_append(&mut self, key: &str, value: &str) -> Result<usize, ValueError> {
+        self._update_string(key, |mut string| {
+            _checked_length(string.len(), value.len())?;
+            string.push_str(value);
+            Ok(string)
+        })
+    }
+
+    /// Overwrites the String value of a key from the byte `offset`, padding it with zero bytes
+    /// if it is too short (or missing). Returns the new length.
+    /// Fails if the result isn't valid UTF-8, e.g. when a multi-byte character is cut in half,
+    /// or if it would be longer than 512MB.
+    pub fn string_set_range(
+        &mut self,
+        key: &str,
+        offset: usize,
+        value: &str,
+    ) -> Result<usize, ValueError> {
+        if value.is_empty() {
+            // Nothing to write, don't create the key.
+            return Ok(self.string_len(key).transpose()?.unwrap_or(0));
+        }
+        let end = _checked_length(offset, value.len())?;
+        self._update_string(key, |string| {
+            let mut bytes = string.into_bytes();
+            if bytes.len() < end {
+                bytes.resize(end, 0);
+            }
+            bytes[offset..end].copy_from_slice(value.as_bytes());
+            String::from_utf8(bytes)
+                .map_err(|e| ValueError::TypeConversionError(TypeConversionError::FromUtf8Error(e)))
+        })
+    }
+
+    /// Gets the bytes between `start` and `end` (both included) of the String value of a key.
+    /// Negative offsets count from the end of the String, -1 being the last byte.
+    pub fn string_range(
+        &self,
+        key: &str,
+        start: i64,
+        end: i64,
+    ) -> Option<Result<String, ValueError>> {
+        let string = match self.get_string(key)? {
+            Ok(string) => string,
+            Err(e) => return Some(Err(e)),
+        };
+        let length = string.len() as i64;
+        let start = if start < 0 { length + start } else { start }.max(0);
+        let end = if end < 0 { length + end } else { end }.min(length - 1);
+        if start > end {
+            return Some(Ok(String::new()));
+        }
+        let bytes = string.as_bytes()[start as usize..=end as usize].to_vec();
+        Some(
+            String::from_utf8(bytes).map_err(|e| {
+                ValueError::TypeConversionError(TypeConversionError::FromUtf8Error(e))
+            }),
+        )
+    }
+
+    /// Gets the length (in bytes) of the String value of a key.
+    pub fn string_len(&self, key: &str) -> Option<Result<usize, ValueError>> {
+        match self._get_or_none_if_expired(key) {
+            Some(ValueEntry {
+                value: ValueType::String(string),
+                ..
+            }) => Some(Ok(string.len())),
+            Some(value_entry) => Some(value_entry.get_value_as_string().map(|string| string.len())),
+            None => None,
+        }
+    }
+
+    /// Sets the String value of a key only if the key doesn't exist, and returns whether it was set.
+    pub fn set_string_if_absent(&mut self, key: &str, value: String, ttl: Option<u64>) -> bool {
+        if self._get_or_none_if_expired(key).is_some() {
+            return false;
+        }
+        self.set_string(key, value, ttl);
+        true
+    }
+
+    /// Sets the String value of a key only if the key exists, and returns whether it was set.
+    pub fn set_string_if_present(&mut self, key: &str, value: String, ttl: Option<u64>) -> bool {
+        if self._get_or_none_if_expired(key).is_none() {
+            return false;
+        }
+        self.set_string(key, value, ttl);
+        true
+    }
+
+    /// Sets the String value of a key and returns the previous one (None if the key didn't exist).
+    /// Nothing is set if the previous value can't be converted to a String.
+    pub fn get_and_set_string(
+        &mut self,
+        key: &str,
+        value: String,
+        ttl: Option<u64>,
+    ) -> Option<Result<String, ValueError>> {
+        let old_value = self.get_string(key);
+        if let Some(Err(e)) = old_value {
+            return Some(Err(e));
+        }
+        self.set_string(key, value, ttl);
+        old_value
+    }
+
+    /// Gets the String values of several keys, None for the keys that don't exist
+    /// or can't be converted to a String.
+    pub fn get_many_strings(&self, keys: &[&str]) -> Vec<Option<String>> {
+        keys.iter()
+            .map(|key| self.get_string(key).and_then(|result| result.ok()))
+            .collect()
+    }
+
+    /// Sets the String values of several keys.
+    pub fn set_many_strings(&mut self, kv_pairs: Vec<(String, String)>, ttl: Option<u64>) {
+        for (key, value) in kv_pairs {
+            self.set_string(&key, value, ttl);
+        }
+    }
+
+    /// Sets the String values of several keys only if none of them exists,
+    /// and returns whether they were set.
+    pub fn set_many_strings_if_absent(
+        &mut self,
+        kv_pairs: Vec<(String, String)>,
+        ttl: Option<u64>,
+    ) -> bool {
+        if kv_pairs
+            .iter()
+            .any(|(key, _)| self._get_or_none_if_expired(key).is_some())
+        {
+            return false;
+        }
+        self.set_many_strings(kv_pairs, ttl);
+        true
+    }
 }
@@ -35,3 +35,106 @@ fn test_pop_string() {
         "HELLO".to_string()
     );
 }
+
+#[test]
+fn test_string_append_and_ranges() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert_eq!(store.string_append("ABC", "HELLO").unwrap(), 5);
+    assert_eq!(store.string_append("ABC", " WORLD").unwrap(), 11);
+    assert_eq!(store.string_len("ABC").unwrap().unwrap(), 11);
+    assert!(store.string_len("nope").is_none());
+
+    assert_eq!(store.string_range("ABC", 0, 4).unwrap().unwrap(), "HELLO");
+    assert_eq!(store.string_range("ABC", -5, -1).unwrap().unwrap(), "WORLD");
+    assert_eq!(store.string_range("ABC", 6, 100).unwrap().unwrap(), "WORLD");
+    assert_eq!(store.string_range("ABC", 5, 2).unwrap().unwrap(), "");
+
+    assert_eq!(store.string_set_range("ABC", 6, "THERE").unwrap(), 11);
+    assert_eq!(store.get_string("ABC").unwrap().unwrap(), "HELLO THERE");
+    assert_eq!(store.string_set_range("DEF", 2, "X").unwrap(), 3);
+    assert_eq!(store.get_string("DEF").unwrap().unwrap(), "\0\0X");
+    assert_eq!(store.string_set_range("GHI", 2, "").unwrap(), 0);
+    assert!(!store.contains_key("GHI"));
+    assert!(store.string_set_range("GHI", usize::MAX, "X").is_err());
+    assert!(store
+        .string_set_range("GHI", 512 * 1024 * 1024, "X")
+        .is_err());
+    assert!(!store.contains_key("GHI"));
+
+    // Counters are appended to as Strings.
+    store.set_i64("counter", 4, None);
+    assert_eq!(store.string_append("counter", "1").unwrap(), 2);
+    assert_eq!(store.get_string("counter").unwrap().unwrap(), "41");
+    assert_eq!(store.incr("counter", None).unwrap(), 42);
+
+    // Cutting a multi-byte character in half is refused.
+    store.set_string("utf8", "héllo".to_string(), None);
+    assert!(store.string_range("utf8", 0, 1).unwrap().is_err());
+    assert!(store.string_set_range("utf8", 2, "x").is_err());
+    assert_eq!(store.get_string("utf8").unwrap().unwrap(), "héllo");
+
+    // Appending keeps the expiration.
+    store.set_string("expiring", "A".to_string(), Some(5000));
+    store.string_append("expiring", "B").unwrap();
+    assert!(store
+        ._get_or_none_if_expired("expiring")
+        .unwrap()
+        .expiration
+        .is_some());
+
+    store.set_list("list", vec!["A".to_string()], None);
+    assert!(store.string_append("list", "B").is_err());
+}
+
+#[test]
+fn test_conditional_and_multi_key_strings() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert!(!store.set_string_if_present("ABC", "A".to_string(), None));
+    assert!(store.set_string_if_absent("ABC", "A".to_string(), None));
+    assert!(!store.set_string_if_absent("ABC", "B".to_string(), None));
+    assert!(store.set_string_if_present("ABC", "C".to_string(), None));
+    assert_eq!(store.get_string("ABC").unwrap().unwrap(), "C");
+
+    assert_eq!(
+        store
+            .get_and_set_string("ABC", "D".to_string(), None)
+            .unwrap()
+            .unwrap(),
+        "C"
+    );
+    assert!(store
+        .get_and_set_string("DEF", "E".to_string(), None)
+        .is_none());
+    assert_eq!(store.get_string("DEF").unwrap().unwrap(), "E");
+
+    store.set_many_strings(
+        vec![
+            ("X".to_string(), "1".to_string()),
+            ("Y".to_string(), "2".to_string()),
+        ],
+        None,
+    );
+    store.set_list("list", vec!["A".to_string()], None);
+    assert_eq!(
+        store.get_many_strings(&["X", "nope", "Y", "list"]),
+        vec![Some("1".to_string()), None, Some("2".to_string()), None]
+    );
+
+    // All or nothing.
+    assert!(!store.set_many_strings_if_absent(
+        vec![
+            ("Z".to_string(), "3".to_string()),
+            ("X".to_string(), "3".to_string()),
+        ],
+        None,
+    ));
+    assert!(!store.contains_key("Z"));
+    assert!(store.set_many_strings_if_absent(
+        vec![
+            ("Z".to_string(), "3".to_string()),
+            ("W".to_string(), "4".to_string()),
+        ],
+        None,
+    ));
+    assert_eq!(store.get_string("W").unwrap().unwrap(), "4");
+}
@@ -0,0 +1,220 @@
+use std::{thread, time::Duration};
+
+use crate::stores::{
+    store::KeyValueStore,
+    text_index::{stem, TextQuery},
+};
+
+fn article(title: &str, body: &str) -> Vec<(String, String)> {
+    vec![
+        ("title".to_string(), title.to_string()),
+        ("body".to_string(), body.to_string()),
+        ("author".to_string(), "someone".to_string()),
+    ]
+}
+
+fn search(store: &KeyValueStore, query: &str) -> Vec<String> {
+    store
+        .text_search("articles", query, 0, None)
+        .unwrap()
+        .unwrap()
+        .matches
+        .into_iter()
+        .map(|text_match| text_match.key)
+        .collect()
+}
+
+fn articles_store() -> KeyValueStore {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.set_hmap(
+        "doc:1",
+        article(
+            "Rust programming language",
+            "Rust is a systems programming language focused on safety.",
+        ),
+        None,
+    );
+    assert!(store.create_text_index("articles", "doc:", &["title", "body"]));
+    assert!(!store.create_text_index("articles", "doc:", &["title"]));
+    store.set_hmap(
+        "doc:2",
+        article(
+            "Python tutorial",
+            "Python programs are easy to write. Programming in Python is fun.",
+        ),
+        None,
+    );
+    store.set_string(
+        "doc:3",
+        "The language of cooking: recipes and programs".to_string(),
+        None,
+    );
+    store.set_hmap("other:1", article("Rust", "Not indexed"), None);
+    store
+}
+
+#[test]
+fn test_stem_and_parse() {
+    for (word, expected) in [
+        ("runs", "run"),
+        ("running", "run"),
+        ("run", "run"),
+        ("studies", "study"),
+        ("studied", "study"),
+        ("hoping", "hop"),
+        ("hope", "hop"),
+        ("dresses", "dress"),
+        ("quickly", "quick"),
+        ("thing", "thing"),
+        ("café", "café"),
+    ] {
+        assert_eq!(stem(word), expected, "{}", word);
+    }
+
+    assert_eq!(
+        TextQuery::parse("Running -fast* OR \"hot dogs\""),
+        Some(TextQuery::Or(vec![
+            TextQuery::And(vec![
+                TextQuery::Term("run".to_string()),
+                TextQuery::Not(Box::new(TextQuery::Prefix("fast".to_string()))),
+            ]),
+            TextQuery::Phrase(vec!["hot".to_string(), "dog".to_string()]),
+        ]))
+    );
+    for invalid in ["", "\"unclosed", "(a", "a)", "*", "OR a", "a AND", "NOT"] {
+        assert_eq!(TextQuery::parse(invalid), None, "{}", invalid);
+    }
+}
+
+#[test]
+fn test_text_search() {
+    let mut store = articles_store();
+    let sorted = |mut keys: Vec<String>| {
+        keys.sort();
+        keys
+    };
+    assert_eq!(
+        sorted(search(&store, "programming")),
+        vec!["doc:1", "doc:2", "doc:3"]
+    );
+    assert_eq!(search(&store, "RUST"), vec!["doc:1"]);
+    assert_eq!(search(&store, "someone"), Vec::<String>::new());
+    assert_eq!(
+        sorted(search(&store, "program -python")),
+        vec!["doc:1", "doc:3"]
+    );
+    assert_eq!(sorted(search(&store, "NOT rust")), vec!["doc:2", "doc:3"]);
+    assert_eq!(
+        sorted(search(&store, "rust OR (python AND fun)")),
+        vec!["doc:1", "doc:2"]
+    );
+    assert_eq!(search(&store, "\"programming language\""), vec!["doc:1"]);
+    assert_eq!(
+        search(&store, "\"language programming\""),
+        Vec::<String>::new()
+    );
+    // Phrases don't span fields.
+    assert_eq!(search(&store, "\"language rust\""), Vec::<String>::new());
+    assert_eq!(search(&store, "cook*"), vec!["doc:3"]);
+
+    // Three mentions of python rank above one in a document of about the same length.
+    store.set_string(
+        "doc:4",
+        "I saw a python at the zoo today with many other animals".to_string(),
+        None,
+    );
+    assert_eq!(search(&store, "python"), vec!["doc:2", "doc:4"]);
+    let results = store
+        .text_search("articles", "python", 1, Some(5))
+        .unwrap()
+        .unwrap();
+    assert_eq!(results.total, 2);
+    assert_eq!(results.matches.len(), 1);
+    assert!(results.matches[0].score > 0.0);
+
+    let results = store
+        .text_search("articles", "cook*", 0, None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        results.matches[0].snippets,
+        vec![(
+            "value".to_string(),
+            "The language of <b>cooking</b>: recipes and programs".to_string()
+        )]
+    );
+    let results = store
+        .text_search("articles", "fun OR tutorials", 0, None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        results.matches[0].snippets,
+        vec![
+            ("title".to_string(), "Python <b>tutorial</b>".to_string()),
+            ("body".to_string(), "...in Python is <b>fun</b>".to_string()),
+        ]
+    );
+
+    assert!(store
+        .text_search("articles", "(rust", 0, None)
+        .unwrap()
+        .is_err());
+    assert!(store.text_search("missing", "rust", 0, None).is_none());
+    assert!(store.drop_text_index("articles"));
+    assert!(!store.drop_text_index("articles"));
+}
+
+#[test]
+fn test_text_index_follows_mutations() {
+    let mut store = articles_store();
+    store
+        .hmap_insert(
+            "doc:1",
+            ("body".to_string(), "Now about ferris".to_string()),
+        )
+        .unwrap();
+    assert_eq!(search(&store, "safety"), Vec::<String>::new());
+    assert_eq!(search(&store, "ferris"), vec!["doc:1"]);
+    store.hmap_remove("doc:1", "title".to_string());
+    assert_eq!(search(&store, "rust"), Vec::<String>::new());
+
+    store.string_append("doc:3", " for crabs").unwrap();
+    assert_eq!(search(&store, "crab"), vec!["doc:3"]);
+    store.bitmap_set_bit("doc:3", 0, true).unwrap();
+    assert_eq!(search(&store, "crab"), Vec::<String>::new());
+
+    assert!(store.rename_key("doc:2", "draft:2"));
+    assert_eq!(search(&store, "python"), Vec::<String>::new());
+    assert!(store.rename_key("draft:2", "doc:2"));
+    assert_eq!(search(&store, "python"), vec!["doc:2"]);
+    assert!(store.remove("doc:2"));
+    assert_eq!(search(&store, "python"), Vec::<String>::new());
+
+    store.set_string("doc:5", "short lived".to_string(), Some(50));
+    store.set_hmap("doc:6", article("Lived", "long"), None);
+    store
+        .hmap_set_field_ttl("doc:6", "title", 50)
+        .unwrap()
+        .unwrap();
+    assert_eq!(search(&store, "lived"), vec!["doc:5", "doc:6"]);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(search(&store, "lived"), Vec::<String>::new());
+    assert_eq!(search(&store, "long"), vec!["doc:6"]);
+    assert_eq!(search(&store, "long -lived"), vec!["doc:6"]);
+    store.clear_all_expired_keys();
+    assert_eq!(search(&store, "lived"), Vec::<String>::new());
+    assert_eq!(search(&store, "-long"), vec!["doc:1"]);
+
+    // Counters aren't Strings anymore.
+    store.set_string("doc:7", "42".to_string(), None);
+    store.set_string("doc:8", "17".to_string(), None);
+    assert_eq!(search(&store, "42 OR 17"), vec!["doc:7", "doc:8"]);
+    store.incr("doc:7", None).unwrap();
+    store.incr_by_float("doc:8", 1.0).unwrap();
+    assert_eq!(search(&store, "42 OR 17"), Vec::<String>::new());
+    assert!(store.text_indexes["articles"].document("doc:7").is_none());
+    assert!(store.text_indexes["articles"].document("doc:8").is_none());
+
+    store.clear();
+    assert_eq!(search(&store, "-long"), Vec::<String>::new());
+}
@@ -0,0 +1,104 @@
+use std::time::Instant;
+
+use crate::stores::{
+    errors::{TypeConversionError, ValueError},
+    store::KeyValueStore,
+    text_index::{TextIndex, TextQuery, TextResults, STRING_FIELD},
+    types::ValueType,
+    value_entry::ValueEntry,
+};
+
+/// Gets the (field, text) pairs a full-text index holds for a value: the String itself,
+/// or the live `fields` of a hmap (in the order of `fields`).
+fn _live_document(
+    value_entry: Option<&ValueEntry>,
+    fields: &[String],
+    now: Instant,
+) -> Vec<(String, String)> {
+    let Some(value_entry) =
+        value_entry.filter(|value_entry| !value_entry.is_expired_entry(Some(now)))
+    else {
+        return vec![];
+    };
+    match &value_entry.value {
+        ValueType::String(string) => vec![(STRING_FIELD.to_owned(), string.to_owned())],
+        ValueType::HashMap(hmap) => fields
+            .iter()
+            .filter(|field| {
+                value_entry
+                    .field_expirations
+                    .get(field.as_str())
+                    .is_none_or(|expiration| now < *expiration)
+            })
+            .filter_map(|field| Some((field.to_owned(), hmap.get(field)?.to_owned())))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+impl KeyValueStore {
+    /// Declares a full-text index over the Strings whose key starts with `prefix`, and over the
+    /// `fields` of the hmaps whose key starts with `prefix`, then indexes the existing keys.
+    /// Returns false if a full-text index with this name already exists.
+    /// The index is then kept up to date on every write, expiry and removal of these keys.
+    pub fn create_text_index(&mut self, name: &str, prefix: &str, fields: &[&str]) -> bool {
+        if self.text_indexes.contains_key(name) {
+            return false;
+        }
+        let now = Instant::now();
+        let mut index = TextIndex::new(
+            prefix,
+            fields.iter().map(|field| field.to_string()).collect(),
+        );
+        for (key, value_entry) in self._data.iter() {
+            if index.covers(key) {
+                let document = _live_document(Some(value_entry), &index.fields, now);
+                index.update(key, document);
+            }
+        }
+        self.text_indexes.insert(name.to_owned(), index);
+        true
+    }
+
+    /// Drops a full-text index, and returns whether it existed.
+    pub fn drop_text_index(&mut self, name: &str) -> bool {
+        self.text_indexes.remove(name).is_some()
+    }
+
+    /// Finds the keys matching a full-text query (see `TextQuery::parse`), the most relevant
+    /// first, then skips `offset` of them and returns at most `limit` with highlighted snippets.
+    /// Returns None if the index doesn't exist.
+    pub fn text_search(
+        &self,
+        name: &str,
+        query: &str,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Option<Result<TextResults, ValueError>> {
+        let index = self.text_indexes.get(name)?;
+        let Some(query) = TextQuery::parse(query) else {
+            return Some(Err(ValueError::TypeConversionError(
+                TypeConversionError::InvalidTextQuery,
+            )));
+        };
+        let now = Instant::now();
+        // Expired keys and fields stay in the index until they are cleared, so skip them here.
+        let live_document = |key: &str| _live_document(self._data.get(key), &index.fields, now);
+        Some(Ok(index.search(&query, offset, limit, live_document)))
+    }
+
+    /// Brings the full-text indexes covering a key up to date with its current value.
+    pub(in crate::stores) fn _update_text_indexes(&mut self, key: &str) {
+        if self.text_indexes.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        let value_entry = self._data.get(key);
+        for index in self.text_indexes.values_mut() {
+            if index.covers(key) {
+                let document = _live_document(value_entry, &index.fields, now);
+                index.update(key, document);
+            }
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::stores::{
+    store::KeyValueStore,
+    timeseries::{Aggregation, Downsampling, LabelFilter},
+    types::ValueKind,
+};
+
+fn downsampling(aggregation: Aggregation, bucket_duration: u64) -> Option<Downsampling> {
+    Downsampling::new(aggregation, bucket_duration)
+}
+
+#[test]
+fn test_ts_add_range_retention() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert!(store.ts_create("temperature", Some(1000), vec![]));
+    assert!(!store.ts_create("temperature", None, vec![]));
+    assert_eq!(store.get_kind("temperature"), Some(ValueKind::TimeSeries));
+    assert_eq!(store.ts_latest("temperature").unwrap().unwrap(), None);
+
+    for (timestamp, value) in [(100, 20.0), (200, 21.0), (300, 23.5), (200, 22.0)] {
+        assert!(store.ts_add("temperature", timestamp, value).unwrap());
+    }
+    assert_eq!(
+        store
+            .ts_range("temperature", 0, u64::MAX, None)
+            .unwrap()
+            .unwrap(),
+        vec![(100, 20.0), (200, 22.0), (300, 23.5)]
+    );
+    assert_eq!(
+        store
+            .ts_range("temperature", 150, 300, None)
+            .unwrap()
+            .unwrap(),
+        vec![(200, 22.0), (300, 23.5)]
+    );
+    assert_eq!(
+        store.ts_latest("temperature").unwrap().unwrap(),
+        Some((300, 23.5))
+    );
+
+    // Samples more than 1000ms older than the latest one are dropped, or refused.
+    assert!(store.ts_add("temperature", 1250, 25.0).unwrap());
+    assert!(!store.ts_add("temperature", 100, 20.0).unwrap());
+    assert_eq!(
+        store
+            .ts_range("temperature", 0, u64::MAX, None)
+            .unwrap()
+            .unwrap(),
+        vec![(300, 23.5), (1250, 25.0)]
+    );
+
+    // Series are created on first add, without retention.
+    assert!(store.ts_add("pressure", 0, 1.0).unwrap());
+    assert!(store.ts_add("pressure", u64::MAX, 1.0).unwrap());
+    assert_eq!(
+        store
+            .ts_range("pressure", 0, u64::MAX, None)
+            .unwrap()
+            .unwrap()
+            .len(),
+        2
+    );
+
+    assert!(store.ts_add("pressure", 1, f64::NAN).is_err());
+    assert!(store.ts_range("nope", 0, 10, None).is_none());
+    store.set_string("string", "abc".to_string(), None);
+    assert!(store.ts_add("string", 0, 1.0).is_err());
+}
+
+#[test]
+fn test_ts_range_aggregations() {
+    let mut store = KeyValueStore::new("new_store", None);
+    for (timestamp, value) in [
+        (0, 1.0),
+        (5, 5.0),
+        (9, 3.0),
+        (10, 10.0),
+        (25, 2.0),
+        (29, 4.0),
+    ] {
+        store.ts_add("series", timestamp, value).unwrap();
+    }
+    let range = |aggregation| {
+        store
+            .ts_range("series", 0, 100, downsampling(aggregation, 10))
+            .unwrap()
+            .unwrap()
+    };
+    assert_eq!(
+        range(Aggregation::Avg),
+        vec![(0, 3.0), (10, 10.0), (20, 3.0)]
+    );
+    assert_eq!(
+        range(Aggregation::Min),
+        vec![(0, 1.0), (10, 10.0), (20, 2.0)]
+    );
+    assert_eq!(
+        range(Aggregation::Max),
+        vec![(0, 5.0), (10, 10.0), (20, 4.0)]
+    );
+    assert_eq!(
+        range(Aggregation::Sum),
+        vec![(0, 9.0), (10, 10.0), (20, 6.0)]
+    );
+    assert_eq!(
+        range(Aggregation::Count),
+        vec![(0, 3.0), (10, 1.0), (20, 2.0)]
+    );
+    assert_eq!(
+        range(Aggregation::Last),
+        vec![(0, 3.0), (10, 10.0), (20, 4.0)]
+    );
+    assert_eq!(downsampling(Aggregation::Avg, 0), None);
+}
+
+#[test]
+fn test_ts_compaction_rules() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.ts_create("raw", None, vec![]);
+    let per_10 = Downsampling::new(Aggregation::Max, 10).unwrap();
+    let per_100 = Downsampling::new(Aggregation::Sum, 100).unwrap();
+    assert!(store
+        .ts_create_rule("raw", "max:10", per_10)
+        .unwrap()
+        .unwrap());
+    assert!(!store
+        .ts_create_rule("raw", "max:10", per_10)
+        .unwrap()
+        .unwrap());
+    assert!(store
+        .ts_create_rule("max:10", "sum:100", per_100)
+        .unwrap()
+        .unwrap());
+    // Rules can't loop back to their source.
+    assert!(!store
+        .ts_create_rule("sum:100", "raw", per_10)
+        .unwrap()
+        .unwrap());
+    assert!(!store.ts_create_rule("raw", "raw", per_10).unwrap().unwrap());
+    assert!(store.ts_create_rule("nope", "max:10", per_10).is_none());
+
+    for (timestamp, value) in [(1, 1.0), (4, 7.0), (12, 2.0), (15, 3.0), (150, 1.0)] {
+        store.ts_add("raw", timestamp, value).unwrap();
+    }
+    assert_eq!(
+        store
+            .ts_range("max:10", 0, u64::MAX, None)
+            .unwrap()
+            .unwrap(),
+        vec![(0, 7.0), (10, 3.0), (150, 1.0)]
+    );
+    assert_eq!(
+        store
+            .ts_range("sum:100", 0, u64::MAX, None)
+            .unwrap()
+            .unwrap(),
+        vec![(0, 10.0), (100, 1.0)]
+    );
+
+    assert!(store.ts_delete_rule("raw", "max:10").unwrap().unwrap());
+    assert!(!store.ts_delete_rule("raw", "max:10").unwrap().unwrap());
+    store.ts_add("raw", 160, 5.0).unwrap();
+    assert_eq!(
+        store.ts_latest("max:10").unwrap().unwrap(),
+        Some((150, 1.0))
+    );
+
+    // Copying or renaming series can make rules loop, the samples then stop going round.
+    assert!(store
+        .ts_create_rule("raw", "max:10", per_10)
+        .unwrap()
+        .unwrap());
+    assert!(store.copy_key("raw", "max:10", true));
+    store.ts_add("max:10", 170, 2.0).unwrap();
+    assert_eq!(
+        store
+            .ts_range("max:10", 170, u64::MAX, None)
+            .unwrap()
+            .unwrap(),
+        vec![(170, 2.0)]
+    );
+    store.ts_create("a", None, vec![]);
+    store.ts_create("c", None, vec![]);
+    assert!(store.ts_create_rule("a", "b", per_10).unwrap().unwrap());
+    assert!(store.ts_create_rule("c", "a", per_10).unwrap().unwrap());
+    assert!(store.copy_key("c", "b", true));
+    store.ts_add("a", 180, 4.0).unwrap();
+    assert_eq!(store.ts_latest("b").unwrap().unwrap(), Some((180, 4.0)));
+}
+
+#[test]
+fn test_ts_query_by_labels() {
+    let mut store = KeyValueStore::new("new_store", None);
+    let labels = |pairs: &[(&str, &str)]| {
+        pairs
+            .iter()
+            .map(|(label, value)| (label.to_string(), value.to_string()))
+            .collect()
+    };
+    store.ts_create("cpu:1", None, labels(&[("metric", "cpu"), ("host", "a")]));
+    store.ts_create("cpu:2", None, labels(&[("metric", "cpu"), ("host", "b")]));
+    store.ts_create("mem:1", None, labels(&[("metric", "mem"), ("host", "a")]));
+    store.ts_create("unlabeled", None, vec![]);
+    for key in ["cpu:1", "cpu:2", "mem:1"] {
+        store.ts_add(key, 10, 1.0).unwrap();
+        store.ts_add(key, 20, 3.0).unwrap();
+    }
+
+    let query = |filters: &[&str]| {
+        let filters: Vec<LabelFilter> = filters
+            .iter()
+            .map(|filter| LabelFilter::parse(filter).unwrap())
+            .collect();
+        store
+            .ts_query(&filters, 0, 100, None)
+            .into_iter()
+            .map(|series| series.key)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(query(&["metric=cpu"]), vec!["cpu:1", "cpu:2"]);
+    assert_eq!(query(&["metric=cpu", "host!=a"]), vec!["cpu:2"]);
+    assert_eq!(query(&["host=a"]), vec!["cpu:1", "mem:1"]);
+    assert_eq!(query(&["metric="]), vec!["unlabeled"]);
+    assert_eq!(query(&["metric!="]), vec!["cpu:1", "cpu:2", "mem:1"]);
+    assert!(LabelFilter::parse("metric").is_none());
+
+    let series = store.ts_query(
+        &[LabelFilter::parse("host=b").unwrap()],
+        0,
+        100,
+        downsampling(Aggregation::Avg, 100),
+    );
+    assert_eq!(series[0].samples, vec![(0, 2.0)]);
+    assert_eq!(series[0].labels.get("metric").unwrap(), "cpu");
+}
@@ -0,0 +1,255 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::stores::{
+    errors::{TypeConversionError, ValueError},
+    store::KeyValueStore,
+    timeseries::{CompactionRule, Downsampling, LabelFilter, Sample, SeriesMatch, TimeSeries},
+    types::ValueType,
+    value_entry::ValueEntry,
+};
+
+/// Longest chain of compaction rules a sample goes through, in case copied or renamed
+/// series made rules loop.
+const MAX_COMPACTION_DEPTH: usize = 32;
+
+impl KeyValueStore {
+    /// Creates an empty time series (with the default time to live of the store),
+    /// and returns false if the key already exists.
+    /// Arguments:
+    /// * `retention` - samples older than this (in milliseconds) compared to the latest sample
+    ///   are dropped, samples are kept forever if None.
+    /// * `labels` - label names and values the series can be queried by.
+    pub fn ts_create(
+        &mut self,
+        key: &str,
+        retention: Option<u64>,
+        labels: Vec<(String, String)>,
+    ) -> bool {
+        if self._get_or_none_if_expired(key).is_some() {
+            return false;
+        }
+        let expiration = self._get_expiration_instant(None);
+        let timeseries = TimeSeries::new(retention, labels.into_iter().collect());
+        self._insert(key, &ValueEntry::from_timeseries(timeseries, expiration));
+        true
+    }
+
+    /// Gets the time series of a key to modify it, creating an empty one
+    /// (without retention nor labels) if needed.
+    fn _get_or_create_mut_timeseries(&mut self, key: &str) -> Result<&mut TimeSeries, ValueError> {
+        self.ts_create(key, None, vec![]);
+        match self._get_mut_or_none_if_expired(key) {
+            Some(value_entry) => value_entry.get_value_as_mut_timeseries(),
+            None => unreachable!("the time series was just created"),
+        }
+    }
+
+    /// Adds a sample to the time series of a key (creating it if needed), replacing the value
+    /// of an existing timestamp, and updates the series its compaction rules write to.
+    /// Returns false if the sample is older than the retention window of the series.
+    /// Arguments:
+    /// * `timestamp` - in milliseconds.
+    pub fn ts_add(&mut self, key: &str, timestamp: u64, value: f64) -> Result<bool, ValueError> {
+        if !value.is_finite() {
+            return Err(ValueError::TypeConversionError(
+                TypeConversionError::NonFiniteFloat,
+            ));
+        }
+        self._ts_add(key, timestamp, value, &mut vec![])
+    }
+
+    /// Adds a sample to a series reached through the compaction rules of the series in `path`.
+    /// Rules leading back to a series of the path are skipped: copying or renaming series
+    /// can make rules loop, even though `ts_create_rule` refuses to.
+    fn _ts_add(
+        &mut self,
+        key: &str,
+        timestamp: u64,
+        value: f64,
+        path: &mut Vec<String>,
+    ) -> Result<bool, ValueError> {
+        let timeseries = self._get_or_create_mut_timeseries(key)?;
+        if !timeseries.add(timestamp, value) {
+            return Ok(false);
+        }
+
+        // The bucket holding the sample is aggregated again, so it is up to date even if
+        // the sample replaced a value or arrived out of order.
+        let compactions: Vec<(String, Sample)> = timeseries
+            .rules
+            .iter()
+            .filter(|rule| rule.destination != key && !path.contains(&rule.destination))
+            .filter_map(|rule| {
+                let bucket_start = rule.downsampling.bucket_start(timestamp);
+                let bucket_end =
+                    bucket_start.saturating_add(rule.downsampling.bucket_duration() - 1);
+                let samples = timeseries.range(bucket_start, bucket_end, Some(rule.downsampling));
+                Some((rule.destination.to_owned(), *samples.first()?))
+            })
+            .collect();
+        if compactions.is_empty() || path.len() >= MAX_COMPACTION_DEPTH {
+            return Ok(true);
+        }
+        path.push(key.to_owned());
+        for (destination, (bucket_start, aggregate)) in compactions {
+            // Rules whose destination was replaced by another kind of value are skipped.
+            let _ = self._ts_add(&destination, bucket_start, aggregate, path);
+        }
+        path.pop();
+        Ok(true)
+    }
+
+    /// Gets the latest sample of the time series of a key, None if the series is empty.
+    pub fn ts_latest(&self, key: &str) -> Option<Result<Option<Sample>, ValueError>> {
+        Some(
+            self._get_or_none_if_expired(key)?
+                .get_value_as_timeseries()
+                .map(|timeseries| timeseries.latest()),
+        )
+    }
+
+    /// Gets the samples of the time series of a key with a timestamp between `from` and `to`
+    /// (inclusive), aggregated into buckets if `downsampling` is given.
+    pub fn ts_range(
+        &self,
+        key: &str,
+        from: u64,
+        to: u64,
+        downsampling: Option<Downsampling>,
+    ) -> Option<Result<Vec<Sample>, ValueError>> {
+        Some(
+            self._get_or_none_if_expired(key)?
+                .get_value_as_timeseries()
+                .map(|timeseries| timeseries.range(from, to, downsampling)),
+        )
+    }
+
+    /// Check whether following the compaction rules from `source` leads to `target`.
+    fn _ts_rules_reach(&self, source: &str, target: &str) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack = vec![source.to_owned()];
+        while let Some(key) = stack.pop() {
+            if key == target {
+                return true;
+            }
+            if !visited.insert(key.to_owned()) {
+                continue;
+            }
+            if let Some(Ok(timeseries)) = self
+                ._get_or_none_if_expired(&key)
+                .map(|value_entry| value_entry.get_value_as_timeseries())
+            {
+                stack.extend(
+                    timeseries
+                        .rules
+                        .iter()
+                        .map(|rule| rule.destination.to_owned()),
+                );
+            }
+        }
+        false
+    }
+
+    /// Adds a compaction rule writing the samples of the time series of `source`, downsampled,
+    /// to the time series of `destination` (created if needed) as new samples are added.
+    /// Samples added before the rule aren't compacted.
+    /// Returns false if `source` already has a rule for `destination`, or if the rule would
+    /// make samples loop back to `source`.
+    pub fn ts_create_rule(
+        &mut self,
+        source: &str,
+        destination: &str,
+        downsampling: Downsampling,
+    ) -> Option<Result<bool, ValueError>> {
+        if let Err(e) = self
+            ._get_or_none_if_expired(source)?
+            .get_value_as_timeseries()
+        {
+            return Some(Err(e));
+        }
+        if let Some(Err(e)) = self
+            ._get_or_none_if_expired(destination)
+            .map(|value_entry| value_entry.get_value_as_timeseries())
+        {
+            return Some(Err(e));
+        }
+        if self._ts_rules_reach(destination, source) {
+            return Some(Ok(false));
+        }
+        self.ts_create(destination, None, vec![]);
+
+        let timeseries = match self._get_mut_or_none_if_expired(source) {
+            Some(value_entry) => value_entry.get_value_as_mut_timeseries(),
+            None => unreachable!("the source time series was checked above"),
+        };
+        Some(timeseries.map(|timeseries| {
+            if timeseries
+                .rules
+                .iter()
+                .any(|rule| rule.destination == destination)
+            {
+                return false;
+            }
+            timeseries.rules.push(CompactionRule {
+                destination: destination.to_owned(),
+                downsampling,
+            });
+            true
+        }))
+    }
+
+    /// Removes the compaction rule of `source` writing to `destination`,
+    /// and returns whether it existed. The destination series is kept.
+    pub fn ts_delete_rule(
+        &mut self,
+        source: &str,
+        destination: &str,
+    ) -> Option<Result<bool, ValueError>> {
+        Some(
+            self._get_mut_or_none_if_expired(source)?
+                .get_value_as_mut_timeseries()
+                .map(|timeseries| {
+                    let rule_count = timeseries.rules.len();
+                    timeseries
+                        .rules
+                        .retain(|rule| rule.destination != destination);
+                    timeseries.rules.len() < rule_count
+                }),
+        )
+    }
+
+    /// Finds the time series whose labels match all the filters, along with their samples
+    /// between `from` and `to` (inclusive), aggregated into buckets if `downsampling` is given.
+    /// Series are sorted by key.
+    /// NOTE: this walks over every key in the store.
+    pub fn ts_query(
+        &self,
+        filters: &[LabelFilter],
+        from: u64,
+        to: u64,
+        downsampling: Option<Downsampling>,
+    ) -> Vec<SeriesMatch> {
+        let matches: BTreeMap<&String, &TimeSeries> = self
+            ._data
+            .iter()
+            .filter(|(_, value_entry)| !value_entry.is_expired_entry(None))
+            .filter_map(|(key, value_entry)| match &value_entry.value {
+                ValueType::TimeSeries(timeseries) => Some((key, timeseries)),
+                _ => None,
+            })
+            .filter(|(_, timeseries)| {
+                filters
+                    .iter()
+                    .all(|filter| filter.matches(&timeseries.labels))
+            })
+            .collect();
+        matches
+            .into_iter()
+            .map(|(key, timeseries)| SeriesMatch {
+                key: key.to_owned(),
+                labels: timeseries.labels.to_owned(),
+                samples: timeseries.range(from, to, downsampling),
+            })
+            .collect()
+    }
+}
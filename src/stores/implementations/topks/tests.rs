@@ -0,0 +1,84 @@
+use crate::stores::{store::KeyValueStore, types::ValueKind};
+
+#[test]
+fn test_topk_add_list() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert!(store.topk_reserve("hitters", 2, 100, 4).unwrap());
+    assert!(!store.topk_reserve("hitters", 2, 100, 4).unwrap());
+    assert!(store.topk_reserve("bad", 0, 100, 4).is_err());
+    assert!(store.topk_reserve("bad", 2, usize::MAX, 4).is_err());
+    assert!(store.topk_reserve("big", usize::MAX, 100, 4).unwrap());
+    assert_eq!(store.get_kind("hitters"), Some(ValueKind::TopK));
+
+    assert_eq!(
+        store.topk_add("hitters", &["a", "b", "a"]).unwrap(),
+        vec![None, None, None]
+    );
+    // "c" needs to be counted more than "b" to push it out.
+    assert_eq!(store.topk_add("hitters", &["c"]).unwrap(), vec![None]);
+    assert_eq!(
+        store
+            .topk_incr("hitters", vec![("c".to_string(), 2)])
+            .unwrap(),
+        vec![Some("b".to_string())]
+    );
+    assert_eq!(
+        store.topk_list("hitters").unwrap().unwrap(),
+        vec![("c".to_string(), 3), ("a".to_string(), 2)]
+    );
+    assert_eq!(
+        store
+            .topk_contains("hitters", &["a", "b", "c"])
+            .unwrap()
+            .unwrap(),
+        vec![true, false, true]
+    );
+    assert_eq!(
+        store.topk_count("hitters", &["b", "d"]).unwrap().unwrap(),
+        vec![1, 0]
+    );
+    assert!(store.topk_list("nope").is_none());
+}
+
+#[test]
+fn test_topk_heavy_hitters_and_merge() {
+    let mut store = KeyValueStore::new("new_store", None);
+    for (key, offset) in [("first", 0), ("second", 1)] {
+        store.topk_reserve(key, 3, 500, 5).unwrap();
+        // "hot:N" elements are counted a lot more than the long tail.
+        for idx in 0..3000 {
+            let element = if idx % 3 == 0 {
+                format!("hot:{}", (idx / 3) % 3 + offset)
+            } else {
+                format!("tail:{}", idx)
+            };
+            store.topk_add(key, &[&element]).unwrap();
+        }
+    }
+    let top = |store: &KeyValueStore, key| {
+        let mut top: Vec<String> = store
+            .topk_list(key)
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|(element, _)| element)
+            .collect();
+        top.sort();
+        top
+    };
+    assert_eq!(top(&store, "first").len(), 3);
+    assert!(top(&store, "first")
+        .iter()
+        .all(|element| element.starts_with("hot:")));
+
+    store.topk_merge("first", &["second"]).unwrap();
+    // hot:1 and hot:2 were counted in both, twice as much as hot:0 and hot:3.
+    let merged = store.topk_list("first").unwrap().unwrap();
+    let mut most_counted = vec![merged[0].0.to_owned(), merged[1].0.to_owned()];
+    most_counted.sort();
+    assert_eq!(most_counted, vec!["hot:1", "hot:2"]);
+    assert!(merged[0].1 >= 666);
+
+    store.topk_reserve("other", 5, 500, 5).unwrap();
+    assert!(store.topk_merge("first", &["other"]).is_err());
+}
@@ -0,0 +1,94 @@
+use crate::stores::{errors::ValueError, store::KeyValueStore, topk::TopK};
+
+/// Parameters of the Top-Ks created on first add.
+const DEFAULT_K: usize = 10;
+const DEFAULT_WIDTH: usize = 1000;
+const DEFAULT_DEPTH: usize = 5;
+
+impl KeyValueStore {
+    /// Creates an empty Top-K (with the default time to live of the store),
+    /// and returns false if the key already exists. See `TopK::new` for the parameters.
+    pub fn topk_reserve(
+        &mut self,
+        key: &str,
+        k: usize,
+        width: usize,
+        depth: usize,
+    ) -> Result<bool, ValueError> {
+        self._sketch_reserve(key, TopK::new(k, width, depth))
+    }
+
+    /// Counts elements more times in the Top-K of a key (creating it with the default
+    /// parameters if needed), and returns for each element the element it pushed out of
+    /// the top list, if any.
+    pub fn topk_incr(
+        &mut self,
+        key: &str,
+        increments: Vec<(String, u64)>,
+    ) -> Result<Vec<Option<String>>, ValueError> {
+        let topk = self._get_or_create_mut_sketch(key, || {
+            TopK::new(DEFAULT_K, DEFAULT_WIDTH, DEFAULT_DEPTH)
+        })?;
+        Ok(increments
+            .iter()
+            .map(|(element, increment)| topk.increment(element, *increment))
+            .collect())
+    }
+
+    /// Same as `topk_incr`, counting each element once.
+    pub fn topk_add(
+        &mut self,
+        key: &str,
+        elements: &[&str],
+    ) -> Result<Vec<Option<String>>, ValueError> {
+        let increments = elements
+            .iter()
+            .map(|element| (element.to_string(), 1))
+            .collect();
+        self.topk_incr(key, increments)
+    }
+
+    /// Checks for each element whether it is in the top list of the Top-K of a key.
+    pub fn topk_contains(
+        &self,
+        key: &str,
+        elements: &[&str],
+    ) -> Option<Result<Vec<bool>, ValueError>> {
+        Some(
+            self._get_or_none_if_expired(key)?
+                .get_value_as_topk()
+                .map(|topk| {
+                    elements
+                        .iter()
+                        .map(|element| topk.contains(element))
+                        .collect()
+                }),
+        )
+    }
+
+    /// Estimates how many times elements were counted in the Top-K of a key,
+    /// even for elements out of the top list.
+    pub fn topk_count(&self, key: &str, elements: &[&str]) -> Option<Result<Vec<u64>, ValueError>> {
+        Some(
+            self._get_or_none_if_expired(key)?
+                .get_value_as_topk()
+                .map(|topk| elements.iter().map(|element| topk.count(element)).collect()),
+        )
+    }
+
+    /// Gets the top list of the Top-K of a key with the estimated counts, the most counted first.
+    pub fn topk_list(&self, key: &str) -> Option<Result<Vec<(String, u64)>, ValueError>> {
+        Some(
+            self._get_or_none_if_expired(key)?
+                .get_value_as_topk()
+                .map(|topk| topk.list()),
+        )
+    }
+
+    /// Adds the counts of the Top-Ks of the given keys to the Top-K of the destination key
+    /// (created from the first of them if needed). Missing keys are ignored,
+    /// and all the Top-Ks must have the same parameters.
+    pub fn topk_merge(&mut self, destination: &str, keys: &[&str]) -> Result<(), ValueError> {
+        self._sketch_merge::<TopK>(destination, keys)
+    }
+}
@@ -0,0 +1,210 @@
+use rand::Rng;
+
+use crate::stores::{
+    store::KeyValueStore,
+    timeseries::LabelFilter,
+    types::ValueKind,
+    vector::{VectorMetric, BRUTE_FORCE_LIMIT},
+};
+
+fn search_ids(store: &KeyValueStore, query: Vec<f32>, k: usize, filters: &[&str]) -> Vec<String> {
+    let filters: Vec<LabelFilter> = filters
+        .iter()
+        .map(|filter| LabelFilter::parse(filter).unwrap())
+        .collect();
+    store
+        .vector_search("embeddings", query, k, &filters)
+        .unwrap()
+        .unwrap()
+        .into_iter()
+        .map(|vector_match| vector_match.id)
+        .collect()
+}
+
+#[test]
+fn test_vector_insert_delete_search() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert!(store
+        .vector_create("embeddings", 2, VectorMetric::L2)
+        .unwrap());
+    assert!(!store
+        .vector_create("embeddings", 3, VectorMetric::L2)
+        .unwrap());
+    assert!(store.vector_create("bad", 0, VectorMetric::L2).is_err());
+    assert_eq!(store.get_kind("embeddings"), Some(ValueKind::VectorIndex));
+
+    let metadata = |lang: &str| vec![("lang".to_string(), lang.to_string())];
+    for (id, values, lang) in [
+        ("a", vec![0.0, 0.0], "en"),
+        ("b", vec![1.0, 0.0], "fr"),
+        ("c", vec![0.0, 2.0], "en"),
+        ("d", vec![3.0, 3.0], "fr"),
+    ] {
+        assert!(store
+            .vector_insert("embeddings", id, values, metadata(lang))
+            .unwrap()
+            .unwrap());
+    }
+    assert!(!store
+        .vector_insert("embeddings", "a", vec![0.1, 0.0], metadata("en"))
+        .unwrap()
+        .unwrap());
+    assert!(store
+        .vector_insert("embeddings", "e", vec![1.0], vec![])
+        .unwrap()
+        .is_err());
+    assert!(store
+        .vector_insert("embeddings", "e", vec![f32::NAN, 1.0], vec![])
+        .unwrap()
+        .is_err());
+    assert_eq!(store.vector_len("embeddings").unwrap().unwrap(), 4);
+
+    assert_eq!(
+        search_ids(&store, vec![0.0, 0.0], 3, &[]),
+        vec!["a", "b", "c"]
+    );
+    assert_eq!(
+        search_ids(&store, vec![0.0, 0.0], 10, &["lang=fr"]),
+        vec!["b", "d"]
+    );
+    let matches = store
+        .vector_search("embeddings", vec![3.0, 0.0], 1, &[])
+        .unwrap()
+        .unwrap();
+    assert_eq!(matches[0].id, "b");
+    assert_eq!(matches[0].distance, 2.0);
+    assert_eq!(matches[0].metadata.get("lang").unwrap(), "fr");
+
+    assert!(store.vector_delete("embeddings", "b").unwrap().unwrap());
+    assert!(!store.vector_delete("embeddings", "b").unwrap().unwrap());
+    assert_eq!(
+        search_ids(&store, vec![0.0, 0.0], 10, &["lang=fr"]),
+        vec!["d"]
+    );
+    assert!(store
+        .vector_search("embeddings", vec![0.0], 1, &[])
+        .unwrap()
+        .is_err());
+    assert!(store.vector_search("nope", vec![0.0], 1, &[]).is_none());
+}
+
+#[test]
+fn test_vector_metrics() {
+    let mut store = KeyValueStore::new("new_store", None);
+    for metric in [VectorMetric::Cosine, VectorMetric::Dot] {
+        store.remove("embeddings");
+        store.vector_create("embeddings", 2, metric).unwrap();
+        store
+            .vector_insert("embeddings", "small", vec![1.0, 0.1], vec![])
+            .unwrap()
+            .unwrap();
+        store
+            .vector_insert("embeddings", "large", vec![10.0, 5.0], vec![])
+            .unwrap()
+            .unwrap();
+        let closest = search_ids(&store, vec![1.0, 0.0], 1, &[]);
+        match metric {
+            // The direction matters, not the length.
+            VectorMetric::Cosine => assert_eq!(closest, vec!["small"]),
+            VectorMetric::Dot => assert_eq!(closest, vec!["large"]),
+            VectorMetric::L2 => unreachable!(),
+        }
+    }
+    assert!(store
+        .vector_insert("embeddings", "zero", vec![0.0, 0.0], vec![])
+        .unwrap()
+        .is_ok());
+    store.remove("embeddings");
+    store
+        .vector_create("embeddings", 2, VectorMetric::Cosine)
+        .unwrap();
+    assert!(store
+        .vector_insert("embeddings", "zero", vec![0.0, 0.0], vec![])
+        .unwrap()
+        .is_err());
+}
+
+#[test]
+fn test_vector_graph_search_recall() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store
+        .vector_create("embeddings", 16, VectorMetric::L2)
+        .unwrap();
+    let mut rng = rand::thread_rng();
+    let mut random_vector = || {
+        (0..16)
+            .map(|_| rng.gen_range(-1.0..1.0))
+            .collect::<Vec<f32>>()
+    };
+    let vectors: Vec<Vec<f32>> = (0..BRUTE_FORCE_LIMIT + 500)
+        .map(|_| random_vector())
+        .collect();
+    for (idx, values) in vectors.iter().enumerate() {
+        let parity = if idx % 2 == 0 { "even" } else { "odd" };
+        store
+            .vector_insert(
+                "embeddings",
+                &idx.to_string(),
+                values.to_owned(),
+                vec![("parity".to_string(), parity.to_string())],
+            )
+            .unwrap()
+            .unwrap();
+    }
+
+    let exact = |query: &[f32], k: usize| {
+        let mut distances: Vec<(f32, String)> = vectors
+            .iter()
+            .enumerate()
+            .map(|(idx, values)| (VectorMetric::L2.distance(query, values), idx.to_string()))
+            .collect();
+        distances.sort_by(|a, b| a.0.total_cmp(&b.0));
+        distances
+            .into_iter()
+            .take(k)
+            .map(|(_, id)| id)
+            .collect::<Vec<_>>()
+    };
+    let (mut found, mut expected) = (0, 0);
+    for _ in 0..20 {
+        let query = random_vector();
+        let approximate = search_ids(&store, query.to_owned(), 10, &[]);
+        let exact = exact(&query, 10);
+        expected += exact.len();
+        found += approximate.iter().filter(|id| exact.contains(id)).count();
+    }
+    assert!(
+        found * 100 >= expected * 90,
+        "recall {}/{}",
+        found,
+        expected
+    );
+
+    // Filtered searches still return k results, all matching.
+    let odd = search_ids(&store, random_vector(), 10, &["parity=odd"]);
+    assert_eq!(odd.len(), 10);
+    assert!(odd.iter().all(|id| id.parse::<usize>().unwrap() % 2 == 1));
+
+    // Deleted vectors left in the graph don't make searches return fewer than k results.
+    for idx in 0..700 {
+        store
+            .vector_delete("embeddings", &idx.to_string())
+            .unwrap()
+            .unwrap();
+    }
+    let remaining = BRUTE_FORCE_LIMIT + 500 - 700;
+    assert_eq!(
+        search_ids(&store, random_vector(), remaining, &[]).len(),
+        remaining
+    );
+
+    // Deleting most of the vectors shrinks the index back to exhaustive searches.
+    for idx in 700..(BRUTE_FORCE_LIMIT + 490) {
+        store
+            .vector_delete("embeddings", &idx.to_string())
+            .unwrap()
+            .unwrap();
+    }
+    assert_eq!(store.vector_len("embeddings").unwrap().unwrap(), 10);
+    assert_eq!(search_ids(&store, random_vector(), 20, &[]).len(), 10);
+}
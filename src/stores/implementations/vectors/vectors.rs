@@ -0,0 +1,95 @@
+use crate::stores::{
+    errors::{TypeConversionError, ValueError},
+    store::KeyValueStore,
+    timeseries::LabelFilter,
+    value_entry::ValueEntry,
+    vector::{VectorIndex, VectorMatch, VectorMetric},
+};
+
+fn _invalid_vector() -> ValueError {
+    ValueError::TypeConversionError(TypeConversionError::InvalidVector)
+}
+
+impl KeyValueStore {
+    /// Creates an empty vector index (with the default time to live of the store),
+    /// and returns false if the key already exists.
+    /// Arguments:
+    /// * `dimension` - number of values of every vector of the index, can't be 0.
+    /// * `metric` - how distances between vectors are measured.
+    pub fn vector_create(
+        &mut self,
+        key: &str,
+        dimension: usize,
+        metric: VectorMetric,
+    ) -> Result<bool, ValueError> {
+        let index = VectorIndex::new(dimension, metric).ok_or_else(_invalid_vector)?;
+        if self._get_or_none_if_expired(key).is_some() {
+            return Ok(false);
+        }
+        let expiration = self._get_expiration_instant(None);
+        self._insert(key, &ValueEntry::from_vector_index(index, expiration));
+        Ok(true)
+    }
+
+    /// Adds a vector with its metadata to the vector index of a key, or replaces the vector
+    /// of an existing id, and returns whether the id is new.
+    pub fn vector_insert(
+        &mut self,
+        key: &str,
+        id: &str,
+        values: Vec<f32>,
+        metadata: Vec<(String, String)>,
+    ) -> Option<Result<bool, ValueError>> {
+        let index = match self
+            ._get_mut_or_none_if_expired(key)?
+            .get_value_as_mut_vector_index()
+        {
+            Ok(index) => index,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(
+            index
+                .insert(id, values, metadata.into_iter().collect())
+                .ok_or_else(_invalid_vector),
+        )
+    }
+
+    /// Removes the vector of an id from the vector index of a key,
+    /// and returns whether it existed.
+    pub fn vector_delete(&mut self, key: &str, id: &str) -> Option<Result<bool, ValueError>> {
+        Some(
+            self._get_mut_or_none_if_expired(key)?
+                .get_value_as_mut_vector_index()
+                .map(|index| index.remove(id)),
+        )
+    }
+
+    /// Gets the number of vectors of the vector index of a key.
+    pub fn vector_len(&self, key: &str) -> Option<Result<usize, ValueError>> {
+        Some(
+            self._get_or_none_if_expired(key)?
+                .get_value_as_vector_index()
+                .map(|index| index.len()),
+        )
+    }
+
+    /// Finds the `k` vectors of the vector index of a key closest to the query, among the
+    /// ones whose metadata matches all the filters, the closest first.
+    /// Large indexes are searched approximately, see `VectorIndex`.
+    pub fn vector_search(
+        &self,
+        key: &str,
+        query: Vec<f32>,
+        k: usize,
+        filters: &[LabelFilter],
+    ) -> Option<Result<Vec<VectorMatch>, ValueError>> {
+        let index = match self
+            ._get_or_none_if_expired(key)?
+            .get_value_as_vector_index()
+        {
+            Ok(index) => index,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(index.search(query, k, filters).ok_or_else(_invalid_vector))
+    }
+}
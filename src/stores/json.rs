@@ -0,0 +1,277 @@
+use serde_json::{Number, Value};
+
+/// One step from a JSON value to one of its children.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum JsonStep {
+    Key(String),
+    Index(usize),
+}
+
+/// Location of a value in a JSON document, as the steps leading to it from the root.
+pub type JsonLocation = Vec<JsonStep>;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Selector {
+    Key(String),
+    /// Negative indexes count from the end of the array.
+    Index(i64),
+    Wildcard,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Segment {
+    selector: Selector,
+    /// Whether the selector applies to every descendant (`..`) rather than to the children.
+    recursive: bool,
+}
+
+/// A parsed JSONPath, supporting the root `$`, children (`.name`, `['name']`, `[0]`, `[-1]`),
+/// wildcards (`.*`, `[*]`) and recursive descent (`..name`, `..*`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+fn _parse_name(chars: &[char], idx: &mut usize) -> Option<String> {
+    let start = *idx;
+    while *idx < chars.len() && (chars[*idx].is_alphanumeric() || "_-".contains(chars[*idx])) {
+        *idx += 1;
+    }
+    (*idx > start).then(|| chars[start..*idx].iter().collect())
+}
+
+fn _parse_bracket(chars: &[char], idx: &mut usize) -> Option<Selector> {
+    // Skip the opening bracket.
+    *idx += 1;
+    let selector = match chars.get(*idx)? {
+        '*' => {
+            *idx += 1;
+            Selector::Wildcard
+        }
+        quote @ ('\'' | '"') => {
+            let end = chars[*idx + 1..].iter().position(|char| char == quote)? + *idx + 1;
+            let name = chars[*idx + 1..end].iter().collect();
+            *idx = end + 1;
+            Selector::Key(name)
+        }
+        _ => {
+            let start = *idx;
+            while *idx < chars.len() && (chars[*idx].is_ascii_digit() || chars[*idx] == '-') {
+                *idx += 1;
+            }
+            let index: String = chars[start..*idx].iter().collect();
+            Selector::Index(index.parse().ok()?)
+        }
+    };
+    if chars.get(*idx) != Some(&']') {
+        return None;
+    }
+    *idx += 1;
+    Some(selector)
+}
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Option<Self> {
+        let chars: Vec<char> = path.trim().chars().collect();
+        if chars.first() != Some(&'$') {
+            return None;
+        }
+        let mut segments = vec![];
+        let mut idx = 1;
+        while idx < chars.len() {
+            let recursive = chars[idx..].starts_with(&['.', '.']);
+            if recursive {
+                idx += 2;
+            } else if chars[idx] == '.' {
+                idx += 1;
+            } else if chars[idx] != '[' {
+                return None;
+            }
+            let selector = match chars.get(idx)? {
+                '[' => _parse_bracket(&chars, &mut idx)?,
+                '*' => {
+                    idx += 1;
+                    Selector::Wildcard
+                }
+                _ if chars[idx - 1] == '.' => Selector::Key(_parse_name(&chars, &mut idx)?),
+                _ => return None,
+            };
+            segments.push(Segment {
+                selector,
+                recursive,
+            });
+        }
+        Some(JsonPath { segments })
+    }
+
+    /// Check whether the path designates the whole document.
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Finds the locations of all the values matching the path in a document.
+    pub fn resolve(&self, document: &Value) -> Vec<JsonLocation> {
+        _resolve(&self.segments, document)
+    }
+
+    /// Splits the path into the path of the parents of the values it designates and the key
+    /// these values have in their parent, if it ends with a plain `.name` or `['name']`.
+    pub fn parent_and_key(&self) -> Option<(JsonPath, &str)> {
+        match self.segments.last()? {
+            Segment {
+                selector: Selector::Key(key),
+                recursive: false,
+            } => Some((
+                JsonPath {
+                    segments: self.segments[..self.segments.len() - 1].to_vec(),
+                },
+                key,
+            )),
+            _ => None,
+        }
+    }
+}
+
+fn _select(value: &Value, location: &JsonLocation, selector: &Selector) -> Vec<JsonLocation> {
+    let child = |step: JsonStep| {
+        let mut location = location.to_owned();
+        location.push(step);
+        location
+    };
+    match (selector, value) {
+        (Selector::Key(key), Value::Object(object)) if object.contains_key(key) => {
+            vec![child(JsonStep::Key(key.to_owned()))]
+        }
+        (Selector::Index(index), Value::Array(array)) => {
+            let index = if *index < 0 {
+                array.len() as i64 + index
+            } else {
+                *index
+            };
+            if (0..array.len() as i64).contains(&index) {
+                vec![child(JsonStep::Index(index as usize))]
+            } else {
+                vec![]
+            }
+        }
+        (Selector::Wildcard, Value::Object(object)) => object
+            .keys()
+            .map(|key| child(JsonStep::Key(key.to_owned())))
+            .collect(),
+        (Selector::Wildcard, Value::Array(array)) => (0..array.len())
+            .map(|idx| child(JsonStep::Index(idx)))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Locations of a value and of all its descendants, the value first.
+fn _descendants(value: &Value, location: JsonLocation) -> Vec<(&Value, JsonLocation)> {
+    let mut descendants = vec![];
+    let mut stack = vec![(value, location)];
+    while let Some((value, location)) = stack.pop() {
+        for child_location in _select(value, &location, &Selector::Wildcard)
+            .into_iter()
+            .rev()
+        {
+            let child = get(value, &child_location[location.len()..]).unwrap();
+            stack.push((child, child_location));
+        }
+        descendants.push((value, location));
+    }
+    descendants
+}
+
+fn _resolve(segments: &[Segment], document: &Value) -> Vec<JsonLocation> {
+    let mut locations = vec![vec![]];
+    for segment in segments {
+        let mut next_locations = vec![];
+        for location in locations {
+            let value = get(document, &location).unwrap();
+            if segment.recursive {
+                for (descendant, descendant_location) in _descendants(value, location) {
+                    next_locations.extend(_select(
+                        descendant,
+                        &descendant_location,
+                        &segment.selector,
+                    ));
+                }
+            } else {
+                next_locations.extend(_select(value, &location, &segment.selector));
+            }
+        }
+        locations = next_locations;
+    }
+    locations
+}
+
+pub fn get<'a>(document: &'a Value, location: &[JsonStep]) -> Option<&'a Value> {
+    location
+        .iter()
+        .try_fold(document, |value, step| match step {
+            JsonStep::Key(key) => value.get(key),
+            JsonStep::Index(idx) => value.get(idx),
+        })
+}
+
+pub fn get_mut<'a>(document: &'a mut Value, location: &[JsonStep]) -> Option<&'a mut Value> {
+    location
+        .iter()
+        .try_fold(document, |value, step| match step {
+            JsonStep::Key(key) => value.get_mut(key),
+            JsonStep::Index(idx) => value.get_mut(idx),
+        })
+}
+
+/// Removes the value at a (non root) location, and returns whether it existed.
+pub fn remove(document: &mut Value, location: &[JsonStep]) -> bool {
+    let Some((step, parent_location)) = location.split_last() else {
+        return false;
+    };
+    match (get_mut(document, parent_location), step) {
+        (Some(Value::Object(object)), JsonStep::Key(key)) => object.shift_remove(key).is_some(),
+        (Some(Value::Array(array)), JsonStep::Index(idx)) if *idx < array.len() => {
+            array.remove(*idx);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Name of the type of a JSON value.
+pub fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Adds `delta` to a number, keeping it an integer if both are integers and the sum fits.
+/// Returns None if the sum isn't finite.
+pub fn add_numbers(number: &Number, delta: &Number) -> Option<Number> {
+    if let (Some(number), Some(delta)) = (number.as_i64(), delta.as_i64()) {
+        if let Some(sum) = number.checked_add(delta) {
+            return Some(Number::from(sum));
+        }
+    }
+    Number::from_f64(number.as_f64()? + delta.as_f64()?)
+}
+
+/// Rough estimate (in bytes) of the memory held by a JSON value.
+pub fn size(value: &Value) -> usize {
+    let children_size = match value {
+        Value::String(string) => string.len(),
+        Value::Array(array) => array.iter().map(size).sum(),
+        Value::Object(object) => object
+            .iter()
+            .map(|(key, value)| key.len() + size(value))
+            .sum(),
+        _ => 0,
+    };
+    std::mem::size_of::<Value>() + children_size
+}
@@ -0,0 +1,56 @@
+use std::{collections::VecDeque, ops::Deref};
+
+/// Items of a list, keeping count of the bytes they hold.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct List {
+    items: VecDeque<String>,
+    /// Bytes held by the items, see `size`.
+    size: usize,
+}
+
+impl Deref for List {
+    type Target = VecDeque<String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.items
+    }
+}
+
+impl FromIterator<String> for List {
+    fn from_iter<I: IntoIterator<Item = String>>(items: I) -> Self {
+        let mut list = List::default();
+        for item in items {
+            list.push_back(item);
+        }
+        list
+    }
+}
+
+impl List {
+    pub fn push_back(&mut self, item: String) {
+        self.size += item.len();
+        self.items.push_back(item);
+    }
+
+    pub fn push_front(&mut self, item: String) {
+        self.size += item.len();
+        self.items.push_front(item);
+    }
+
+    pub fn pop_back(&mut self) -> Option<String> {
+        let item = self.items.pop_back()?;
+        self.size -= item.len();
+        Some(item)
+    }
+
+    pub fn pop_front(&mut self) -> Option<String> {
+        let item = self.items.pop_front()?;
+        self.size -= item.len();
+        Some(item)
+    }
+
+    /// Rough estimate (in bytes) of the memory held by the items.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
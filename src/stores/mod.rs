@@ -1,6 +1,20 @@
-mod errors;
+mod bloom;
+mod countmin;
+mod cuckoo;
+mod cursor;
+pub mod errors;
+mod field_index;
+mod geo;
+mod hashing;
+mod hyperloglog;
 mod implementations;
+mod json;
+mod list;
 pub mod store;
 mod tests;
-mod types;
+mod text_index;
+mod timeseries;
+mod topk;
+pub mod types;
 mod value_entry;
+mod vector;
@@ -1,9 +1,43 @@
-use std::collections::HashMap;
+use rand::seq::IteratorRandom;
+use std::collections::{BTreeMap, HashMap};
 use std::time::{Duration, Instant};
 
-use super::value_entry::ValueEntry;
+use super::{
+    cursor::CursorOrder,
+    field_index::FieldIndex,
+    text_index::TextIndex,
+    types::{ValueKind, ValueType},
+    value_entry::ValueEntry,
+};
+use crate::utils::glob::glob_match;
+
+/// Number of keys a scan looks at when no count is given.
+pub static DEFAULT_SCAN_COUNT: usize = 10;
+
+/// Summary of the contents and settings of a store.
+#[derive(Debug, PartialEq)]
+pub struct StoreInfo {
+    pub name: String,
+    /// Number of keys that aren't expired.
+    pub keys: usize,
+    /// Number of keys that aren't expired yet but have a time to live.
+    pub expiring_keys: usize,
+    pub default_ttl: Option<u64>,
+    pub max_memory: Option<u64>,
+    pub estimated_memory_usage: usize,
+}
+
+/// Rough estimate (in bytes) of the memory held by a key and its value.
+/// Keys are held twice, the second time in their cursor order.
+fn _entry_size(key: &str, value_entry: &ValueEntry) -> usize {
+    2 * key.len() + value_entry.estimated_size()
+}
+
+/// Copy of a key taken by `KeyValueStore::snapshot_key`, None if the key didn't exist.
+pub struct KeySnapshot(Option<ValueEntry>);
 
 /// The main struct of the Key-Value store
+#[derive(Clone)]
 pub struct KeyValueStore {
     /// Identifier of the store
     _name: String,
@@ -13,6 +47,26 @@ pub struct KeyValueStore {
 
     /// The default time to live for each key is set here (globally).
     pub(super) default_ttl: Option<u64>,
+
+    /// Upper bound (in bytes) for the estimated memory usage of the store.
+    max_memory: Option<u64>,
+
+    /// Estimated memory usage of the keys and values, kept up to date while `max_memory`
+    /// is set so that checking the limit doesn't walk over the store. None otherwise.
+    /// The keys in `_changed_keys` aren't counted, they are measured again once changed.
+    _data_usage: Option<usize>,
+
+    /// Keys whose value was handed out to be changed in place since the last write.
+    _changed_keys: Vec<String>,
+
+    /// The keys, in the order `scan` iterates over them.
+    _key_order: CursorOrder,
+
+    /// Secondary indexes over hmap fields, by name.
+    pub(super) indexes: BTreeMap<String, FieldIndex>,
+
+    /// Full-text indexes over strings and hmap fields, by name.
+    pub(super) text_indexes: BTreeMap<String, TextIndex>,
 }
 
 impl KeyValueStore {
@@ -25,6 +79,12 @@ impl KeyValueStore {
             _name: name.to_owned(),
             _data: HashMap::new(),
             default_ttl: default_ttl,
+            max_memory: None,
+            _data_usage: None,
+            _changed_keys: vec![],
+            _key_order: CursorOrder::default(),
+            indexes: BTreeMap::new(),
+            text_indexes: BTreeMap::new(),
         }
     }
 
@@ -32,6 +92,73 @@ impl KeyValueStore {
         self._name.to_owned()
     }
 
+    pub fn set_name(&mut self, name: &str) {
+        self._name = name.to_owned();
+    }
+
+    pub fn get_default_ttl(&self) -> Option<u64> {
+        self.default_ttl
+    }
+
+    /// Changes the default time to live, only keys set from now on are affected.
+    pub fn set_default_ttl(&mut self, default_ttl: Option<u64>) {
+        self.default_ttl = default_ttl;
+    }
+
+    /// Sets the memory limit, the memory usage is then kept up to date as keys are written.
+    pub fn set_max_memory(&mut self, max_memory: Option<u64>) {
+        self.max_memory = max_memory;
+        self._changed_keys.clear();
+        self._data_usage = match max_memory {
+            Some(_) => Some(
+                self._data
+                    .iter()
+                    .map(|(key, value_entry)| _entry_size(key, value_entry))
+                    .sum(),
+            ),
+            None => None,
+        };
+    }
+
+    /// Rough estimate (in bytes) of the memory held by the keys and values of the store.
+    /// NOTE: this walks over every key in the store, unless `max_memory` is set.
+    pub fn estimated_memory_usage(&self) -> usize {
+        let data_usage = match self._data_usage {
+            Some(data_usage) => {
+                data_usage
+                    + self
+                        ._changed_keys
+                        .iter()
+                        .filter_map(|key| Some(_entry_size(key, self._data.get(key)?)))
+                        .sum::<usize>()
+            }
+            None => self
+                ._data
+                .iter()
+                .map(|(key, value_entry)| _entry_size(key, value_entry))
+                .sum(),
+        };
+        data_usage
+            + self
+                .indexes
+                .values()
+                .map(|index| index.size())
+                .sum::<usize>()
+            + self
+                .text_indexes
+                .values()
+                .map(|index| index.size())
+                .sum::<usize>()
+    }
+
+    /// Check whether the estimated memory usage has reached the `max_memory` limit.
+    pub fn is_over_memory_limit(&self) -> bool {
+        match self.max_memory {
+            Some(max_memory) => self.estimated_memory_usage() as u64 >= max_memory,
+            None => false,
+        }
+    }
+
     pub(super) fn _get_expiration_instant(&self, ttl: Option<u64>) -> Option<Instant> {
         if ttl.is_none() && self.default_ttl.is_none() {
             // If the ttl is None and the store allows for infinite ttl
@@ -44,12 +171,51 @@ impl KeyValueStore {
         }
     }
 
+    /// Counts the keys changed in place since the last write in the memory usage again.
+    fn _settle_data_usage(&mut self) {
+        let Some(data_usage) = self._data_usage.as_mut() else {
+            return;
+        };
+        for key in self._changed_keys.drain(..) {
+            if let Some(value_entry) = self._data.get(&key) {
+                *data_usage += _entry_size(&key, value_entry);
+            }
+        }
+    }
+
+    /// Inserts an entry without updating the indexes, keeping the memory usage up to date.
+    pub(super) fn _data_insert(&mut self, key: &str, value_entry: ValueEntry) {
+        self._settle_data_usage();
+        if let Some(data_usage) = self._data_usage.as_mut() {
+            if let Some(replaced) = self._data.get(key) {
+                *data_usage -= _entry_size(key, replaced);
+            }
+            *data_usage += _entry_size(key, &value_entry);
+        }
+        self._key_order.insert(key);
+        self._data.insert(key.to_owned(), value_entry);
+    }
+
+    /// Removes an entry without updating the indexes, keeping the memory usage up to date.
+    pub(super) fn _data_remove(&mut self, key: &str) -> Option<ValueEntry> {
+        self._settle_data_usage();
+        let removed = self._data.remove(key);
+        if let (Some(data_usage), Some(value_entry)) = (self._data_usage.as_mut(), &removed) {
+            *data_usage -= _entry_size(key, value_entry);
+        }
+        self._key_order.remove(key);
+        removed
+    }
+
     pub(super) fn _insert(&mut self, key: &str, value_entry: &ValueEntry) {
-        self._data.insert(key.to_owned(), value_entry.to_owned());
+        self._data_insert(key, value_entry.to_owned());
+        self._update_indexes(key);
     }
 
     pub(super) fn _remove_and_none_if_expired(&mut self, key: &str) -> Option<ValueEntry> {
-        if let Some(value_entry) = self._data.remove(key) {
+        let removed = self._data_remove(key);
+        self._update_indexes(key);
+        if let Some(value_entry) = removed {
             if value_entry.is_expired_entry(None) {
                 None
             } else {
@@ -72,7 +238,18 @@ impl KeyValueStore {
         }
     }
 
+    /// The entry may be changed in place, so it is left out of the memory usage
+    /// until it is measured again.
     pub(super) fn _get_mut_or_none_if_expired(&mut self, key: &str) -> Option<&mut ValueEntry> {
+        self._settle_data_usage();
+        if let (Some(data_usage), Some(value_entry)) =
+            (self._data_usage.as_mut(), self._data.get(key))
+        {
+            if !value_entry.is_expired_entry(None) {
+                *data_usage -= _entry_size(key, value_entry);
+                self._changed_keys.push(key.to_owned());
+            }
+        }
         if let Some(value_entry) = self._data.get_mut(key) {
             if value_entry.is_expired_entry(None) {
                 None
@@ -96,6 +273,26 @@ impl KeyValueStore {
         for key in expired_keys {
             self.remove(key.as_str());
         }
+
+        // Hmaps left empty once their expired fields are gone are removed as well.
+        let keys_with_field_expirations: Vec<String> = self
+            ._data
+            .iter()
+            .filter(|(_, value_entry)| !value_entry.field_expirations.is_empty())
+            .map(|(key, _)| key.to_owned())
+            .collect();
+        for key in keys_with_field_expirations {
+            let Some(value_entry) = self._get_mut_or_none_if_expired(&key) else {
+                continue;
+            };
+            if !value_entry.clear_expired_fields(now) {
+                continue;
+            }
+            if matches!(&value_entry.value, ValueType::HashMap(hmap) if hmap.is_empty()) {
+                self._data_remove(&key);
+            }
+            self._update_indexes(&key);
+        }
     }
 
     /// Check whether the key exists in the store.
@@ -113,13 +310,241 @@ impl KeyValueStore {
         }
     }
 
+    /// Sets the time to live (in milliseconds) of a key, and returns whether the key exists.
+    pub fn set_ttl(&mut self, key: &str, ttl: u64) -> bool {
+        match self._get_mut_or_none_if_expired(key) {
+            Some(value_entry) => {
+                value_entry.expiration = Some(Instant::now() + Duration::from_millis(ttl));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Gets the remaining time to live (in milliseconds) of a key,
+    /// None inside the option if the key never expires.
+    /// Returns None if the key doesn't exist.
+    pub fn get_ttl(&self, key: &str) -> Option<Option<u64>> {
+        let value_entry = self._get_or_none_if_expired(key)?;
+        let now = Instant::now();
+        Some(
+            value_entry
+                .expiration
+                .map(|expiration| expiration.duration_since(now).as_millis() as u64),
+        )
+    }
+
+    /// Removes the time to live of a key, and returns whether it had one.
+    pub fn remove_ttl(&mut self, key: &str) -> bool {
+        match self._get_mut_or_none_if_expired(key) {
+            Some(value_entry) => value_entry.expiration.take().is_some(),
+            None => false,
+        }
+    }
+
     /// Removes the Key-Value pair for the given Key in the KeyValueStore
-    pub fn remove(&mut self, key: &str) {
-        self._remove_and_none_if_expired(key);
+    /// and returns whether a (non expired) key was removed.
+    pub fn remove(&mut self, key: &str) -> bool {
+        self._remove_and_none_if_expired(key).is_some()
+    }
+
+    /// Removes the Key-Value pairs for all the given Keys in the KeyValueStore
+    /// and returns how many (non expired) keys were removed.
+    pub fn remove_many(&mut self, keys: &[&str]) -> usize {
+        keys.iter().filter(|key| self.remove(key)).count()
+    }
+
+    /// Counts how many of the given keys exist, ignoring expired keys.
+    /// A key given several times is counted several times.
+    pub fn count_existing(&self, keys: &[&str]) -> usize {
+        keys.iter()
+            .filter(|key| self._get_or_none_if_expired(key).is_some())
+            .count()
+    }
+
+    /// Gets the kind of the Value associated to the Key in the KeyValueStore
+    pub fn get_kind(&self, key: &str) -> Option<ValueKind> {
+        self._get_or_none_if_expired(key)
+            .map(|value_entry| value_entry.value.kind())
     }
 
-    /// Clear all Key-Value pairs from the KeyValueStore
+    /// Renames a key, keeping its value and expiration. An existing `new_key` is overwritten.
+    /// Returns false if the key doesn't exist.
+    pub fn rename_key(&mut self, key: &str, new_key: &str) -> bool {
+        match self._remove_and_none_if_expired(key) {
+            Some(value_entry) => {
+                self._data_insert(new_key, value_entry);
+                self._update_indexes(new_key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Renames a key only if `new_key` doesn't exist, and returns whether it was renamed.
+    /// Returns None if the key doesn't exist.
+    pub fn rename_key_if_absent(&mut self, key: &str, new_key: &str) -> Option<bool> {
+        self._get_or_none_if_expired(key)?;
+        if self._get_or_none_if_expired(new_key).is_some() {
+            return Some(false);
+        }
+        Some(self.rename_key(key, new_key))
+    }
+
+    /// Copies a key (along with its expiration) to `new_key` in the same store.
+    /// Returns false if the key doesn't exist, or if `new_key` exists and `replace` is false.
+    pub fn copy_key(&mut self, key: &str, new_key: &str, replace: bool) -> bool {
+        let Some(value_entry) = self._get_or_none_if_expired(key) else {
+            return false;
+        };
+        if key == new_key || (!replace && self._get_or_none_if_expired(new_key).is_some()) {
+            return false;
+        }
+        let value_entry = value_entry.to_owned();
+        self._insert(new_key, &value_entry);
+        true
+    }
+
+    /// Takes a copy of a key as it is now (expired or not), to put it back with `restore_key`.
+    pub fn snapshot_key(&self, key: &str) -> KeySnapshot {
+        KeySnapshot(self._data.get(key).cloned())
+    }
+
+    /// Puts a key back the way it was when the snapshot was taken, removing it if it didn't exist.
+    pub fn restore_key(&mut self, key: &str, snapshot: KeySnapshot) {
+        match snapshot.0 {
+            Some(value_entry) => self._insert(key, &value_entry),
+            None => {
+                self._remove_and_none_if_expired(key);
+            }
+        }
+    }
+
+    /// Clear all Key-Value pairs from the KeyValueStore, indexes stay declared but empty.
     pub fn clear(&mut self) {
         self._data.clear();
+        self._key_order.clear();
+        self._changed_keys.clear();
+        self._data_usage = self._data_usage.map(|_| 0);
+        for index in self.indexes.values_mut() {
+            index.clear();
+        }
+        for index in self.text_indexes.values_mut() {
+            index.clear();
+        }
+    }
+
+    fn _live_keys(&self) -> impl Iterator<Item = (&String, &ValueEntry)> {
+        let now = Instant::now();
+        self._data
+            .iter()
+            .filter(move |(_, value_entry)| !value_entry.is_expired_entry(Some(now)))
+    }
+
+    /// Iterates over the keys of the store in batches, without holding any state between calls.
+    /// Start with a cursor of 0 and keep calling with the returned cursor until it is 0 again.
+    /// Each call looks at about `count` keys and returns the ones matching the glob `pattern`
+    /// and the value `kind`, so a batch may be empty even though the iteration isn't complete.
+    /// Keys present for the whole iteration are returned exactly once.
+    pub fn scan(
+        &self,
+        cursor: u64,
+        pattern: Option<&str>,
+        kind: Option<ValueKind>,
+        count: Option<usize>,
+    ) -> (u64, Vec<String>) {
+        let now = Instant::now();
+        let (next_cursor, batch) = self
+            ._key_order
+            .scan(cursor, count.unwrap_or(DEFAULT_SCAN_COUNT));
+        let keys = batch
+            .into_iter()
+            .filter_map(|key| self._data.get_key_value(key))
+            .filter(|(_, value_entry)| !value_entry.is_expired_entry(Some(now)))
+            .filter(|(key, _)| pattern.is_none_or(|pattern| glob_match(pattern, key)))
+            .filter(|(_, value_entry)| kind.is_none_or(|kind| value_entry.value.kind() == kind))
+            .map(|(key, _)| key.to_owned())
+            .collect();
+        (next_cursor, keys)
+    }
+
+    /// Gets all the keys matching the glob pattern.
+    /// NOTE: this walks over every key in the store, prefer `scan` for large stores.
+    pub fn keys(&self, pattern: &str) -> Vec<String> {
+        self._live_keys()
+            .filter(|(key, _)| glob_match(pattern, key))
+            .map(|(key, _)| key.to_owned())
+            .collect()
+    }
+
+    /// Samples up to `count` distinct keys at random, among the ones `is_visible` accepts.
+    pub fn random_keys(&self, count: usize, is_visible: impl Fn(&str) -> bool) -> Vec<String> {
+        self._live_keys()
+            .filter(|(key, _)| is_visible(key))
+            .map(|(key, _)| key.to_owned())
+            .choose_multiple(&mut rand::thread_rng(), count)
+    }
+
+    /// Summarizes the contents and settings of the store.
+    pub fn info(&self) -> StoreInfo {
+        let now = Instant::now();
+        let live_entries = self
+            ._data
+            .values()
+            .filter(|value_entry| !value_entry.is_expired_entry(Some(now)));
+        let (mut keys, mut expiring_keys) = (0, 0);
+        for value_entry in live_entries {
+            keys += 1;
+            if value_entry.expiration.is_some() {
+                expiring_keys += 1;
+            }
+        }
+        StoreInfo {
+            name: self.get_name(),
+            keys,
+            expiring_keys,
+            default_ttl: self.default_ttl,
+            max_memory: self.max_memory,
+            estimated_memory_usage: self.estimated_memory_usage(),
+        }
+    }
+
+    /// Copies the Key-Value pair (along with its expiration) into another store, under a new key.
+    /// Returns false if the key doesn't exist, or if the destination key exists and `replace` is false.
+    pub fn copy_key_to(
+        &self,
+        key: &str,
+        destination: &mut KeyValueStore,
+        destination_key: &str,
+        replace: bool,
+    ) -> bool {
+        let Some(value_entry) = self._get_or_none_if_expired(key) else {
+            return false;
+        };
+        if !replace
+            && destination
+                ._get_or_none_if_expired(destination_key)
+                .is_some()
+        {
+            return false;
+        }
+        destination._insert(destination_key, value_entry);
+        true
+    }
+
+    /// Moves the Key-Value pair (along with its expiration) into another store.
+    /// Returns false if the key doesn't exist, or if it exists in the destination and `replace` is false.
+    pub fn move_key_to(
+        &mut self,
+        key: &str,
+        destination: &mut KeyValueStore,
+        replace: bool,
+    ) -> bool {
+        if !self.copy_key_to(key, destination, key, replace) {
+            return false;
+        }
+        self._data_remove(key);
+        self._update_indexes(key);
+        true
     }
 }
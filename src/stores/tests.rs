@@ -1,3 +1,4 @@
+use crate::stores::types::ValueKind;
 use crate::KeyValueStore;
 use std::time::Duration;
 
@@ -78,6 +79,26 @@ fn test_key_expiry() {
     };
 }
 
+#[test]
+fn test_key_ttl() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.set_string("ABC", "HELLO".to_string(), None);
+    assert_eq!(store.get_ttl("ABC"), Some(None));
+    assert_eq!(store.get_ttl("missing"), None);
+    assert!(!store.set_ttl("missing", 100));
+
+    assert!(store.set_ttl("ABC", 5000));
+    assert!(store.get_ttl("ABC").unwrap().unwrap() > 4000);
+    assert!(store.remove_ttl("ABC"));
+    assert!(!store.remove_ttl("ABC"));
+    assert_eq!(store.get_ttl("ABC"), Some(None));
+
+    assert!(store.set_ttl("ABC", 50));
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(store.get_string("ABC").is_none());
+    assert!(!store.remove_ttl("ABC"));
+}
+
 #[test]
 fn test_clear_store() {
     let mut store = KeyValueStore::new("new_store", None);
@@ -101,3 +122,202 @@ fn test_clear_store() {
         Some(_) => assert!(false),
     };
 }
+
+#[test]
+fn test_scan() {
+    let mut store = KeyValueStore::new("new_store", None);
+    for idx in 0..100 {
+        store.set_i64(&format!("key:{}", idx), idx, None);
+    }
+    store.set_string("expired", "HELLO".to_string(), Some(0));
+
+    let mut scanned = vec![];
+    let mut cursor = 0;
+    loop {
+        let (next_cursor, keys) = store.scan(cursor, None, None, Some(7));
+        assert!(keys.len() <= 8);
+        scanned.extend(keys);
+
+        // Keys added or removed during the iteration don't disturb the others.
+        if cursor == 0 {
+            store.set_i64("added", 1, None);
+            store.remove("key:99");
+        }
+        cursor = next_cursor;
+        if cursor == 0 {
+            break;
+        }
+    }
+
+    let mut expected: Vec<String> = (0..99).map(|idx| format!("key:{}", idx)).collect();
+    scanned.retain(|key| key != "added" && key != "key:99");
+    scanned.sort();
+    expected.sort();
+    assert_eq!(scanned, expected);
+}
+
+#[test]
+fn test_scan_with_filters() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.set_string("user:1", "HELLO".to_string(), None);
+    store.set_string("user:2", "HELLO".to_string(), None);
+    store.set_i64("user:3", 3, None);
+    store.set_string("order:1", "HELLO".to_string(), None);
+
+    let (cursor, mut keys) = store.scan(0, Some("user:*"), Some(ValueKind::String), Some(100));
+    keys.sort();
+    assert_eq!(cursor, 0);
+    assert_eq!(keys, vec!["user:1", "user:2"]);
+
+    let mut keys = store.keys("user:?");
+    keys.sort();
+    assert_eq!(keys, vec!["user:1", "user:2", "user:3"]);
+    assert_eq!(store.keys("*:1").len(), 2);
+}
+
+#[test]
+fn test_random_keys() {
+    let mut store = KeyValueStore::new("new_store", None);
+    assert!(store.random_keys(1, |_| true).is_empty());
+
+    store.set_string("ABC", "HELLO".to_string(), None);
+    store.set_string("XYZ", "HELLO".to_string(), None);
+    store.set_string("expired", "HELLO".to_string(), Some(0));
+
+    let mut keys = store.random_keys(5, |_| true);
+    keys.sort();
+    assert_eq!(keys, vec!["ABC", "XYZ"]);
+
+    let key = store.random_keys(1, |_| true).pop().unwrap();
+    assert!(key == "ABC" || key == "XYZ");
+    assert_eq!(store.random_keys(5, |key| key != "ABC"), vec!["XYZ"]);
+}
+
+#[test]
+fn test_generic_key_operations() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.set_string("ABC", "HELLO".to_string(), Some(5000));
+    store.set_string("XYZ", "WORLD".to_string(), None);
+    store.set_string("expired", "HELLO".to_string(), Some(0));
+
+    assert_eq!(store.get_kind("ABC"), Some(ValueKind::String));
+    assert_eq!(store.get_kind("expired"), None);
+    assert_eq!(
+        store.count_existing(&["ABC", "ABC", "XYZ", "expired", "nope"]),
+        3
+    );
+
+    assert!(store.copy_key("ABC", "copy", false));
+    assert!(!store.copy_key("ABC", "XYZ", false));
+    assert!(!store.copy_key("ABC", "ABC", true));
+    assert!(!store.is_expired("copy").unwrap());
+    assert!(store
+        ._get_or_none_if_expired("copy")
+        .unwrap()
+        .expiration
+        .is_some());
+
+    assert_eq!(store.rename_key_if_absent("copy", "XYZ"), Some(false));
+    assert_eq!(store.rename_key_if_absent("nope", "other"), None);
+    assert!(store.rename_key("copy", "XYZ"));
+    assert_eq!(store.get_string("XYZ").unwrap().unwrap(), "HELLO");
+    assert!(!store.rename_key("copy", "XYZ"));
+
+    assert_eq!(store.remove_many(&["ABC", "XYZ", "expired", "nope"]), 2);
+    assert!(!store.remove("ABC"));
+}
+
+#[test]
+fn test_memory_usage_tracking() {
+    let mut store = KeyValueStore::new("new_store", None);
+    store.set_string("ABC", "HELLO".to_string(), None);
+    store.set_max_memory(Some(1 << 20));
+
+    // The usage kept up to date as keys are written matches a walk over the store.
+    let measured_usage = |store: &KeyValueStore| {
+        let mut store = store.clone();
+        store.set_max_memory(None);
+        store.estimated_memory_usage()
+    };
+    store.set_string("XYZ", "HELLO".to_string(), Some(5000));
+    store.set_list("list", vec!["a".to_owned()], None);
+    store.list_pushb("list", "a much longer item".to_owned());
+    store
+        .hmap_insert("hmap", ("field".to_owned(), "value".to_owned()))
+        .unwrap();
+    store.incr("counter", Some(10)).unwrap();
+    store.string_append("ABC", "WORLD").unwrap();
+    assert_eq!(store.estimated_memory_usage(), measured_usage(&store));
+
+    assert!(store.rename_key("XYZ", "ABC"));
+    assert!(store.copy_key("list", "copy", false));
+    store.list_popf("list");
+    store.remove("hmap");
+    assert_eq!(store.estimated_memory_usage(), measured_usage(&store));
+
+    store.set_string("expired", "HELLO".to_string(), Some(0));
+    store.clear_all_expired_keys();
+    assert_eq!(store.estimated_memory_usage(), measured_usage(&store));
+
+    store.clear();
+    assert_eq!(store.estimated_memory_usage(), 0);
+}
+
+#[test]
+fn test_running_sizes() {
+    use crate::stores::{
+        cursor::{CursorMap, CursorSet},
+        geo::GeoSet,
+        list::List,
+        vector::{VectorIndex, VectorMetric},
+    };
+    use std::collections::BTreeMap;
+
+    // Sizes counted as the collections change match the ones of collections built at once.
+    let mut map = CursorMap::default();
+    map.insert("a".to_owned(), "short".to_owned());
+    map.insert("b".to_owned(), "value".to_owned());
+    map.insert("a".to_owned(), "a longer value".to_owned());
+    map.remove("b");
+    let built: CursorMap = [("a".to_owned(), "a longer value".to_owned())]
+        .into_iter()
+        .collect();
+    assert_eq!(map.size(), built.size());
+    assert_eq!(built.size(), 2 + 14);
+
+    let mut set = CursorSet::default();
+    set.insert("a".to_owned());
+    set.insert("a".to_owned());
+    set.insert("bcd".to_owned());
+    set.remove("a");
+    set.remove("nope");
+    assert_eq!(set.size(), 6);
+
+    let mut list = List::default();
+    list.push_back("abc".to_owned());
+    list.push_front("de".to_owned());
+    list.pop_back();
+    list.pop_back();
+    list.pop_back();
+    list.push_back("f".to_owned());
+    assert_eq!(list.size(), 1);
+
+    let mut geo = GeoSet::new();
+    geo.insert("a".to_owned(), 1.0, 2.0);
+    geo.insert("b".to_owned(), 1.0, 2.0);
+    geo.insert("a".to_owned(), 3.0, 4.0);
+    geo.remove("b");
+    let mut built = GeoSet::new();
+    built.insert("a".to_owned(), 3.0, 4.0);
+    assert_eq!(geo.size(), built.size());
+
+    let mut index = VectorIndex::new(2, VectorMetric::L2).unwrap();
+    index.insert("a", vec![1.0, 2.0], BTreeMap::new());
+    index.insert("b", vec![1.0, 2.0], BTreeMap::new());
+    index.insert("b", vec![3.0, 4.0], BTreeMap::new());
+    index.remove("a");
+    let mut built = VectorIndex::new(2, VectorMetric::L2).unwrap();
+    built.insert("b", vec![3.0, 4.0], BTreeMap::new());
+    // Both deleted slots are dropped once they are the majority.
+    assert_eq!(index.size(), built.size());
+}
@@ -0,0 +1,633 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap},
+};
+
+/// Name of the field a String value is indexed (and highlighted) under.
+pub const STRING_FIELD: &str = "value";
+/// Marks around the words of a snippet matching the query.
+pub const HIGHLIGHT_START: &str = "<b>";
+pub const HIGHLIGHT_END: &str = "</b>";
+/// Term frequency saturation of BM25.
+const BM25_K1: f64 = 1.2;
+/// Document length normalization of BM25.
+const BM25_B: f64 = 0.75;
+/// Number of words of a snippet.
+const SNIPPET_WORDS: usize = 12;
+/// Number of words of a snippet before its first match.
+const SNIPPET_CONTEXT: usize = 3;
+
+/// A word of a text, with its position (in bytes) in the text.
+struct Token {
+    term: String,
+    start: usize,
+    end: usize,
+}
+
+fn has_vowel(word: &str) -> bool {
+    word.chars().any(|c| "aeiouy".contains(c))
+}
+
+/// Reduces a lowercase word to its stem with a few English suffix rules, so that
+/// e.g. "runs", "running" and "run" are the same term. Other words are kept as is.
+pub fn stem(word: &str) -> String {
+    if word.len() <= 3 || !word.chars().all(|c| c.is_ascii_lowercase()) {
+        return word.to_owned();
+    }
+    let mut stem = word.to_owned();
+    if stem.ends_with("sses") {
+        stem.truncate(stem.len() - 2);
+    } else if stem.ends_with("ies") || stem.ends_with("ied") {
+        stem.truncate(stem.len() - 3);
+        stem.push('y');
+    } else if stem.ends_with('s') && !["ss", "us", "is"].iter().any(|s| stem.ends_with(s)) {
+        stem.pop();
+    }
+    for suffix in ["ing", "ed", "ly", "ness"] {
+        let Some(base) = stem.strip_suffix(suffix) else {
+            continue;
+        };
+        if base.len() < 3 || !has_vowel(base) {
+            break;
+        }
+        stem.truncate(base.len());
+        let bytes = stem.as_bytes();
+        let (last, before_last) = (bytes[bytes.len() - 1], bytes[bytes.len() - 2]);
+        if (suffix == "ing" || suffix == "ed")
+            && last == before_last
+            && !b"aeiouylsz".contains(&last)
+        {
+            stem.pop();
+        }
+        break;
+    }
+    if stem.len() > 3 && stem.ends_with('e') {
+        stem.pop();
+    }
+    stem
+}
+
+/// Splits a text in lowercase alphanumeric words, and stems them.
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut start = None;
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(word_start)) => {
+                tokens.push(Token {
+                    term: stem(&text[word_start..i].to_lowercase()),
+                    start: word_start,
+                    end: i,
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+/// A parsed full-text query.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TextQuery {
+    Term(String),
+    /// Terms starting with this (lowercase) prefix, terms are matched as stemmed.
+    Prefix(String),
+    /// Terms following each other in the same field.
+    Phrase(Vec<String>),
+    And(Vec<TextQuery>),
+    Or(Vec<TextQuery>),
+    Not(Box<TextQuery>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum QueryToken {
+    Word(String),
+    Quoted(String),
+    Minus,
+    Open,
+    Close,
+}
+
+fn lex_query(query: &str) -> Option<Vec<QueryToken>> {
+    let mut tokens = vec![];
+    let mut chars = query.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(QueryToken::Open),
+            ')' => tokens.push(QueryToken::Close),
+            '-' => tokens.push(QueryToken::Minus),
+            '"' => {
+                let mut phrase = String::new();
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        c => phrase.push(c),
+                    }
+                }
+                tokens.push(QueryToken::Quoted(phrase));
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "()\"".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(QueryToken::Word(word));
+            }
+        }
+    }
+    Some(tokens)
+}
+
+fn phrase_query(text: &str) -> Option<TextQuery> {
+    let mut terms: Vec<String> = tokenize(text).into_iter().map(|token| token.term).collect();
+    match terms.len() {
+        0 => None,
+        1 => terms.pop().map(TextQuery::Term),
+        _ => Some(TextQuery::Phrase(terms)),
+    }
+}
+
+struct QueryParser {
+    tokens: Vec<QueryToken>,
+    position: usize,
+}
+
+impl QueryParser {
+    fn peek(&self) -> Option<&QueryToken> {
+        self.tokens.get(self.position)
+    }
+
+    fn is_operator(&self, operator: &str) -> bool {
+        matches!(self.peek(), Some(QueryToken::Word(word)) if word == operator)
+    }
+
+    fn parse_or(&mut self) -> Option<TextQuery> {
+        let mut branches = vec![self.parse_and()?];
+        while self.is_operator("OR") {
+            self.position += 1;
+            branches.push(self.parse_and()?);
+        }
+        Some(match branches.len() {
+            1 => branches.pop()?,
+            _ => TextQuery::Or(branches),
+        })
+    }
+
+    fn parse_and(&mut self) -> Option<TextQuery> {
+        let mut items = vec![];
+        loop {
+            match self.peek() {
+                None | Some(QueryToken::Close) => break,
+                _ if self.is_operator("OR") => break,
+                _ if self.is_operator("AND") => {
+                    if items.is_empty() {
+                        return None;
+                    }
+                    self.position += 1;
+                    items.push(self.parse_unary()?);
+                }
+                _ => items.push(self.parse_unary()?),
+            }
+        }
+        match items.len() {
+            0 => None,
+            1 => items.pop(),
+            _ => Some(TextQuery::And(items)),
+        }
+    }
+
+    fn parse_unary(&mut self) -> Option<TextQuery> {
+        if self.is_operator("NOT") {
+            self.position += 1;
+            return Some(TextQuery::Not(Box::new(self.parse_unary()?)));
+        }
+        let token = self.tokens.get(self.position)?.to_owned();
+        self.position += 1;
+        match token {
+            QueryToken::Minus => Some(TextQuery::Not(Box::new(self.parse_unary()?))),
+            QueryToken::Open => {
+                let query = self.parse_or()?;
+                match self.peek() {
+                    Some(QueryToken::Close) => {
+                        self.position += 1;
+                        Some(query)
+                    }
+                    _ => None,
+                }
+            }
+            QueryToken::Close => None,
+            QueryToken::Quoted(phrase) => phrase_query(&phrase),
+            QueryToken::Word(word) => match word.strip_suffix('*') {
+                Some(prefix) => {
+                    let prefix = prefix.to_lowercase();
+                    let is_word = !prefix.is_empty() && prefix.chars().all(char::is_alphanumeric);
+                    is_word.then_some(TextQuery::Prefix(prefix))
+                }
+                None => phrase_query(&word),
+            },
+        }
+    }
+}
+
+impl TextQuery {
+    /// Parses a query made of:
+    /// * `word` - documents with the word (or another word with the same stem)
+    /// * `word*` - documents with a word starting with the prefix
+    /// * `"some words"` - documents with these words following each other in a field
+    /// * `a b` / `a AND b` - documents matching both, `a OR b` - documents matching either
+    /// * `-a` / `NOT a` - documents not matching, `( ... )` - grouping
+    pub fn parse(query: &str) -> Option<Self> {
+        let mut parser = QueryParser {
+            tokens: lex_query(query)?,
+            position: 0,
+        };
+        let query = parser.parse_or()?;
+        (parser.position == parser.tokens.len()).then_some(query)
+    }
+}
+
+/// A document found by a search.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextMatch {
+    pub key: String,
+    /// BM25 score, higher is better.
+    pub score: f64,
+    /// (field, snippet) for the fields with matching words, which are highlighted.
+    pub snippets: Vec<(String, String)>,
+}
+
+/// Documents found by a search, the best first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextResults {
+    /// Number of documents matching the query, whatever the offset and limit.
+    pub total: usize,
+    pub matches: Vec<TextMatch>,
+}
+
+/// Whether each indexed field of a document is still live, for the documents with live fields.
+type LiveFields<'a> = BTreeMap<&'a String, Vec<bool>>;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Document {
+    /// (field, text) of the indexed fields.
+    fields: Vec<(String, String)>,
+    /// Number of words of all the fields.
+    length: usize,
+    /// Rough estimate (in bytes) of the memory the index holds for the document.
+    size: usize,
+}
+
+/// Inverted index over the text of the Strings, and of some fields of the hmaps,
+/// whose key starts with a prefix.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextIndex {
+    pub prefix: String,
+    pub fields: Vec<String>,
+    documents: HashMap<String, Document>,
+    /// Positions (field, word) of each term in each document.
+    postings: BTreeMap<String, BTreeMap<String, Vec<(usize, usize)>>>,
+    total_length: usize,
+    /// Rough estimate (in bytes) of the memory held by the index, kept up to date.
+    size: usize,
+}
+
+impl TextIndex {
+    pub fn new(prefix: &str, fields: Vec<String>) -> Self {
+        TextIndex {
+            prefix: prefix.to_owned(),
+            fields,
+            ..TextIndex::default()
+        }
+    }
+
+    pub fn covers(&self, key: &str) -> bool {
+        key.starts_with(&self.prefix)
+    }
+
+    /// Gets the (field, text) pairs indexed for a key.
+    pub fn document(&self, key: &str) -> Option<&[(String, String)]> {
+        self.documents
+            .get(key)
+            .map(|document| document.fields.as_slice())
+    }
+
+    /// Indexes the (field, text) pairs of a key in place of the previous ones,
+    /// no pairs removes the key from the index.
+    pub fn update(&mut self, key: &str, fields: Vec<(String, String)>) {
+        if self.document(key).unwrap_or_default() == fields.as_slice() {
+            return;
+        }
+        if let Some(document) = self.documents.remove(key) {
+            self.total_length -= document.length;
+            self.size -= document.size;
+            for (_, text) in document.fields.iter() {
+                for token in tokenize(text) {
+                    if let Some(positions) = self.postings.get_mut(&token.term) {
+                        positions.remove(key);
+                        if positions.is_empty() {
+                            self.postings.remove(&token.term);
+                        }
+                    }
+                }
+            }
+        }
+        if fields.is_empty() {
+            return;
+        }
+        let mut length = 0;
+        let mut size = key.len();
+        for (field_position, (field, text)) in fields.iter().enumerate() {
+            size += field.len() + text.len();
+            for (position, token) in tokenize(text).into_iter().enumerate() {
+                // Terms are counted for each document they appear in.
+                let term_size = token.term.len();
+                let positions = self.postings.entry(token.term).or_default();
+                if !positions.contains_key(key) {
+                    size += term_size + key.len();
+                }
+                size += 16;
+                positions
+                    .entry(key.to_owned())
+                    .or_default()
+                    .push((field_position, position));
+                length += 1;
+            }
+        }
+        self.total_length += length;
+        self.size += size;
+        self.documents.insert(
+            key.to_owned(),
+            Document {
+                fields,
+                length,
+                size,
+            },
+        );
+    }
+
+    /// Forgets every document, the index stays declared.
+    pub fn clear(&mut self) {
+        self.documents.clear();
+        self.postings.clear();
+        self.total_length = 0;
+        self.size = 0;
+    }
+
+    fn _prefixed_terms<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a String> + 'a {
+        self.postings
+            .range(prefix.to_owned()..)
+            .map(|(term, _)| term)
+            .take_while(move |term| term.starts_with(prefix))
+    }
+
+    /// Positions of a term in a document, in the fields that aren't expired
+    /// (all of them when `live` is None).
+    fn _live_positions<'a>(
+        &'a self,
+        term: &str,
+        key: &String,
+        live: Option<&'a LiveFields>,
+    ) -> impl Iterator<Item = &'a (usize, usize)> + 'a {
+        let live_fields = live.map(|live| live.get(key));
+        self.postings
+            .get(term)
+            .and_then(|keys| keys.get(key))
+            .into_iter()
+            .flatten()
+            .filter(move |(field, _)| match live_fields {
+                Some(live_fields) => live_fields.is_some_and(|fields| fields[*field]),
+                None => true,
+            })
+    }
+
+    /// Keys of the live documents (of all the documents when `live` is None).
+    fn _live_keys<'a>(&'a self, live: Option<&'a LiveFields>) -> BTreeSet<&'a String> {
+        match live {
+            Some(live) => live.keys().copied().collect(),
+            None => self.documents.keys().collect(),
+        }
+    }
+
+    fn _term_keys<'a>(&'a self, term: &str, live: Option<&'a LiveFields>) -> BTreeSet<&'a String> {
+        self.postings
+            .get(term)
+            .into_iter()
+            .flat_map(|keys| keys.keys())
+            .filter(|key| self._live_positions(term, key, live).next().is_some())
+            .collect()
+    }
+
+    fn _has_phrase(&self, key: &String, terms: &[String], live: Option<&LiveFields>) -> bool {
+        self._live_positions(&terms[0], key, live)
+            .any(|(field, start)| {
+                terms.iter().enumerate().skip(1).all(|(i, term)| {
+                    self.postings
+                        .get(term)
+                        .and_then(|keys| keys.get(key))
+                        .is_some_and(|positions| {
+                            positions.binary_search(&(*field, start + i)).is_ok()
+                        })
+                })
+            })
+    }
+
+    /// Keys of the documents matching the query. When `live` is None every field counts as
+    /// live and nothing is excluded, which gives a superset of the live matches.
+    fn _matches<'a>(
+        &'a self,
+        query: &TextQuery,
+        live: Option<&'a LiveFields>,
+    ) -> BTreeSet<&'a String> {
+        match query {
+            TextQuery::Term(term) => self._term_keys(term, live),
+            TextQuery::Prefix(prefix) => self
+                ._prefixed_terms(prefix)
+                .flat_map(|term| self._term_keys(term, live))
+                .collect(),
+            TextQuery::Phrase(terms) => self
+                ._term_keys(&terms[0], live)
+                .into_iter()
+                .filter(|key| self._has_phrase(key, terms, live))
+                .collect(),
+            TextQuery::And(items) => {
+                let mut keys: Option<BTreeSet<&String>> = None;
+                for item in items
+                    .iter()
+                    .filter(|item| !matches!(item, TextQuery::Not(_)))
+                {
+                    let item_keys = self._matches(item, live);
+                    keys = Some(match keys {
+                        Some(keys) => keys.intersection(&item_keys).copied().collect(),
+                        None => item_keys,
+                    });
+                }
+                let mut keys = keys.unwrap_or_else(|| self._live_keys(live));
+                for item in items.iter().filter(|_| live.is_some()) {
+                    if let TextQuery::Not(excluded) = item {
+                        for key in self._matches(excluded, live) {
+                            keys.remove(key);
+                        }
+                    }
+                }
+                keys
+            }
+            TextQuery::Or(branches) => branches
+                .iter()
+                .flat_map(|branch| self._matches(branch, live))
+                .collect(),
+            TextQuery::Not(excluded) => {
+                let mut keys = self._live_keys(live);
+                if live.is_some() {
+                    for key in self._matches(excluded, live) {
+                        keys.remove(key);
+                    }
+                }
+                keys
+            }
+        }
+    }
+
+    /// Collects the terms a query looks for (the ones it excludes aside).
+    fn _positive_terms(&self, query: &TextQuery, terms: &mut BTreeSet<String>) {
+        match query {
+            TextQuery::Term(term) => {
+                terms.insert(term.to_owned());
+            }
+            TextQuery::Prefix(prefix) => terms.extend(self._prefixed_terms(prefix).cloned()),
+            TextQuery::Phrase(phrase) => terms.extend(phrase.iter().cloned()),
+            TextQuery::And(items) | TextQuery::Or(items) => {
+                for item in items {
+                    self._positive_terms(item, terms);
+                }
+            }
+            TextQuery::Not(_) => {}
+        }
+    }
+
+    fn _score(&self, key: &String, terms: &BTreeSet<String>, live: &LiveFields) -> f64 {
+        let document_length = self.documents[key].length as f64;
+        let average_length = self.total_length as f64 / self.documents.len().max(1) as f64;
+        let normalization = 1.0 - BM25_B + BM25_B * document_length / average_length.max(1.0);
+        terms
+            .iter()
+            .filter_map(|term| {
+                let frequency = self._live_positions(term, key, Some(live)).count() as f64;
+                if frequency == 0.0 {
+                    return None;
+                }
+                let with_term = self.postings[term].len() as f64;
+                let idf = (1.0
+                    + (self.documents.len() as f64 - with_term + 0.5).max(0.0) / (with_term + 0.5))
+                    .ln();
+                Some(idf * frequency * (BM25_K1 + 1.0) / (frequency + BM25_K1 * normalization))
+            })
+            .sum()
+    }
+
+    fn _snippets(
+        &self,
+        key: &String,
+        terms: &BTreeSet<String>,
+        live: &LiveFields,
+    ) -> Vec<(String, String)> {
+        let mut snippets = vec![];
+        for (field_position, (field, text)) in self.documents[key].fields.iter().enumerate() {
+            if !live[key][field_position] {
+                continue;
+            }
+            let tokens = tokenize(text);
+            let Some(first_match) = tokens.iter().position(|token| terms.contains(&token.term))
+            else {
+                continue;
+            };
+            let start = first_match.saturating_sub(SNIPPET_CONTEXT);
+            let end = tokens.len().min(start + SNIPPET_WORDS);
+            let mut snippet = String::new();
+            if start > 0 {
+                snippet.push_str("...");
+            }
+            let mut copied = tokens[start].start;
+            for token in tokens[start..end].iter() {
+                snippet.push_str(&text[copied..token.start]);
+                if terms.contains(&token.term) {
+                    snippet.push_str(HIGHLIGHT_START);
+                    snippet.push_str(&text[token.start..token.end]);
+                    snippet.push_str(HIGHLIGHT_END);
+                } else {
+                    snippet.push_str(&text[token.start..token.end]);
+                }
+                copied = token.end;
+            }
+            if end < tokens.len() {
+                snippet.push_str("...");
+            }
+            snippets.push((field.to_owned(), snippet));
+        }
+        snippets
+    }
+
+    /// Finds the documents matching the query, ranked by BM25 (ties sorted by key),
+    /// then skips `offset` of them and returns at most `limit`, with highlighted snippets.
+    /// `live_document` gets the current (field, text) pairs of a key: the indexed fields
+    /// that aren't among them anymore (e.g. expired ones) are ignored. It's only called for
+    /// the documents that may match.
+    pub fn search(
+        &self,
+        query: &TextQuery,
+        offset: usize,
+        limit: Option<usize>,
+        live_document: impl Fn(&str) -> Vec<(String, String)>,
+    ) -> TextResults {
+        // Only the documents that would match if all their fields were live are looked up.
+        let live: LiveFields = self
+            ._matches(query, None)
+            .into_iter()
+            .filter_map(|key| {
+                let document = &self.documents[key];
+                let live_document = live_document(key);
+                let live_fields: Vec<bool> = document
+                    .fields
+                    .iter()
+                    .map(|field| live_document.contains(field))
+                    .collect();
+                live_fields.contains(&true).then_some((key, live_fields))
+            })
+            .collect();
+        let mut terms = BTreeSet::new();
+        self._positive_terms(query, &mut terms);
+        let mut scored: Vec<(&String, f64)> = self
+            ._matches(query, Some(&live))
+            .into_iter()
+            .map(|key| (key, self._score(key, &terms, &live)))
+            .collect();
+        scored.sort_by(|(a_key, a), (b_key, b)| {
+            b.partial_cmp(a)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a_key.cmp(b_key))
+        });
+        let total = scored.len();
+        let matches = scored
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|(key, score)| TextMatch {
+                key: key.to_owned(),
+                score,
+                snippets: self._snippets(key, &terms, &live),
+            })
+            .collect();
+        TextResults { total, matches }
+    }
+
+    /// Rough estimate (in bytes) of the memory held by the index.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+
+/// How the samples of a bucket are combined into one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aggregation {
+    Avg,
+    Min,
+    Max,
+    Sum,
+    Count,
+    Last,
+}
+
+impl Aggregation {
+    /// Combines the values of a non empty bucket, in timestamp order.
+    fn apply(&self, values: &[f64]) -> f64 {
+        match self {
+            Aggregation::Avg => values.iter().sum::<f64>() / values.len() as f64,
+            Aggregation::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            Aggregation::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Aggregation::Sum => values.iter().sum(),
+            Aggregation::Count => values.len() as f64,
+            Aggregation::Last => values[values.len() - 1],
+        }
+    }
+}
+
+/// Aggregation of the samples into buckets of a fixed duration, aligned on timestamp 0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Downsampling {
+    aggregation: Aggregation,
+    bucket_duration: u64,
+}
+
+impl Downsampling {
+    /// Returns None if the bucket duration is 0.
+    pub fn new(aggregation: Aggregation, bucket_duration: u64) -> Option<Self> {
+        (bucket_duration > 0).then_some(Downsampling {
+            aggregation,
+            bucket_duration,
+        })
+    }
+
+    pub fn bucket_duration(&self) -> u64 {
+        self.bucket_duration
+    }
+
+    /// Timestamp of the start of the bucket holding a timestamp.
+    pub fn bucket_start(&self, timestamp: u64) -> u64 {
+        timestamp - timestamp % self.bucket_duration
+    }
+
+    /// Aggregates samples (sorted by timestamp) into one sample per non empty bucket,
+    /// timestamped with the start of the bucket.
+    pub fn apply<'a>(&self, samples: impl Iterator<Item = (&'a u64, &'a f64)>) -> Vec<Sample> {
+        let mut buckets: Vec<(u64, Vec<f64>)> = vec![];
+        for (timestamp, value) in samples {
+            let bucket_start = self.bucket_start(*timestamp);
+            match buckets.last_mut() {
+                Some((start, values)) if *start == bucket_start => values.push(*value),
+                _ => buckets.push((bucket_start, vec![*value])),
+            }
+        }
+        buckets
+            .into_iter()
+            .map(|(start, values)| (start, self.aggregation.apply(&values)))
+            .collect()
+    }
+}
+
+/// A timestamp (in milliseconds) and its value.
+pub type Sample = (u64, f64);
+
+/// Downsampled copy of a series, written to another key as samples are added.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompactionRule {
+    pub destination: String,
+    pub downsampling: Downsampling,
+}
+
+/// Condition on labels, like the labels of a series or the metadata of a vector.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LabelFilter {
+    /// `label=value`, or `label=` when the label is missing.
+    Equals(String, String),
+    /// `label!=value`, or `label!=` when the label is present.
+    NotEquals(String, String),
+}
+
+impl LabelFilter {
+    pub fn parse(filter: &str) -> Option<Self> {
+        if let Some((label, value)) = filter.split_once("!=") {
+            Some(LabelFilter::NotEquals(label.to_owned(), value.to_owned()))
+        } else {
+            let (label, value) = filter.split_once('=')?;
+            Some(LabelFilter::Equals(label.to_owned(), value.to_owned()))
+        }
+    }
+
+    /// An empty value stands for a missing label.
+    pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        let label_value = |label: &str| labels.get(label).map(String::as_str).unwrap_or("");
+        match self {
+            LabelFilter::Equals(label, value) => label_value(label) == value,
+            LabelFilter::NotEquals(label, value) => label_value(label) != value,
+        }
+    }
+}
+
+/// Samples sorted by timestamp, with the settings of the series.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TimeSeries {
+    samples: BTreeMap<u64, f64>,
+    /// Samples older than this (in milliseconds) compared to the latest sample are dropped.
+    pub retention: Option<u64>,
+    pub labels: BTreeMap<String, String>,
+    pub rules: Vec<CompactionRule>,
+}
+
+impl TimeSeries {
+    pub fn new(retention: Option<u64>, labels: BTreeMap<String, String>) -> Self {
+        TimeSeries {
+            retention,
+            labels,
+            ..TimeSeries::default()
+        }
+    }
+
+    /// Rough estimate (in bytes) of the memory held by the samples and settings.
+    pub fn size(&self) -> usize {
+        let labels_size: usize = self
+            .labels
+            .iter()
+            .map(|(label, value)| label.len() + value.len())
+            .sum();
+        let rules_size: usize = self
+            .rules
+            .iter()
+            .map(|rule| rule.destination.len() + std::mem::size_of::<CompactionRule>())
+            .sum();
+        self.samples.len() * std::mem::size_of::<Sample>() + labels_size + rules_size
+    }
+
+    pub fn latest(&self) -> Option<Sample> {
+        self.samples
+            .last_key_value()
+            .map(|(timestamp, value)| (*timestamp, *value))
+    }
+
+    /// Adds a sample, replacing the value of an existing timestamp, and drops the samples
+    /// out of the retention window. Returns false if the sample itself is out of the window.
+    pub fn add(&mut self, timestamp: u64, value: f64) -> bool {
+        let oldest_retained = |latest: u64| match self.retention {
+            Some(retention) => latest.saturating_sub(retention),
+            None => 0,
+        };
+        if let Some((latest, _)) = self.latest() {
+            if timestamp < oldest_retained(latest) {
+                return false;
+            }
+        }
+        self.samples.insert(timestamp, value);
+        if let Some((latest, _)) = self.latest() {
+            self.samples = self.samples.split_off(&oldest_retained(latest));
+        }
+        true
+    }
+
+    /// Samples with a timestamp between `from` and `to` (inclusive), downsampled if asked.
+    pub fn range(&self, from: u64, to: u64, downsampling: Option<Downsampling>) -> Vec<Sample> {
+        if from > to {
+            return vec![];
+        }
+        let samples = self.samples.range(from..=to);
+        match downsampling {
+            Some(downsampling) => downsampling.apply(samples),
+            None => samples
+                .map(|(timestamp, value)| (*timestamp, *value))
+                .collect(),
+        }
+    }
+}
+
+/// A series found by a label query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeriesMatch {
+    pub key: String,
+    pub labels: BTreeMap<String, String>,
+    pub samples: Vec<Sample>,
+}
@@ -0,0 +1,103 @@
+use super::countmin::CountMinSketch;
+
+/// Top-K heavy hitters: the `k` elements counted the most, with their estimated counts.
+/// Counts are estimated by a Count-Min sketch, so only the top list grows with the elements.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TopK {
+    k: usize,
+    sketch: CountMinSketch,
+    /// Elements of the top list with their estimated counts, in no particular order.
+    top: Vec<(String, u64)>,
+}
+
+impl TopK {
+    /// Returns None if `k`, the width or the depth is 0.
+    /// Arguments:
+    /// * `width` / `depth` - dimensions of the sketch counting the elements, see `CountMinSketch`.
+    pub fn new(k: usize, width: usize, depth: usize) -> Option<Self> {
+        if k == 0 {
+            return None;
+        }
+        Some(TopK {
+            k,
+            sketch: CountMinSketch::new(width, depth)?,
+            top: vec![],
+        })
+    }
+
+    /// Size (in bytes) of the sketch and of the top list.
+    pub fn size(&self) -> usize {
+        let top_size: usize = self
+            .top
+            .iter()
+            .map(|(element, _)| element.len() + std::mem::size_of::<u64>())
+            .sum();
+        self.sketch.size() + top_size
+    }
+
+    /// Updates the top list with the estimated count of an element, and returns the element
+    /// it pushed out of the list, if any.
+    fn _update_top(&mut self, element: &str, count: u64) -> Option<String> {
+        if let Some(entry) = self.top.iter_mut().find(|(item, _)| item == element) {
+            entry.1 = count;
+            return None;
+        }
+        if self.top.len() < self.k {
+            self.top.push((element.to_owned(), count));
+            return None;
+        }
+        let (min_idx, (_, min_count)) = self
+            .top
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (_, count))| *count)?;
+        if count <= *min_count {
+            return None;
+        }
+        let (evicted, _) = std::mem::replace(&mut self.top[min_idx], (element.to_owned(), count));
+        Some(evicted)
+    }
+
+    /// Counts an element `increment` more times, and returns the element it pushed out of
+    /// the top list, if any.
+    pub fn increment(&mut self, element: &str, increment: u64) -> Option<String> {
+        let count = self.sketch.increment(element, increment);
+        self._update_top(element, count)
+    }
+
+    /// Check whether an element is in the top list.
+    pub fn contains(&self, element: &str) -> bool {
+        self.top.iter().any(|(item, _)| item == element)
+    }
+
+    /// Estimated number of times an element was counted, even if it isn't in the top list.
+    pub fn count(&self, element: &str) -> u64 {
+        self.sketch.query(element)
+    }
+
+    /// Elements of the top list with their estimated counts, the most counted first.
+    pub fn list(&self) -> Vec<(String, u64)> {
+        let mut top = self.top.to_owned();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top
+    }
+
+    /// Adds the counts of another Top-K with the same parameters to this one.
+    /// Returns false (leaving the Top-K untouched) if the parameters differ.
+    pub fn merge(&mut self, other: &TopK) -> bool {
+        if self.k != other.k || !self.sketch.merge(&other.sketch) {
+            return false;
+        }
+        let mut candidates: Vec<String> = self.top.drain(..).map(|(element, _)| element).collect();
+        for (element, _) in other.top.iter() {
+            if !candidates.contains(element) {
+                candidates.push(element.to_owned());
+            }
+        }
+        for element in candidates {
+            let count = self.sketch.query(&element);
+            self._update_top(&element, count);
+        }
+        true
+    }
+}
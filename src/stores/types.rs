@@ -1,11 +1,258 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use super::{
+    bloom::BloomFilter,
+    countmin::CountMinSketch,
+    cuckoo::CuckooFilter,
+    cursor::{CursorMap, CursorSet},
+    geo::GeoSet,
+    hyperloglog::HyperLogLog,
+    list::List,
+    timeseries::TimeSeries,
+    topk::TopK,
+    vector::VectorIndex,
+};
+use serde_json::Value;
 
 #[derive(Clone)]
 pub enum ValueType {
     Integer64(i64),
+    Float64(f64),
     Bytes(Vec<u8>),
     String(String),
-    Deque(VecDeque<String>),
-    Set(HashSet<String>),
-    HashMap(HashMap<String, String>),
+    Deque(List),
+    Set(CursorSet),
+    HashMap(CursorMap),
+    HyperLogLog(HyperLogLog),
+    Geo(GeoSet),
+    Json(Value),
+    TimeSeries(TimeSeries),
+    Bloom(BloomFilter),
+    Cuckoo(CuckooFilter),
+    CountMin(CountMinSketch),
+    TopK(TopK),
+    VectorIndex(VectorIndex),
+}
+
+/// The kind of a ValueType, without its data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueKind {
+    Integer64,
+    Float64,
+    Bytes,
+    String,
+    Deque,
+    Set,
+    HashMap,
+    HyperLogLog,
+    Geo,
+    Json,
+    TimeSeries,
+    Bloom,
+    Cuckoo,
+    CountMin,
+    TopK,
+    VectorIndex,
+}
+
+impl ValueType {
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            ValueType::Integer64(_) => ValueKind::Integer64,
+            ValueType::Float64(_) => ValueKind::Float64,
+            ValueType::Bytes(_) => ValueKind::Bytes,
+            ValueType::String(_) => ValueKind::String,
+            ValueType::Deque(_) => ValueKind::Deque,
+            ValueType::Set(_) => ValueKind::Set,
+            ValueType::HashMap(_) => ValueKind::HashMap,
+            ValueType::HyperLogLog(_) => ValueKind::HyperLogLog,
+            ValueType::Geo(_) => ValueKind::Geo,
+            ValueType::Json(_) => ValueKind::Json,
+            ValueType::TimeSeries(_) => ValueKind::TimeSeries,
+            ValueType::Bloom(_) => ValueKind::Bloom,
+            ValueType::Cuckoo(_) => ValueKind::Cuckoo,
+            ValueType::CountMin(_) => ValueKind::CountMin,
+            ValueType::TopK(_) => ValueKind::TopK,
+            ValueType::VectorIndex(_) => ValueKind::VectorIndex,
+        }
+    }
+}
+
+impl ValueKind {
+    /// Name of the kind, as used in the protocol.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ValueKind::Integer64 => "integer",
+            ValueKind::Float64 => "float",
+            ValueKind::Bytes => "bytes",
+            ValueKind::String => "string",
+            ValueKind::Deque => "list",
+            ValueKind::Set => "set",
+            ValueKind::HashMap => "hmap",
+            ValueKind::HyperLogLog => "hyperloglog",
+            ValueKind::Geo => "geo",
+            ValueKind::Json => "json",
+            ValueKind::TimeSeries => "timeseries",
+            ValueKind::Bloom => "bloom",
+            ValueKind::Cuckoo => "cuckoo",
+            ValueKind::CountMin => "countmin",
+            ValueKind::TopK => "topk",
+            ValueKind::VectorIndex => "vector",
+        }
+    }
+
+    /// Parses the (case-insensitive) name of a kind.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            ValueKind::Integer64,
+            ValueKind::Float64,
+            ValueKind::Bytes,
+            ValueKind::String,
+            ValueKind::Deque,
+            ValueKind::Set,
+            ValueKind::HashMap,
+            ValueKind::HyperLogLog,
+            ValueKind::Geo,
+            ValueKind::Json,
+            ValueKind::TimeSeries,
+            ValueKind::Bloom,
+            ValueKind::Cuckoo,
+            ValueKind::CountMin,
+            ValueKind::TopK,
+            ValueKind::VectorIndex,
+        ]
+        .into_iter()
+        .find(|kind| kind.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// What a bounded counter does when an increment would take it past one of its bounds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CounterLimit {
+    /// Stop at the bound.
+    Clamp,
+    /// Refuse the increment.
+    Fail,
+}
+
+/// The time to live of a hmap_key.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldTtl {
+    /// The hmap has no such hmap_key.
+    NoField,
+    /// The hmap_key never expires.
+    Persistent,
+    /// Remaining time to live, in milliseconds.
+    Expires(u64),
+}
+
+/// How `sort` orders the elements of a list or a set, and what it returns for each of them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SortOptions {
+    /// Pattern of the keys (e.g. `weight_*`) or hmap fields (e.g. `user_*->age`) holding the
+    /// weight of each element, `*` being replaced by the element. Missing weights count as 0
+    /// (or as an empty string with `alpha`), and a pattern without `*` skips the sort.
+    pub by: Option<String>,
+    /// Patterns of the keys or hmap fields whose values are returned instead of each element,
+    /// `#` being the element itself.
+    pub get: Vec<String>,
+    /// Compare lexically instead of numerically.
+    pub alpha: bool,
+    pub descending: bool,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+/// Whether the offsets of a bitmap range are byte or bit offsets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BitUnit {
+    Byte,
+    Bit,
+}
+
+/// Bitwise operations combining several bitmaps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BitOp {
+    And,
+    Or,
+    Xor,
+}
+
+/// What a bitfield write does when the value doesn't fit in the integer type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BitfieldOverflow {
+    /// Wrap around, like integer arithmetic does.
+    Wrap,
+    /// Saturate at the minimum or maximum value of the type.
+    Sat,
+    /// Leave the bitfield untouched.
+    Fail,
+}
+
+/// Signed (up to 64 bits) or unsigned (up to 63 bits) integer stored at a bit offset of a bitmap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BitfieldType {
+    signed: bool,
+    bits: u8,
+}
+
+impl BitfieldType {
+    pub fn new(signed: bool, bits: u8) -> Option<Self> {
+        let max_bits = if signed { 64 } else { 63 };
+        if bits == 0 || bits > max_bits {
+            return None;
+        }
+        Some(BitfieldType { signed, bits })
+    }
+
+    /// Parses types like `i8` or `u16`.
+    pub fn parse(name: &str) -> Option<Self> {
+        let signed = match name.chars().next()? {
+            'i' | 'I' => true,
+            'u' | 'U' => false,
+            _ => return None,
+        };
+        BitfieldType::new(signed, name[1..].parse().ok()?)
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    pub fn min(&self) -> i128 {
+        if self.signed {
+            -(1 << (self.bits - 1))
+        } else {
+            0
+        }
+    }
+
+    pub fn max(&self) -> i128 {
+        if self.signed {
+            (1 << (self.bits - 1)) - 1
+        } else {
+            (1 << self.bits) - 1
+        }
+    }
+
+    /// Interprets the lowest `bits` bits of `raw` as a value of this type.
+    pub fn decode(&self, raw: u64) -> i64 {
+        if self.signed && self.bits < 64 && raw & (1 << (self.bits - 1)) != 0 {
+            (raw | (u64::MAX << self.bits)) as i64
+        } else {
+            raw as i64
+        }
+    }
+
+    /// Fits `value` in the type according to the overflow policy, None if it doesn't fit
+    /// and the policy is `BitfieldOverflow::Fail`.
+    pub fn fit(&self, value: i128, overflow: BitfieldOverflow) -> Option<i64> {
+        let (min, max) = (self.min(), self.max());
+        if (min..=max).contains(&value) {
+            return Some(value as i64);
+        }
+        match overflow {
+            BitfieldOverflow::Wrap => Some(((value - min).rem_euclid(max - min + 1) + min) as i64),
+            BitfieldOverflow::Sat => Some(value.clamp(min, max) as i64),
+            BitfieldOverflow::Fail => None,
+        }
+    }
 }
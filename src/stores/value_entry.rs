@@ -1,11 +1,23 @@
+use serde_json::Value;
 use std::{
-    collections::{vec_deque::VecDeque, HashMap, HashSet},
+    collections::{HashMap, HashSet},
     time::Instant,
 };
 
 use super::{
+    bloom::BloomFilter,
+    countmin::CountMinSketch,
+    cuckoo::CuckooFilter,
+    cursor::{CursorMap, CursorSet},
     errors::{TypeConversionError, TypeConversionImpossible, ValueError},
+    geo::GeoSet,
+    hyperloglog::HyperLogLog,
+    json,
+    list::List,
+    timeseries::TimeSeries,
+    topk::TopK,
     types::ValueType,
+    vector::VectorIndex,
 };
 
 /// Each entry of the Key-Value pair in the Data store is this struct.
@@ -16,6 +28,9 @@ pub struct ValueEntry {
 
     /// Expiration datetime of the given key is stored here.
     pub expiration: Option<Instant>,
+
+    /// Expiration datetimes of the fields of a hmap, only for the fields that have one.
+    pub field_expirations: HashMap<String, Instant>,
     // todo: Add more fields depending on type maybe.
 }
 
@@ -24,6 +39,15 @@ impl ValueEntry {
         ValueEntry {
             value: ValueType::Integer64(value),
             expiration,
+            field_expirations: HashMap::new(),
+        }
+    }
+
+    pub fn from_f64(value: f64, expiration: Option<Instant>) -> Self {
+        ValueEntry {
+            value: ValueType::Float64(value),
+            expiration,
+            field_expirations: HashMap::new(),
         }
     }
 
@@ -31,6 +55,7 @@ impl ValueEntry {
         ValueEntry {
             value: ValueType::Bytes(value),
             expiration,
+            field_expirations: HashMap::new(),
         }
     }
 
@@ -38,27 +63,103 @@ impl ValueEntry {
         ValueEntry {
             value: ValueType::String(value),
             expiration,
+            field_expirations: HashMap::new(),
         }
     }
 
     pub fn from_list(value: Vec<String>, expiration: Option<Instant>) -> Self {
         ValueEntry {
-            value: ValueType::Deque(VecDeque::from(value)),
+            value: ValueType::Deque(value.into_iter().collect()),
             expiration,
+            field_expirations: HashMap::new(),
         }
     }
 
     pub fn from_hset(value: HashSet<String>, expiration: Option<Instant>) -> Self {
         ValueEntry {
-            value: ValueType::Set(value),
+            value: ValueType::Set(value.into_iter().collect()),
             expiration,
+            field_expirations: HashMap::new(),
+        }
+    }
+
+    pub fn from_geo(value: GeoSet, expiration: Option<Instant>) -> Self {
+        ValueEntry {
+            value: ValueType::Geo(value),
+            expiration,
+            field_expirations: HashMap::new(),
+        }
+    }
+
+    pub fn from_json(value: Value, expiration: Option<Instant>) -> Self {
+        ValueEntry {
+            value: ValueType::Json(value),
+            expiration,
+            field_expirations: HashMap::new(),
+        }
+    }
+
+    pub fn from_timeseries(value: TimeSeries, expiration: Option<Instant>) -> Self {
+        ValueEntry {
+            value: ValueType::TimeSeries(value),
+            expiration,
+            field_expirations: HashMap::new(),
+        }
+    }
+
+    pub fn from_bloom(value: BloomFilter, expiration: Option<Instant>) -> Self {
+        ValueEntry {
+            value: ValueType::Bloom(value),
+            expiration,
+            field_expirations: HashMap::new(),
+        }
+    }
+
+    pub fn from_cuckoo(value: CuckooFilter, expiration: Option<Instant>) -> Self {
+        ValueEntry {
+            value: ValueType::Cuckoo(value),
+            expiration,
+            field_expirations: HashMap::new(),
+        }
+    }
+
+    pub fn from_countmin(value: CountMinSketch, expiration: Option<Instant>) -> Self {
+        ValueEntry {
+            value: ValueType::CountMin(value),
+            expiration,
+            field_expirations: HashMap::new(),
+        }
+    }
+
+    pub fn from_topk(value: TopK, expiration: Option<Instant>) -> Self {
+        ValueEntry {
+            value: ValueType::TopK(value),
+            expiration,
+            field_expirations: HashMap::new(),
+        }
+    }
+
+    pub fn from_vector_index(value: VectorIndex, expiration: Option<Instant>) -> Self {
+        ValueEntry {
+            value: ValueType::VectorIndex(value),
+            expiration,
+            field_expirations: HashMap::new(),
         }
     }
 
     pub fn from_hashmap(value: HashMap<String, String>, expiration: Option<Instant>) -> Self {
         ValueEntry {
-            value: ValueType::HashMap(value),
+            value: ValueType::HashMap(value.into_iter().collect()),
+            expiration,
+            field_expirations: HashMap::new(),
+        }
+    }
+
+    pub fn from_hyperloglog(value: HyperLogLog, expiration: Option<Instant>) -> Self {
+        ValueEntry {
+            value: ValueType::HyperLogLog(value),
             expiration,
+            field_expirations: HashMap::new(),
         }
     }
 
@@ -77,11 +178,28 @@ impl ValueEntry {
         }
     }
 
+    pub fn get_value_as_f64(&self) -> Result<f64, ValueError> {
+        match &self.value {
+            ValueType::Float64(float) => Ok(*float),
+            ValueType::Integer64(integer) => Ok(*integer as f64),
+            ValueType::String(string) => match string.parse::<f64>() {
+                Ok(float) => Ok(float),
+                Err(e) => Err(ValueError::TypeConversionError(
+                    TypeConversionError::ParseFloatError(e),
+                )),
+            },
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::IncompatibleTypes,
+            )),
+        }
+    }
+
     pub fn get_value_as_bytes(&self) -> Result<Vec<u8>, ValueError> {
         match &self.value {
             ValueType::Bytes(bytes) => Ok(bytes.to_owned()),
             ValueType::String(string) => Ok(string.to_owned().into_bytes()),
             ValueType::Integer64(integer) => Ok(integer.to_le_bytes().to_vec()),
+            ValueType::Float64(float) => Ok(float.to_le_bytes().to_vec()),
             _ => Err(ValueError::TypeConversionImpossible(
                 TypeConversionImpossible::IncompatibleTypes,
             )),
@@ -98,6 +216,7 @@ impl ValueEntry {
                 Ok(string) => Ok(string),
             },
             ValueType::Integer64(integer) => Ok(integer.to_string()),
+            ValueType::Float64(float) => Ok(float.to_string()),
             _ => Err(ValueError::TypeConversionImpossible(
                 TypeConversionImpossible::IncompatibleTypes,
             )),
@@ -106,7 +225,7 @@ impl ValueEntry {
 
     pub fn get_value_as_list(&self) -> Result<Vec<String>, ValueError> {
         match &self.value {
-            ValueType::Deque(list) => Ok(Vec::from(list.to_owned())),
+            ValueType::Deque(list) => Ok(list.iter().cloned().collect()),
             ValueType::String(string) => Ok(string.chars().map(|ch| ch.to_string()).collect()),
             ValueType::Bytes(bytes) => Ok(bytes.iter().map(|byte| byte.to_string()).collect()),
             ValueType::Set(hash_set) => Ok(hash_set.iter().map(|val| val.to_string()).collect()),
@@ -116,7 +235,7 @@ impl ValueEntry {
         }
     }
 
-    pub fn get_value_as_deque(&self) -> Result<&VecDeque<String>, ValueError> {
+    pub fn get_value_as_deque(&self) -> Result<&List, ValueError> {
         match &self.value {
             ValueType::Deque(list) => Ok(list),
             _ => Err(ValueError::TypeConversionImpossible(
@@ -125,7 +244,7 @@ impl ValueEntry {
         }
     }
 
-    pub fn get_value_as_mut_deque(&mut self) -> Result<&mut VecDeque<String>, ValueError> {
+    pub fn get_value_as_mut_deque(&mut self) -> Result<&mut List, ValueError> {
         match &mut self.value {
             ValueType::Deque(list) => Ok(list),
             _ => Err(ValueError::TypeConversionImpossible(
@@ -136,7 +255,7 @@ impl ValueEntry {
 
     pub fn get_value_as_hset(&self) -> Result<HashSet<String>, ValueError> {
         match &self.value {
-            ValueType::Set(hash_set) => Ok(hash_set.to_owned()),
+            ValueType::Set(hash_set) => Ok((**hash_set).to_owned()),
             ValueType::String(string) => Ok(HashSet::from_iter(
                 string.chars().map(|item| item.to_string()),
             )),
@@ -149,7 +268,7 @@ impl ValueEntry {
         }
     }
 
-    pub fn get_value_as_mut_hset(&mut self) -> Result<&mut HashSet<String>, ValueError> {
+    pub fn get_value_as_mut_hset(&mut self) -> Result<&mut CursorSet, ValueError> {
         match &mut self.value {
             ValueType::Set(hash_set) => Ok(hash_set),
             _ => Err(ValueError::TypeConversionImpossible(
@@ -160,7 +279,7 @@ impl ValueEntry {
 
     pub fn get_value_as_hmap(&self) -> Result<HashMap<String, String>, ValueError> {
         match &self.value {
-            ValueType::HashMap(hmap) => Ok(hmap.to_owned()),
+            ValueType::HashMap(hmap) => Ok((**hmap).to_owned()),
             ValueType::Deque(list) => Ok(list
                 .iter()
                 .fold(HashMap::new(), |mut map, item| {
@@ -185,7 +304,7 @@ impl ValueEntry {
         }
     }
 
-    pub fn get_value_as_mut_hmap(&mut self) -> Result<&mut HashMap<String, String>, ValueError> {
+    pub fn get_value_as_mut_hmap(&mut self) -> Result<&mut CursorMap, ValueError> {
         match &mut self.value {
             ValueType::HashMap(hmap) => Ok(hmap),
             _ => Err(ValueError::TypeConversionImpossible(
@@ -194,6 +313,216 @@ impl ValueEntry {
         }
     }
 
+    pub fn get_value_as_hyperloglog(&self) -> Result<&HyperLogLog, ValueError> {
+        match &self.value {
+            ValueType::HyperLogLog(hyperloglog) => Ok(hyperloglog),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::IncompatibleTypes,
+            )),
+        }
+    }
+
+    pub fn get_value_as_mut_hyperloglog(&mut self) -> Result<&mut HyperLogLog, ValueError> {
+        match &mut self.value {
+            ValueType::HyperLogLog(hyperloglog) => Ok(hyperloglog),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::AsMutable,
+            )),
+        }
+    }
+
+    pub fn get_value_as_geo(&self) -> Result<&GeoSet, ValueError> {
+        match &self.value {
+            ValueType::Geo(geo) => Ok(geo),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::IncompatibleTypes,
+            )),
+        }
+    }
+
+    pub fn get_value_as_mut_geo(&mut self) -> Result<&mut GeoSet, ValueError> {
+        match &mut self.value {
+            ValueType::Geo(geo) => Ok(geo),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::AsMutable,
+            )),
+        }
+    }
+
+    pub fn get_value_as_json(&self) -> Result<&Value, ValueError> {
+        match &self.value {
+            ValueType::Json(document) => Ok(document),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::IncompatibleTypes,
+            )),
+        }
+    }
+
+    pub fn get_value_as_mut_json(&mut self) -> Result<&mut Value, ValueError> {
+        match &mut self.value {
+            ValueType::Json(document) => Ok(document),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::AsMutable,
+            )),
+        }
+    }
+
+    pub fn get_value_as_timeseries(&self) -> Result<&TimeSeries, ValueError> {
+        match &self.value {
+            ValueType::TimeSeries(timeseries) => Ok(timeseries),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::IncompatibleTypes,
+            )),
+        }
+    }
+
+    pub fn get_value_as_mut_timeseries(&mut self) -> Result<&mut TimeSeries, ValueError> {
+        match &mut self.value {
+            ValueType::TimeSeries(timeseries) => Ok(timeseries),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::AsMutable,
+            )),
+        }
+    }
+
+    pub fn get_value_as_bloom(&self) -> Result<&BloomFilter, ValueError> {
+        match &self.value {
+            ValueType::Bloom(bloom) => Ok(bloom),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::IncompatibleTypes,
+            )),
+        }
+    }
+
+    pub fn get_value_as_mut_bloom(&mut self) -> Result<&mut BloomFilter, ValueError> {
+        match &mut self.value {
+            ValueType::Bloom(bloom) => Ok(bloom),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::AsMutable,
+            )),
+        }
+    }
+
+    pub fn get_value_as_cuckoo(&self) -> Result<&CuckooFilter, ValueError> {
+        match &self.value {
+            ValueType::Cuckoo(cuckoo) => Ok(cuckoo),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::IncompatibleTypes,
+            )),
+        }
+    }
+
+    pub fn get_value_as_mut_cuckoo(&mut self) -> Result<&mut CuckooFilter, ValueError> {
+        match &mut self.value {
+            ValueType::Cuckoo(cuckoo) => Ok(cuckoo),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::AsMutable,
+            )),
+        }
+    }
+
+    pub fn get_value_as_countmin(&self) -> Result<&CountMinSketch, ValueError> {
+        match &self.value {
+            ValueType::CountMin(countmin) => Ok(countmin),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::IncompatibleTypes,
+            )),
+        }
+    }
+
+    pub fn get_value_as_mut_countmin(&mut self) -> Result<&mut CountMinSketch, ValueError> {
+        match &mut self.value {
+            ValueType::CountMin(countmin) => Ok(countmin),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::AsMutable,
+            )),
+        }
+    }
+
+    pub fn get_value_as_topk(&self) -> Result<&TopK, ValueError> {
+        match &self.value {
+            ValueType::TopK(topk) => Ok(topk),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::IncompatibleTypes,
+            )),
+        }
+    }
+
+    pub fn get_value_as_mut_topk(&mut self) -> Result<&mut TopK, ValueError> {
+        match &mut self.value {
+            ValueType::TopK(topk) => Ok(topk),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::AsMutable,
+            )),
+        }
+    }
+
+    pub fn get_value_as_vector_index(&self) -> Result<&VectorIndex, ValueError> {
+        match &self.value {
+            ValueType::VectorIndex(index) => Ok(index),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::IncompatibleTypes,
+            )),
+        }
+    }
+
+    pub fn get_value_as_mut_vector_index(&mut self) -> Result<&mut VectorIndex, ValueError> {
+        match &mut self.value {
+            ValueType::VectorIndex(index) => Ok(index),
+            _ => Err(ValueError::TypeConversionImpossible(
+                TypeConversionImpossible::AsMutable,
+            )),
+        }
+    }
+
+    /// Rough estimate (in bytes) of the memory held by this entry.
+    /// The collections keep count of their size as they change, only JSON documents are
+    /// measured again each time.
+    pub fn estimated_size(&self) -> usize {
+        let value_size = match &self.value {
+            ValueType::Integer64(_) | ValueType::Float64(_) => 0,
+            ValueType::Bytes(bytes) => bytes.len(),
+            ValueType::String(string) => string.len(),
+            ValueType::Deque(list) => list.size(),
+            ValueType::Set(hash_set) => hash_set.size(),
+            ValueType::HashMap(hmap) => hmap.size(),
+            ValueType::HyperLogLog(hyperloglog) => hyperloglog.size(),
+            ValueType::Geo(geo) => geo.size(),
+            ValueType::Json(document) => json::size(document),
+            ValueType::TimeSeries(timeseries) => timeseries.size(),
+            ValueType::Bloom(bloom) => bloom.size(),
+            ValueType::Cuckoo(cuckoo) => cuckoo.size(),
+            ValueType::CountMin(countmin) => countmin.size(),
+            ValueType::TopK(topk) => topk.size(),
+            ValueType::VectorIndex(index) => index.size(),
+        };
+        // The field names are counted with the hmap.
+        let field_expirations_size = self.field_expirations.len()
+            * (std::mem::size_of::<String>() + std::mem::size_of::<Instant>());
+        std::mem::size_of::<ValueEntry>() + value_size + field_expirations_size
+    }
+
+    /// Removes the expired fields of a hmap, returns whether any field was removed.
+    pub fn clear_expired_fields(&mut self, now: Instant) -> bool {
+        if self.field_expirations.is_empty() {
+            return false;
+        }
+        let ValueType::HashMap(hmap) = &mut self.value else {
+            return false;
+        };
+        let expired_fields: Vec<String> = self
+            .field_expirations
+            .iter()
+            .filter(|(_, expiration)| now >= **expiration)
+            .map(|(field, _)| field.to_owned())
+            .collect();
+        for field in expired_fields.iter() {
+            hmap.remove(field);
+            self.field_expirations.remove(field);
+        }
+        !expired_fields.is_empty()
+    }
+
     /// Check if this entry is expired.
     pub fn is_expired_entry(&self, option_now: Option<Instant>) -> bool {
         if self.expiration.is_none() {
@@ -0,0 +1,420 @@
+use rand::Rng;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BinaryHeap, HashMap},
+};
+
+use super::timeseries::LabelFilter;
+
+/// Indexes with more vectors than this are searched through an HNSW graph,
+/// smaller ones are searched exhaustively.
+pub const BRUTE_FORCE_LIMIT: usize = 1000;
+/// Number of neighbours of a node on the upper layers of the graph, twice that on layer 0.
+const HNSW_M: usize = 16;
+/// Number of candidates considered when linking a new node.
+const HNSW_EF_CONSTRUCTION: usize = 100;
+/// Minimum number of candidates considered by a search.
+const HNSW_EF_SEARCH: usize = 64;
+
+/// How the distance between two vectors is measured, lower is closer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VectorMetric {
+    /// 1 - cosine similarity, vectors are normalized on insert.
+    Cosine,
+    /// Euclidean distance.
+    L2,
+    /// Negated dot product.
+    Dot,
+}
+
+impl VectorMetric {
+    pub fn distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        let dot = || a.iter().zip(b.iter()).map(|(x, y)| x * y).sum::<f32>();
+        match self {
+            VectorMetric::Cosine => 1.0 - dot(),
+            VectorMetric::L2 => a
+                .iter()
+                .zip(b.iter())
+                .map(|(x, y)| (x - y) * (x - y))
+                .sum::<f32>()
+                .sqrt(),
+            VectorMetric::Dot => -dot(),
+        }
+    }
+}
+
+/// A vector found by a search.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VectorMatch {
+    pub id: String,
+    pub distance: f32,
+    pub metadata: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Slot {
+    id: String,
+    values: Vec<f32>,
+    metadata: BTreeMap<String, String>,
+    /// Deleted slots stay in the graph to navigate it, until the index is compacted.
+    deleted: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Candidate {
+    distance: f32,
+    slot: usize,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then(self.slot.cmp(&other.slot))
+    }
+}
+
+/// Hierarchical Navigable Small World graph over the slots of an index.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Hnsw {
+    /// Neighbours of each slot, for each layer the slot is on.
+    neighbours: Vec<Vec<Vec<usize>>>,
+    entry_point: Option<usize>,
+    /// Number of links over all the slots and layers, see `VectorIndex::size`.
+    link_count: usize,
+}
+
+impl Hnsw {
+    fn _max_neighbours(layer: usize) -> usize {
+        if layer == 0 {
+            2 * HNSW_M
+        } else {
+            HNSW_M
+        }
+    }
+
+    /// Closest slots to the query on a layer, found by a greedy walk from the entry points,
+    /// the closest first.
+    fn _search_layer(
+        &self,
+        slots: &[Slot],
+        metric: VectorMetric,
+        query: &[f32],
+        entry_points: &[usize],
+        ef: usize,
+        layer: usize,
+    ) -> Vec<Candidate> {
+        let distance = |slot: usize| metric.distance(query, &slots[slot].values);
+        let mut visited = vec![false; slots.len()];
+        for slot in entry_points {
+            visited[*slot] = true;
+        }
+        // Closest candidates first, through a max-heap of reversed candidates.
+        let mut to_visit: BinaryHeap<std::cmp::Reverse<Candidate>> = BinaryHeap::new();
+        // Furthest result first, to drop it when a closer one is found.
+        let mut results: BinaryHeap<Candidate> = BinaryHeap::new();
+        for slot in entry_points {
+            let candidate = Candidate {
+                distance: distance(*slot),
+                slot: *slot,
+            };
+            to_visit.push(std::cmp::Reverse(candidate));
+            results.push(candidate);
+        }
+        while let Some(std::cmp::Reverse(candidate)) = to_visit.pop() {
+            if results.len() >= ef && candidate.distance > results.peek().unwrap().distance {
+                break;
+            }
+            for neighbour in self.neighbours[candidate.slot][layer].iter() {
+                if std::mem::replace(&mut visited[*neighbour], true) {
+                    continue;
+                }
+                let neighbour = Candidate {
+                    distance: distance(*neighbour),
+                    slot: *neighbour,
+                };
+                if results.len() < ef || neighbour.distance < results.peek().unwrap().distance {
+                    to_visit.push(std::cmp::Reverse(neighbour));
+                    results.push(neighbour);
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+        results.into_sorted_vec()
+    }
+
+    /// Links a slot (the last one) into the graph.
+    fn insert(&mut self, slots: &[Slot], metric: VectorMetric, slot: usize) {
+        // Each layer holds about 1 / M of the slots of the layer below.
+        let level_multiplier = 1.0 / (HNSW_M as f64).ln();
+        let random: f64 = rand::thread_rng().gen_range(f64::EPSILON..1.0);
+        let level = (-random.ln() * level_multiplier).floor() as usize;
+        self.neighbours.push(vec![vec![]; level + 1]);
+
+        let Some(entry_point) = self.entry_point else {
+            self.entry_point = Some(slot);
+            return;
+        };
+        let query = &slots[slot].values;
+        let top_level = self.neighbours[entry_point].len() - 1;
+        let mut entry_points = vec![entry_point];
+        for layer in (level + 1..=top_level).rev() {
+            entry_points =
+                vec![self._search_layer(slots, metric, query, &entry_points, 1, layer)[0].slot];
+        }
+        for layer in (0..=level.min(top_level)).rev() {
+            let candidates = self._search_layer(
+                slots,
+                metric,
+                query,
+                &entry_points,
+                HNSW_EF_CONSTRUCTION,
+                layer,
+            );
+            let max_neighbours = Hnsw::_max_neighbours(layer);
+            let neighbours: Vec<usize> = candidates
+                .iter()
+                .take(max_neighbours)
+                .map(|candidate| candidate.slot)
+                .collect();
+            for neighbour in neighbours.iter() {
+                let links = &mut self.neighbours[*neighbour][layer];
+                links.push(slot);
+                self.link_count += 1;
+                if links.len() > max_neighbours {
+                    let link_count = links.len();
+                    // Keep the closest neighbours of the neighbour.
+                    let origin = &slots[*neighbour].values;
+                    let mut candidates: Vec<Candidate> = links
+                        .iter()
+                        .map(|link| Candidate {
+                            distance: metric.distance(origin, &slots[*link].values),
+                            slot: *link,
+                        })
+                        .collect();
+                    candidates.sort();
+                    *links = candidates
+                        .into_iter()
+                        .take(max_neighbours)
+                        .map(|candidate| candidate.slot)
+                        .collect();
+                    self.link_count -= link_count - links.len();
+                }
+            }
+            self.link_count += neighbours.len();
+            self.neighbours[slot][layer] = neighbours;
+            entry_points = candidates.iter().map(|candidate| candidate.slot).collect();
+        }
+        if level > top_level {
+            self.entry_point = Some(slot);
+        }
+    }
+
+    /// Approximate closest slots to the query, the closest first.
+    fn search(
+        &self,
+        slots: &[Slot],
+        metric: VectorMetric,
+        query: &[f32],
+        ef: usize,
+    ) -> Vec<Candidate> {
+        let Some(entry_point) = self.entry_point else {
+            return vec![];
+        };
+        let mut entry_points = vec![entry_point];
+        for layer in (1..self.neighbours[entry_point].len()).rev() {
+            entry_points =
+                vec![self._search_layer(slots, metric, query, &entry_points, 1, layer)[0].slot];
+        }
+        self._search_layer(slots, metric, query, &entry_points, ef, 0)
+    }
+}
+
+/// Vectors of a fixed dimension with their metadata, searchable by distance to a query.
+/// Small indexes are searched exhaustively, larger ones through an HNSW graph, which is
+/// approximate: a search may miss some of the actual nearest neighbours.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VectorIndex {
+    dimension: usize,
+    metric: VectorMetric,
+    slots: Vec<Slot>,
+    /// Slot of each (non deleted) id.
+    ids: HashMap<String, usize>,
+    graph: Option<Hnsw>,
+    /// Bytes held by the slots (deleted ones included), see `size`.
+    slots_size: usize,
+}
+
+impl VectorIndex {
+    /// Returns None if the dimension is 0.
+    pub fn new(dimension: usize, metric: VectorMetric) -> Option<Self> {
+        (dimension > 0).then(|| VectorIndex {
+            dimension,
+            metric,
+            slots: vec![],
+            ids: HashMap::new(),
+            graph: None,
+            slots_size: 0,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Rough estimate (in bytes) of the memory held by the vectors, metadata and graph.
+    pub fn size(&self) -> usize {
+        let graph_size = self
+            .graph
+            .as_ref()
+            .map_or(0, |graph| graph.link_count * std::mem::size_of::<usize>());
+        self.slots_size + graph_size
+    }
+
+    fn _slot_size(slot: &Slot) -> usize {
+        let metadata_size: usize = slot
+            .metadata
+            .iter()
+            .map(|(name, value)| name.len() + value.len())
+            .sum();
+        2 * slot.id.len() + slot.values.len() * std::mem::size_of::<f32>() + metadata_size
+    }
+
+    /// Checks the dimension of a vector, and normalizes it for the cosine metric.
+    /// Returns None if the dimension is wrong, a value isn't finite, or the vector is 0
+    /// with the cosine metric.
+    fn _prepare(&self, mut values: Vec<f32>) -> Option<Vec<f32>> {
+        if values.len() != self.dimension || values.iter().any(|value| !value.is_finite()) {
+            return None;
+        }
+        if self.metric == VectorMetric::Cosine {
+            let norm = values.iter().map(|value| value * value).sum::<f32>().sqrt();
+            if norm == 0.0 || !norm.is_finite() {
+                return None;
+            }
+            values.iter_mut().for_each(|value| *value /= norm);
+        }
+        Some(values)
+    }
+
+    /// Adds a vector, or replaces the vector of an existing id, and returns whether the id
+    /// is new. Returns None (leaving the index untouched) if the vector is invalid,
+    /// see `_prepare`.
+    pub fn insert(
+        &mut self,
+        id: &str,
+        values: Vec<f32>,
+        metadata: BTreeMap<String, String>,
+    ) -> Option<bool> {
+        let values = self._prepare(values)?;
+        let is_new = !self.remove(id);
+        self.slots.push(Slot {
+            id: id.to_owned(),
+            values,
+            metadata,
+            deleted: false,
+        });
+        let slot = self.slots.len() - 1;
+        self.slots_size += VectorIndex::_slot_size(&self.slots[slot]);
+        self.ids.insert(id.to_owned(), slot);
+        if let Some(graph) = self.graph.as_mut() {
+            graph.insert(&self.slots, self.metric, slot);
+        } else if self.len() > BRUTE_FORCE_LIMIT {
+            self._rebuild();
+        }
+        Some(is_new)
+    }
+
+    /// Removes the vector of an id, and returns whether it existed.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(slot) = self.ids.remove(id) else {
+            return false;
+        };
+        self.slots[slot].deleted = true;
+        // Deleted slots are dropped once they are the majority.
+        if self.slots.len() > 2 * self.len() {
+            self._rebuild();
+        }
+        true
+    }
+
+    /// Drops the deleted slots, and builds the graph again if the index is large enough.
+    fn _rebuild(&mut self) {
+        self.slots.retain(|slot| !slot.deleted);
+        self.slots_size = self.slots.iter().map(VectorIndex::_slot_size).sum();
+        self.ids = self
+            .slots
+            .iter()
+            .enumerate()
+            .map(|(idx, slot)| (slot.id.to_owned(), idx))
+            .collect();
+        self.graph = None;
+        if self.len() > BRUTE_FORCE_LIMIT {
+            let mut graph = Hnsw::default();
+            for slot in 0..self.slots.len() {
+                graph.insert(&self.slots, self.metric, slot);
+            }
+            self.graph = Some(graph);
+        }
+    }
+
+    /// Finds the `k` vectors closest to the query among the ones whose metadata matches all
+    /// the filters, the closest first. Returns None if the query vector is invalid.
+    pub fn search(
+        &self,
+        query: Vec<f32>,
+        k: usize,
+        filters: &[LabelFilter],
+    ) -> Option<Vec<VectorMatch>> {
+        let query = self._prepare(query)?;
+        let is_match = |slot: &Slot| {
+            !slot.deleted && filters.iter().all(|filter| filter.matches(&slot.metadata))
+        };
+        let to_match = |candidate: Candidate| {
+            let slot = &self.slots[candidate.slot];
+            VectorMatch {
+                id: slot.id.to_owned(),
+                distance: candidate.distance,
+                metadata: slot.metadata.to_owned(),
+            }
+        };
+
+        if let Some(graph) = self.graph.as_ref() {
+            let matches: Vec<VectorMatch> = graph
+                .search(&self.slots, self.metric, &query, HNSW_EF_SEARCH.max(k))
+                .into_iter()
+                .filter(|candidate| is_match(&self.slots[candidate.slot]))
+                .take(k)
+                .map(to_match)
+                .collect();
+            // Filters, deleted slots or a poorly connected graph may leave too few candidates,
+            // the exhaustive search below then finds all of them.
+            if matches.len() >= k.min(self.len()) {
+                return Some(matches);
+            }
+        }
+
+        let mut candidates: Vec<Candidate> = self
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| is_match(slot))
+            .map(|(idx, slot)| Candidate {
+                distance: self.metric.distance(&query, &slot.values),
+                slot: idx,
+            })
+            .collect();
+        candidates.sort();
+        Some(candidates.into_iter().take(k).map(to_match).collect())
+    }
+}
@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::commands::dispatcher::CommandCategory;
+use crate::utils::{constants::INTERNAL_STORE_NAME, glob::glob_match};
+
+/// Name of the user new connections are logged in as, if it exists and needs no password.
+pub static DEFAULT_USER_NAME: &str = "default";
+
+#[derive(Debug, PartialEq)]
+pub enum AclError {
+    InvalidRule(String),
+}
+
+/// A named user and its permissions, as declared in the config file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserConfig {
+    pub name: String,
+
+    /// Disabled users can't authenticate.
+    pub enabled: bool,
+
+    /// Hex encoded SHA-256 digests of the passwords the user can authenticate with.
+    pub password_sha256: Vec<String>,
+
+    /// Whether the user can authenticate with any password, whatever its passwords.
+    /// A user without passwords and without `nopass` can't authenticate.
+    pub nopass: bool,
+
+    /// Categories of commands the user is allowed to run.
+    pub categories: Vec<CommandCategory>,
+
+    /// Glob patterns of the keys the user is allowed to access.
+    pub key_patterns: Vec<String>,
+
+    /// Glob patterns of the stores the user is allowed to access.
+    pub store_patterns: Vec<String>,
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        UserConfig {
+            name: DEFAULT_USER_NAME.to_owned(),
+            enabled: true,
+            password_sha256: vec![],
+            nopass: false,
+            categories: vec![],
+            key_patterns: vec![],
+            store_patterns: vec![],
+        }
+    }
+}
+
+/// Hex encoded SHA-256 digest of the password.
+pub fn hash_password(password: &str) -> String {
+    Sha256::digest(password.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Finds the enabled user a connection acts as: the one it authenticated as, or the
+/// `default` user if it didn't authenticate and the `default` user needs no password.
+pub fn find_session_user<'a>(
+    users: &'a [UserConfig],
+    name: Option<&str>,
+) -> Option<&'a UserConfig> {
+    let user = match name {
+        Some(name) => users.iter().find(|user| user.name == name),
+        None => users
+            .iter()
+            .find(|user| user.name == DEFAULT_USER_NAME)
+            .filter(|user| user.is_nopass()),
+    };
+    user.filter(|user| user.enabled)
+}
+
+impl UserConfig {
+    pub fn new(name: &str) -> Self {
+        UserConfig {
+            name: name.to_owned(),
+            // Users created at runtime are locked out until explicitly enabled.
+            enabled: false,
+            ..UserConfig::default()
+        }
+    }
+
+    /// Check whether the user needs no password.
+    pub fn is_nopass(&self) -> bool {
+        self.nopass
+    }
+
+    pub fn check_password(&self, password: &str) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if self.is_nopass() {
+            return true;
+        }
+        let digest = hash_password(password);
+        self.password_sha256.contains(&digest)
+    }
+
+    /// Connection commands are always allowed, others need their category to be granted.
+    pub fn can_run(&self, category: CommandCategory) -> bool {
+        category == CommandCategory::Connection || self.categories.contains(&category)
+    }
+
+    /// Check whether the user may access any key, e.g. ones a command derives from patterns.
+    pub fn can_access_all_keys(&self) -> bool {
+        self.key_patterns.iter().any(|pattern| pattern == "*")
+    }
+
+    pub fn can_access_key(&self, key: &str) -> bool {
+        self.key_patterns
+            .iter()
+            .any(|pattern| glob_match(pattern, key))
+    }
+
+    /// The internal store is reserved to admins, whatever the store patterns.
+    pub fn can_access_store(&self, store_name: &str) -> bool {
+        if store_name == INTERNAL_STORE_NAME && !self.can_run(CommandCategory::Admin) {
+            return false;
+        }
+        self.store_patterns
+            .iter()
+            .any(|pattern| glob_match(pattern, store_name))
+    }
+
+    /// Applies an ACL rule to the user:
+    /// * `on` / `off` - enables / disables the user
+    /// * `>password` / `<password` - adds (removing `nopass`) / removes a password
+    /// * `#digest` - adds the hex encoded SHA-256 digest of a password (removing `nopass`)
+    /// * `nopass` - removes all the passwords and allows any password
+    /// * `resetpass` - removes all the passwords and `nopass`, the user can't authenticate anymore
+    /// * `+@category` / `-@category` - grants / revokes a command category (`all` for every category)
+    /// * `~pattern` / `allkeys` / `resetkeys` - adds a key pattern / allows all keys / removes all key patterns
+    /// * `%pattern` / `allstores` / `resetstores` - same for store patterns
+    pub fn apply_rule(&mut self, rule: &str) -> Result<(), AclError> {
+        match rule.to_lowercase().as_str() {
+            "on" => self.enabled = true,
+            "off" => self.enabled = false,
+            "nopass" => {
+                self.password_sha256.clear();
+                self.nopass = true;
+            }
+            "resetpass" => {
+                self.password_sha256.clear();
+                self.nopass = false;
+            }
+            "allkeys" => self.key_patterns = vec!["*".to_owned()],
+            "resetkeys" => self.key_patterns.clear(),
+            "allstores" => self.store_patterns = vec!["*".to_owned()],
+            "resetstores" => self.store_patterns.clear(),
+            _ => {
+                if let Some(password) = rule.strip_prefix('>') {
+                    let digest = hash_password(password);
+                    if !self.password_sha256.contains(&digest) {
+                        self.password_sha256.push(digest);
+                    }
+                    self.nopass = false;
+                } else if let Some(password) = rule.strip_prefix('<') {
+                    let digest = hash_password(password);
+                    self.password_sha256.retain(|hash| *hash != digest);
+                } else if let Some(hash) = rule.strip_prefix('#') {
+                    let hash = hash.to_lowercase();
+                    if !self.password_sha256.contains(&hash) {
+                        self.password_sha256.push(hash);
+                    }
+                    self.nopass = false;
+                } else if let Some(category) = rule.strip_prefix("+@") {
+                    for category in _parse_categories(rule, category)? {
+                        if !self.categories.contains(&category) {
+                            self.categories.push(category);
+                        }
+                    }
+                } else if let Some(category) = rule.strip_prefix("-@") {
+                    let revoked = _parse_categories(rule, category)?;
+                    self.categories
+                        .retain(|category| !revoked.contains(category));
+                } else if let Some(pattern) = rule.strip_prefix('~') {
+                    self.key_patterns.push(pattern.to_owned());
+                } else if let Some(pattern) = rule.strip_prefix('%') {
+                    self.store_patterns.push(pattern.to_owned());
+                } else {
+                    return Err(AclError::InvalidRule(rule.to_owned()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Describes the user with the rules that would recreate it.
+    pub fn describe(&self) -> String {
+        let mut rules = vec![
+            format!("user {}", self.name),
+            (if self.enabled { "on" } else { "off" }).to_owned(),
+        ];
+        if self.is_nopass() {
+            rules.push("nopass".to_owned());
+        }
+        for hash in self.password_sha256.iter() {
+            rules.push(format!("#{}", hash));
+        }
+        for category in self.categories.iter() {
+            rules.push(format!("+@{}", category.name()));
+        }
+        for pattern in self.key_patterns.iter() {
+            rules.push(format!("~{}", pattern));
+        }
+        for pattern in self.store_patterns.iter() {
+            rules.push(format!("%{}", pattern));
+        }
+        rules.join(" ")
+    }
+}
+
+fn _parse_categories(rule: &str, name: &str) -> Result<Vec<CommandCategory>, AclError> {
+    match name.to_lowercase().as_str() {
+        "all" => Ok(vec![
+            CommandCategory::Read,
+            CommandCategory::Write,
+            CommandCategory::Admin,
+        ]),
+        "read" => Ok(vec![CommandCategory::Read]),
+        "write" => Ok(vec![CommandCategory::Write]),
+        "admin" => Ok(vec![CommandCategory::Admin]),
+        _ => Err(AclError::InvalidRule(rule.to_owned())),
+    }
+}
@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf};
+
+use crate::managers::manager::RusticManager;
+use crate::utils::{
+    acl::UserConfig,
+    constants::{DEFAULT_SCRIPT_TIME_LIMIT_MS, DEFAULT_STORE_NAME, IP_PORT_BINDING},
+    glob::glob_match,
+    tls::TlsConfig,
+    unix_socket::UnixSocketConfig,
+};
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+    NoConfigFile,
+    UnknownParameter(String),
+    ReadOnlyParameter(String),
+    InvalidValue(String),
+}
+
+/// Settings of a single store, as declared in the config file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StoreConfig {
+    pub name: String,
+
+    /// Default time to live (in milliseconds) for keys of this store.
+    pub default_ttl: Option<u64>,
+
+    /// Once the estimated memory usage (in bytes) of the store reaches this limit,
+    /// write commands are rejected.
+    pub max_memory: Option<u64>,
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        StoreConfig {
+            name: DEFAULT_STORE_NAME.to_owned(),
+            default_ttl: None,
+            max_memory: None,
+        }
+    }
+}
+
+/// Server configuration, loaded from a TOML file and changeable at runtime.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RusticConfig {
+    /// Address the TCP listener binds to. Changing it requires a restart.
+    pub bind: String,
+
+    /// Optional TLS listener, running side by side with the plaintext one.
+    pub tls: Option<TlsConfig>,
+
+    /// Optional Unix domain socket listener, running side by side with the TCP ones.
+    pub unix_socket: Option<UnixSocketConfig>,
+
+    /// Commands taking longer than this (in milliseconds) are logged as slow.
+    pub slowlog_threshold_ms: Option<u64>,
+
+    /// Scripts running longer than this (in milliseconds) are stopped.
+    pub script_time_limit_ms: Option<u64>,
+
+    /// Whether scripts may open stores other than the one they run against.
+    pub script_cross_store: bool,
+
+    pub stores: Vec<StoreConfig>,
+
+    /// Users allowed to connect, access control is disabled when there are none.
+    pub users: Vec<UserConfig>,
+
+    /// File this config was loaded from, used for reloading and rewriting it.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+impl Default for RusticConfig {
+    fn default() -> Self {
+        RusticConfig {
+            bind: IP_PORT_BINDING.to_owned(),
+            tls: None,
+            unix_socket: None,
+            slowlog_threshold_ms: None,
+            script_time_limit_ms: Some(DEFAULT_SCRIPT_TIME_LIMIT_MS),
+            script_cross_store: false,
+            stores: vec![StoreConfig::default()],
+            users: vec![],
+            path: None,
+        }
+    }
+}
+
+fn _format_optional(value: Option<u64>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "none".to_owned(),
+    }
+}
+
+fn _parse_optional(value: &str) -> Result<Option<u64>, ConfigError> {
+    if value.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    match value.parse::<u64>() {
+        Ok(value) => Ok(Some(value)),
+        Err(_) => Err(ConfigError::InvalidValue(value.to_owned())),
+    }
+}
+
+fn _format_bool(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_owned()
+}
+
+fn _parse_bool(value: &str) -> Result<bool, ConfigError> {
+    match value.to_lowercase().as_str() {
+        "yes" => Ok(true),
+        "no" => Ok(false),
+        _ => Err(ConfigError::InvalidValue(value.to_owned())),
+    }
+}
+
+impl RusticConfig {
+    /// Reads and parses the config file at the given path.
+    pub fn load(path: PathBuf) -> Result<Self, ConfigError> {
+        let contents = fs::read_to_string(&path).map_err(ConfigError::Io)?;
+        let mut config: RusticConfig = toml::from_str(&contents).map_err(ConfigError::Parse)?;
+        config.path = Some(path);
+        Ok(config)
+    }
+
+    /// Writes the current config back to the file it was loaded from.
+    pub fn rewrite(&self) -> Result<(), ConfigError> {
+        let path = self.path.as_ref().ok_or(ConfigError::NoConfigFile)?;
+        let contents = toml::to_string_pretty(self).map_err(ConfigError::Serialize)?;
+        fs::write(path, contents).map_err(ConfigError::Io)
+    }
+
+    /// Re-reads the config file, replacing this config and applying the store settings
+    /// to the stores of the manager. Stores missing from the file are left untouched.
+    pub fn reload(&mut self, manager: &mut RusticManager) -> Result<(), ConfigError> {
+        let path = self.path.to_owned().ok_or(ConfigError::NoConfigFile)?;
+        let new_config = RusticConfig::load(path)?;
+        for store_config in new_config.stores.iter() {
+            manager.apply_store_config(store_config);
+        }
+        *self = new_config;
+        Ok(())
+    }
+
+    pub fn get_store_config(&self, name: &str) -> Option<&StoreConfig> {
+        self.stores.iter().find(|store| store.name == name)
+    }
+
+    pub fn get_user(&self, name: &str) -> Option<&UserConfig> {
+        self.users.iter().find(|user| user.name == name)
+    }
+
+    /// Gets a mutable reference to the user, creating it (disabled and without permissions) if needed.
+    pub fn get_or_create_user(&mut self, name: &str) -> &mut UserConfig {
+        if let Some(idx) = self.users.iter().position(|user| user.name == name) {
+            &mut self.users[idx]
+        } else {
+            self.users.push(UserConfig::new(name));
+            self.users.last_mut().unwrap()
+        }
+    }
+
+    /// Removes the user and returns True or False based on if it existed.
+    pub fn remove_user(&mut self, name: &str) -> bool {
+        let users_count = self.users.len();
+        self.users.retain(|user| user.name != name);
+        self.users.len() != users_count
+    }
+
+    fn _get_or_create_store_config(&mut self, name: &str) -> &mut StoreConfig {
+        if let Some(idx) = self.stores.iter().position(|store| store.name == name) {
+            &mut self.stores[idx]
+        } else {
+            self.stores.push(StoreConfig {
+                name: name.to_owned(),
+                ..StoreConfig::default()
+            });
+            self.stores.last_mut().unwrap()
+        }
+    }
+
+    /// Lists all the (parameter, value) pairs.
+    /// Store settings are named `<store_name>.<setting>`.
+    pub fn list_parameters(&self) -> Vec<(String, String)> {
+        let mut parameters = vec![
+            ("bind".to_owned(), self.bind.to_owned()),
+            (
+                "tls_bind".to_owned(),
+                match &self.tls {
+                    Some(tls) => tls.bind.to_owned(),
+                    None => "none".to_owned(),
+                },
+            ),
+            (
+                "unix_socket".to_owned(),
+                match &self.unix_socket {
+                    Some(unix_socket) => unix_socket.path.display().to_string(),
+                    None => "none".to_owned(),
+                },
+            ),
+            (
+                "slowlog_threshold_ms".to_owned(),
+                _format_optional(self.slowlog_threshold_ms),
+            ),
+            (
+                "script_time_limit_ms".to_owned(),
+                _format_optional(self.script_time_limit_ms),
+            ),
+            (
+                "script_cross_store".to_owned(),
+                _format_bool(self.script_cross_store),
+            ),
+        ];
+        for store in self.stores.iter() {
+            parameters.push((
+                format!("{}.default_ttl", store.name),
+                _format_optional(store.default_ttl),
+            ));
+            parameters.push((
+                format!("{}.max_memory", store.name),
+                _format_optional(store.max_memory),
+            ));
+        }
+        parameters
+    }
+
+    /// Gets all the (parameter, value) pairs whose parameter name matches the glob pattern.
+    pub fn get_parameters(&self, pattern: &str) -> Vec<(String, String)> {
+        self.list_parameters()
+            .into_iter()
+            .filter(|(name, _)| glob_match(pattern, name))
+            .collect()
+    }
+
+    /// Sets a parameter from its string representation.
+    /// `none` unsets optional parameters.
+    pub fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), ConfigError> {
+        match name {
+            "bind" | "tls_bind" | "unix_socket" => {
+                Err(ConfigError::ReadOnlyParameter(name.to_owned()))
+            }
+            "slowlog_threshold_ms" => {
+                self.slowlog_threshold_ms = _parse_optional(value)?;
+                Ok(())
+            }
+            "script_time_limit_ms" => {
+                self.script_time_limit_ms = _parse_optional(value)?;
+                Ok(())
+            }
+            "script_cross_store" => {
+                self.script_cross_store = _parse_bool(value)?;
+                Ok(())
+            }
+            _ => match name.rsplit_once('.') {
+                Some((store_name, "default_ttl")) => {
+                    let value = _parse_optional(value)?;
+                    self._get_or_create_store_config(store_name).default_ttl = value;
+                    Ok(())
+                }
+                Some((store_name, "max_memory")) => {
+                    let value = _parse_optional(value)?;
+                    self._get_or_create_store_config(store_name).max_memory = value;
+                    Ok(())
+                }
+                _ => Err(ConfigError::UnknownParameter(name.to_owned())),
+            },
+        }
+    }
+}
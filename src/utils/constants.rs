@@ -1,2 +1,5 @@
 pub static INTERNAL_STORE_NAME: &str = "__INTERNAL_STORE__";
 pub static IP_PORT_BINDING: &str = "127.0.0.1:29997";
+pub static DEFAULT_STORE_NAME: &str = "default_store";
+pub static CONFIG_PATH_ENV_VAR: &str = "RUSTIC_CONFIG";
+pub static DEFAULT_SCRIPT_TIME_LIMIT_MS: u64 = 5000;
@@ -1,14 +1,16 @@
 use log::{error, info};
-use std::{sync::Arc, time::Duration};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use tokio::{
-    net::TcpListener,
+    net::{TcpListener, UnixListener},
+    signal::unix::{signal, SignalKind},
     sync::RwLock,
     time::{timeout, Instant},
 };
+use tokio_rustls::TlsAcceptor;
 
 use crate::managers::manager::RusticManager;
-use crate::utils::request_handler::handle_client_request;
+use crate::utils::{config::RusticConfig, request_handler::handle_client_request};
 
 async fn execute_periodic_tasks(
     shared_manager: Arc<RwLock<RusticManager>>,
@@ -24,20 +26,110 @@ async fn execute_periodic_tasks(
     }
 }
 
+/// Reloads the config file every time the process receives a SIGHUP.
+pub async fn reload_config_on_sighup(
+    shared_manager: Arc<RwLock<RusticManager>>,
+    shared_config: Arc<RwLock<RusticConfig>>,
+) {
+    let mut hangups = signal(SignalKind::hangup()).unwrap();
+    while hangups.recv().await.is_some() {
+        info!("SIGHUP received, reloading the config file.");
+        // NOTE: Always lock the config before the manager to avoid deadlocks.
+        let mut config = shared_config.write().await;
+        let mut manager = shared_manager.write().await;
+        match config.reload(&mut manager) {
+            Ok(()) => info!("Config reloaded."),
+            Err(err) => error!("Error reloading config: {:?}", err),
+        }
+    }
+}
+
+/// Waits until the process is asked to stop, with a SIGINT or a SIGTERM.
+pub async fn shutdown_signal() {
+    let mut interrupts = signal(SignalKind::interrupt()).unwrap();
+    let mut terminations = signal(SignalKind::terminate()).unwrap();
+    tokio::select! {
+        _ = interrupts.recv() => info!("SIGINT received, shutting down."),
+        _ = terminations.recv() => info!("SIGTERM received, shutting down."),
+    }
+}
+
+/// Accepts TLS connections and serves them like the plaintext ones.
+/// Periodic tasks are left to the main event loop.
+pub async fn tls_event_loop(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    shared_manager: Arc<RwLock<RusticManager>>,
+    shared_config: Arc<RwLock<RusticConfig>>,
+) {
+    loop {
+        match listener.accept().await {
+            Ok((socket, peer_addr)) => {
+                let acceptor = acceptor.clone();
+                let shared_manager = Arc::clone(&shared_manager);
+                let shared_config = Arc::clone(&shared_config);
+                // Handshake in the spawned task so a slow client doesn't block the listener.
+                tokio::spawn(async move {
+                    match acceptor.accept(socket).await {
+                        Ok(tls_stream) => {
+                            handle_client_request(
+                                tls_stream,
+                                peer_addr.to_string(),
+                                shared_manager,
+                                shared_config,
+                            )
+                            .await
+                        }
+                        Err(err) => error!("TLS handshake with {:?} failed: {:?}", peer_addr, err),
+                    }
+                });
+            }
+            Err(err) => error!("Error accepting TLS connection: {:?}", err),
+        }
+    }
+}
+
+/// Accepts connections on the Unix domain socket and serves them like the TCP ones.
+/// Periodic tasks are left to the main event loop.
+pub async fn unix_event_loop(
+    listener: UnixListener,
+    socket_path: PathBuf,
+    shared_manager: Arc<RwLock<RusticManager>>,
+    shared_config: Arc<RwLock<RusticConfig>>,
+) {
+    loop {
+        match listener.accept().await {
+            Ok((socket, _)) => {
+                // Unix socket peers are unnamed, identify them by the socket path instead.
+                let peer = format!("unix:{}", socket_path.display());
+                tokio::spawn(handle_client_request(
+                    socket,
+                    peer,
+                    Arc::clone(&shared_manager),
+                    Arc::clone(&shared_config),
+                ));
+            }
+            Err(err) => error!("Error accepting Unix socket connection: {:?}", err),
+        }
+    }
+}
+
 pub async fn event_loop(
     listener: TcpListener,
     shared_manager: Arc<RwLock<RusticManager>>,
+    shared_config: Arc<RwLock<RusticConfig>>,
     start_instant: Instant,
 ) {
     loop {
         // Accept incoming connections for 5 seconds.
         match timeout(Duration::from_secs(5), listener.accept()).await {
-            Ok(Ok((socket, _))) => {
+            Ok(Ok((socket, peer_addr))) => {
                 // Spawn a new task to handle the client
                 tokio::spawn(handle_client_request(
                     socket,
+                    peer_addr.to_string(),
                     Arc::clone(&shared_manager),
-                    start_instant,
+                    Arc::clone(&shared_config),
                 ));
             }
             Ok(Err(err)) => error!("Error accepting connection: {:?}", err),
@@ -0,0 +1,96 @@
+/// Matches `text` against a glob-style `pattern`.
+/// Supported syntax:
+/// * `*` - matches any sequence of characters (including none)
+/// * `?` - matches exactly one character
+/// * `[abc]`, `[a-z]`, `[^abc]` - matches one character from (or not from) the class
+/// * `\x` - matches the character `x` literally
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    _glob_match(&pattern, &text)
+}
+
+fn _glob_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p_idx, mut t_idx) = (0, 0);
+    // Position to backtrack to after the last `*` seen: (pattern index, text index)
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t_idx < text.len() {
+        let consumed = match pattern.get(p_idx) {
+            Some('*') => {
+                backtrack = Some((p_idx, t_idx));
+                p_idx += 1;
+                continue;
+            }
+            Some('?') => Some(1),
+            Some('[') => _match_class(&pattern[p_idx..], text[t_idx]),
+            Some('\\') if p_idx + 1 < pattern.len() => {
+                if pattern[p_idx + 1] == text[t_idx] {
+                    Some(2)
+                } else {
+                    None
+                }
+            }
+            Some(ch) if *ch == text[t_idx] => Some(1),
+            _ => None,
+        };
+
+        match consumed {
+            Some(pattern_len) => {
+                p_idx += pattern_len;
+                t_idx += 1;
+            }
+            None => match backtrack {
+                Some((star_p_idx, star_t_idx)) => {
+                    // Let the last `*` swallow one more character and retry.
+                    p_idx = star_p_idx + 1;
+                    t_idx = star_t_idx + 1;
+                    backtrack = Some((star_p_idx, star_t_idx + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p_idx..].iter().all(|ch| *ch == '*')
+}
+
+/// Tries to match `ch` against the character class at the start of `pattern`.
+/// Returns the length of the class in the pattern if it matches.
+fn _match_class(pattern: &[char], ch: char) -> Option<usize> {
+    let mut idx = 1;
+    let negated = pattern.get(idx) == Some(&'^');
+    if negated {
+        idx += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    while idx < pattern.len() && (first || pattern[idx] != ']') {
+        first = false;
+        let mut low = pattern[idx];
+        if low == '\\' && idx + 1 < pattern.len() {
+            idx += 1;
+            low = pattern[idx];
+        }
+        if idx + 2 < pattern.len() && pattern[idx + 1] == '-' && pattern[idx + 2] != ']' {
+            let high = pattern[idx + 2];
+            matched |= low <= ch && ch <= high;
+            idx += 3;
+        } else {
+            matched |= low == ch;
+            idx += 1;
+        }
+    }
+
+    if idx >= pattern.len() {
+        // Unterminated class, treat `[` as a literal.
+        return if ch == '[' { Some(1) } else { None };
+    }
+
+    if matched != negated {
+        Some(idx + 1)
+    } else {
+        None
+    }
+}
@@ -1,3 +1,10 @@
+pub mod acl;
+pub mod config;
 pub mod constants;
 pub mod event_loop;
+pub mod glob;
 mod request_handler;
+#[cfg(test)]
+mod tests;
+pub mod tls;
+pub mod unix_socket;
@@ -1,63 +1,82 @@
+use crate::commands::{
+    dispatcher::{execute_command, CommandContext, Session},
+    parser::parse_command_line,
+    response::Response,
+    scripts::kill_running_script,
+};
 use crate::managers::manager::RusticManager;
-use log::info;
+use crate::utils::config::RusticConfig;
+use log::{error, info, warn};
 use std::sync::Arc;
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
-    net::TcpStream,
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
     sync::RwLock,
     time::Instant,
 };
 
-pub(super) async fn handle_client_request(
-    mut tcp_stream: TcpStream,
+/// Serves a client connection: every line received is parsed as a command,
+/// executed against the shared manager and answered with a RESP2 encoded reply.
+pub(super) async fn handle_client_request<S>(
+    stream: S,
+    peer: String,
     shared_manager: Arc<RwLock<RusticManager>>,
-    start_instant: Instant,
-) {
-    // Your client handling logic goes here
-    // TODO: Add all logic to handle client requests here.
-    // This is only temporary to test the Sockets and demonstrate the logic of handling requests.
-
-    // This is how we can share the manager and store for the case where we just want to read.
-    // NOTE: write_manager should only invoked if we want to write to the store.
-    let mut write_manager = shared_manager.write().await;
-    let store = write_manager.get_store_mut("default_store").unwrap();
-
-    // // This is how we can share the manager and store for the case where we just want to read.
-    // // We can only use one of these managers in a single thread or it will lead to a deadlock.
-    // let manager = shared_manager.read().await;
-    // let store = manager.get_store("default_store".to_owned()).unwrap();
+    shared_config: Arc<RwLock<RusticConfig>>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    info!("Handling client from: {:?}", peer);
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    let mut session = Session::new();
 
-    info!(
-        "Handling client from: {:?}",
-        tcp_stream.peer_addr().unwrap()
-    );
-    let buf: &mut Vec<u8> = &mut vec![];
-    info!(
-        "Message received: {:?}",
-        tcp_stream.read_buf(buf).await.unwrap()
-    );
-    let string_buff: String = String::from_utf8_lossy(buf).to_string();
-    info!("Message body: {:?}", string_buff);
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(err) => {
+                error!("Error reading from {:?}: {:?}", peer, err);
+                break;
+            }
+        };
 
-    let value = store.get_string("ABC");
-    let return_buff: &[u8];
+        let response = match parse_command_line(&line) {
+            Ok(command) if command.is_empty() => continue,
+            // A running script holds the manager, so killing it can't wait for the locks.
+            Ok(command) => match kill_running_script(&session, &command) {
+                Some(response) => response,
+                None => {
+                    // NOTE: Always lock the config before the manager to avoid deadlocks.
+                    let mut config = shared_config.write().await;
+                    let mut manager = shared_manager.write().await;
 
-    if value.is_some() {
-        let cache_value = value.unwrap().unwrap();
-        info!("Cache value is present {:?}", cache_value.to_string());
-        return_buff = cache_value.as_bytes();
-        let res = tcp_stream.write(return_buff).await;
-        info!("Response sent: {:?}", res.unwrap());
-    } else {
-        info!("Cache value is not present");
-        store.set_string(
-            "ABC",
-            start_instant.elapsed().as_secs().to_string() + " " + string_buff.as_str(),
-            Some(5000),
-        );
+                    let started = Instant::now();
+                    let response = execute_command(
+                        &mut CommandContext {
+                            session: &mut session,
+                            manager: &mut manager,
+                            config: &mut config,
+                        },
+                        &command,
+                    );
+                    let elapsed = started.elapsed();
+                    if let Some(threshold) = config.slowlog_threshold_ms {
+                        if elapsed.as_millis() >= threshold as u128 {
+                            warn!(
+                                "Slow command from {:?} ({:?}): {:?}",
+                                peer, elapsed, command
+                            );
+                        }
+                    }
+                    response
+                }
+            },
+            Err(_) => Response::error("unbalanced quotes in request"),
+        };
 
-        return_buff = "Cache is set".as_bytes();
-        let res = tcp_stream.write(return_buff).await;
-        info!("Response sent: {:?}", res.unwrap());
+        if let Err(err) = writer.write_all(&response.encode()).await {
+            error!("Error writing to {:?}: {:?}", peer, err);
+            break;
+        }
     }
+    info!("Client {:?} disconnected", peer);
 }
@@ -0,0 +1,284 @@
+use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa, KeyPair};
+use rustls::{
+    crypto::ring::default_provider,
+    pki_types::{PrivateKeyDer, ServerName},
+    ClientConfig, RootCertStore,
+};
+use std::{env, fs, net::SocketAddr, os::unix::fs::PermissionsExt, path::PathBuf, sync::Arc};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream, UnixStream},
+    sync::RwLock,
+};
+use tokio_rustls::TlsConnector;
+
+use crate::commands::dispatcher::CommandCategory;
+use crate::managers::manager::RusticManager;
+
+use super::{
+    acl::{AclError, UserConfig},
+    config::RusticConfig,
+    constants::{DEFAULT_STORE_NAME, INTERNAL_STORE_NAME},
+    event_loop::{tls_event_loop, unix_event_loop},
+    glob::glob_match,
+    tls::{build_tls_acceptor, TlsConfig, TlsError},
+    unix_socket::{bind_unix_socket, remove_unix_socket, UnixSocketConfig},
+};
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("*", ""));
+    assert!(glob_match("*", "anything"));
+    assert!(glob_match("user:*", "user:42"));
+    assert!(!glob_match("user:*", "order:42"));
+    assert!(glob_match("h?llo", "hello"));
+    assert!(!glob_match("h?llo", "hllo"));
+    assert!(glob_match("h*llo*", "heeeello world"));
+    assert!(glob_match("h[ae]llo", "hallo"));
+    assert!(!glob_match("h[ae]llo", "hillo"));
+    assert!(glob_match("h[^e]llo", "hallo"));
+    assert!(!glob_match("h[^e]llo", "hello"));
+    assert!(glob_match("key[0-9]", "key7"));
+    assert!(!glob_match("key[0-9]", "keyx"));
+    assert!(glob_match("star\\*", "star*"));
+    assert!(!glob_match("star\\*", "starx"));
+    assert!(glob_match("a*b*c", "a__b__c"));
+    assert!(!glob_match("a*b*c", "a__c__b"));
+}
+
+#[test]
+fn test_user_permissions() {
+    let mut user = UserConfig::new("service");
+    assert!(!user.check_password("anything"));
+
+    for rule in [
+        "on", ">pass1", ">pass2", "+@read", "+@write", "~cache:*", "%*",
+    ] {
+        user.apply_rule(rule).unwrap();
+    }
+    assert!(user.check_password("pass1"));
+    assert!(user.check_password("pass2"));
+    assert!(!user.check_password("pass3"));
+    user.apply_rule("<pass1").unwrap();
+    assert!(!user.check_password("pass1"));
+
+    // Removing the last password, or all of them, leaves the user unable to authenticate.
+    user.apply_rule("<pass2").unwrap();
+    assert!(!user.is_nopass());
+    assert!(!user.check_password("pass2"));
+    assert!(!user.check_password(""));
+    user.apply_rule("nopass").unwrap();
+    assert!(user.is_nopass());
+    assert!(user.check_password("anything"));
+    user.apply_rule(">pass1").unwrap();
+    assert!(!user.check_password("anything"));
+    assert!(user.check_password("pass1"));
+    user.apply_rule("nopass").unwrap();
+    user.apply_rule("resetpass").unwrap();
+    assert!(!user.is_nopass());
+    assert!(!user.check_password("anything"));
+    assert!(user.password_sha256.is_empty());
+
+    assert!(user.can_run(CommandCategory::Read));
+    assert!(user.can_run(CommandCategory::Connection));
+    assert!(!user.can_run(CommandCategory::Admin));
+    user.apply_rule("-@write").unwrap();
+    assert!(!user.can_run(CommandCategory::Write));
+
+    assert!(user.can_access_key("cache:42"));
+    assert!(!user.can_access_key("session:42"));
+
+    // Only admins can touch the internal store.
+    assert!(user.can_access_store("default_store"));
+    assert!(!user.can_access_store(INTERNAL_STORE_NAME));
+    user.apply_rule("+@admin").unwrap();
+    assert!(user.can_access_store(INTERNAL_STORE_NAME));
+
+    assert_eq!(
+        user.apply_rule("+@unknown"),
+        Err(AclError::InvalidRule("+@unknown".to_owned()))
+    );
+}
+
+/// Writes a PEM file in the temp dir and returns its path.
+fn write_temp_pem(name: &str, contents: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("rustic_{}_{}.pem", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+/// Sends PING over a TLS connection and returns the reply, or None if the connection failed.
+async fn tls_ping(
+    addr: SocketAddr,
+    ca: &Certificate,
+    client_identity: Option<(&Certificate, &KeyPair)>,
+) -> Option<String> {
+    let mut roots = RootCertStore::empty();
+    roots.add(ca.der().to_owned()).unwrap();
+    let builder = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots);
+    let client_config = match client_identity {
+        Some((cert, key_pair)) => builder
+            .with_client_auth_cert(
+                vec![cert.der().to_owned()],
+                PrivateKeyDer::try_from(key_pair.serialize_der()).unwrap(),
+            )
+            .unwrap(),
+        None => builder.with_no_client_auth(),
+    };
+
+    let tcp_stream = TcpStream::connect(addr).await.unwrap();
+    let mut tls_stream = TlsConnector::from(Arc::new(client_config))
+        .connect(ServerName::try_from("localhost").unwrap(), tcp_stream)
+        .await
+        .ok()?;
+    tls_stream.write_all(b"PING\r\n").await.ok()?;
+    let mut buf = [0; 64];
+    let read = tls_stream.read(&mut buf).await.ok()?;
+    Some(String::from_utf8_lossy(&buf[..read]).to_string())
+}
+
+#[tokio::test]
+async fn test_tls_listener() {
+    let mut ca_params = CertificateParams::new(vec![]).unwrap();
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca_key = KeyPair::generate().unwrap();
+    let ca = ca_params.self_signed(&ca_key).unwrap();
+
+    let server_key = KeyPair::generate().unwrap();
+    let server_cert = CertificateParams::new(vec!["localhost".to_owned()])
+        .unwrap()
+        .signed_by(&server_key, &ca, &ca_key)
+        .unwrap();
+    let client_key = KeyPair::generate().unwrap();
+    let client_cert = CertificateParams::new(vec!["client".to_owned()])
+        .unwrap()
+        .signed_by(&client_key, &ca, &ca_key)
+        .unwrap();
+
+    let mut tls_config = TlsConfig {
+        bind: "127.0.0.1:0".to_owned(),
+        cert_file: write_temp_pem("server_cert", &server_cert.pem()),
+        key_file: write_temp_pem("server_key", &server_key.serialize_pem()),
+        client_ca_file: None,
+        require_client_cert: false,
+    };
+
+    let shared_manager = Arc::new(RwLock::new(RusticManager::new()));
+    shared_manager
+        .write()
+        .await
+        .create_store(DEFAULT_STORE_NAME, None)
+        .unwrap();
+    let shared_config = Arc::new(RwLock::new(RusticConfig::default()));
+
+    // Server authentication only.
+    let listener = TcpListener::bind(&tls_config.bind).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(tls_event_loop(
+        listener,
+        build_tls_acceptor(&tls_config).unwrap(),
+        Arc::clone(&shared_manager),
+        Arc::clone(&shared_config),
+    ));
+    assert_eq!(tls_ping(addr, &ca, None).await.unwrap(), "+PONG\r\n");
+
+    // Mutual authentication.
+    tls_config.client_ca_file = Some(write_temp_pem("ca_cert", &ca.pem()));
+    tls_config.require_client_cert = true;
+    let listener = TcpListener::bind(&tls_config.bind).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(tls_event_loop(
+        listener,
+        build_tls_acceptor(&tls_config).unwrap(),
+        Arc::clone(&shared_manager),
+        Arc::clone(&shared_config),
+    ));
+    assert_eq!(
+        tls_ping(addr, &ca, Some((&client_cert, &client_key)))
+            .await
+            .unwrap(),
+        "+PONG\r\n"
+    );
+    assert!(tls_ping(addr, &ca, None).await.is_none());
+
+    // Requiring client certificates without authorities to verify them is rejected.
+    let mut invalid_config = tls_config.clone();
+    invalid_config.client_ca_file = None;
+    assert!(matches!(
+        build_tls_acceptor(&invalid_config),
+        Err(TlsError::MissingClientCa)
+    ));
+    invalid_config.require_client_cert = false;
+    invalid_config.key_file = env::temp_dir().join("rustic_missing_key.pem");
+    let error = build_tls_acceptor(&invalid_config).err().unwrap();
+    assert!(error.to_string().contains("rustic_missing_key.pem"));
+
+    for path in [
+        tls_config.cert_file,
+        tls_config.key_file,
+        tls_config.client_ca_file.unwrap(),
+    ] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_unix_socket_listener() {
+    let unix_socket_config = UnixSocketConfig {
+        path: env::temp_dir().join(format!("rustic_{}.sock", std::process::id())),
+        permissions: Some(0o600),
+    };
+    // A stale socket file is replaced, a live one or anything else is left alone.
+    drop(bind_unix_socket(&unix_socket_config).unwrap());
+    let listener = bind_unix_socket(&unix_socket_config).unwrap();
+    assert_eq!(
+        bind_unix_socket(&unix_socket_config).unwrap_err().kind(),
+        std::io::ErrorKind::AddrInUse
+    );
+    assert_eq!(
+        fs::metadata(&unix_socket_config.path)
+            .unwrap()
+            .permissions()
+            .mode()
+            & 0o777,
+        0o600
+    );
+
+    let shared_manager = Arc::new(RwLock::new(RusticManager::new()));
+    shared_manager
+        .write()
+        .await
+        .create_store(DEFAULT_STORE_NAME, None)
+        .unwrap();
+    let shared_config = Arc::new(RwLock::new(RusticConfig::default()));
+    tokio::spawn(unix_event_loop(
+        listener,
+        unix_socket_config.path.to_owned(),
+        shared_manager,
+        shared_config,
+    ));
+
+    let mut stream = UnixStream::connect(&unix_socket_config.path).await.unwrap();
+    stream
+        .write_all(b"SET ABC hello\r\nGET ABC\r\n")
+        .await
+        .unwrap();
+    let expected = b"+OK\r\n$5\r\nhello\r\n";
+    let mut buf = vec![0; expected.len()];
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, expected);
+
+    remove_unix_socket(&unix_socket_config).unwrap();
+    assert!(!unix_socket_config.path.exists());
+    let not_a_socket = env::temp_dir().join(format!("rustic_{}.notsock", std::process::id()));
+    fs::write(&not_a_socket, "data").unwrap();
+    assert!(bind_unix_socket(&UnixSocketConfig {
+        path: not_a_socket.to_owned(),
+        permissions: None,
+    })
+    .is_err());
+    fs::remove_file(&not_a_socket).unwrap();
+}
@@ -0,0 +1,107 @@
+use rustls::{
+    crypto::ring::default_provider,
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio_rustls::TlsAcceptor;
+
+#[derive(Debug)]
+pub enum TlsError {
+    Pem(PathBuf, rustls::pki_types::pem::Error),
+    /// `require_client_cert` is set, but no `client_ca_file` to verify client certificates.
+    MissingClientCa,
+    Rustls(rustls::Error),
+    ClientVerifier(rustls::server::VerifierBuilderError),
+}
+
+impl fmt::Display for TlsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TlsError::Pem(path, e) => write!(f, "could not read PEM file {:?}: {}", path, e),
+            TlsError::MissingClientCa => {
+                write!(f, "'require_client_cert' needs a 'client_ca_file'")
+            }
+            TlsError::Rustls(e) => write!(f, "{}", e),
+            TlsError::ClientVerifier(e) => {
+                write!(f, "invalid client certificate authorities: {}", e)
+            }
+        }
+    }
+}
+
+/// Settings of the TLS listener, which runs next to the plaintext one.
+/// Changing them requires a restart.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// Address the TLS listener binds to.
+    pub bind: String,
+
+    /// PEM file holding the certificate chain presented to clients.
+    pub cert_file: PathBuf,
+
+    /// PEM file holding the private key of the certificate.
+    pub key_file: PathBuf,
+
+    /// PEM file holding the certificate authorities client certificates are verified against.
+    /// Client certificates are only checked when this is set.
+    #[serde(default)]
+    pub client_ca_file: Option<PathBuf>,
+
+    /// Reject clients that don't present a certificate signed by `client_ca_file`.
+    #[serde(default)]
+    pub require_client_cert: bool,
+}
+
+/// Reads every certificate of a PEM file.
+fn _read_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let pem_error = |e| TlsError::Pem(path.to_owned(), e);
+    CertificateDer::pem_file_iter(path)
+        .map_err(pem_error)?
+        .collect::<Result<_, _>>()
+        .map_err(pem_error)
+}
+
+/// Builds the acceptor terminating TLS for client connections.
+pub fn build_tls_acceptor(tls_config: &TlsConfig) -> Result<TlsAcceptor, TlsError> {
+    if tls_config.require_client_cert && tls_config.client_ca_file.is_none() {
+        return Err(TlsError::MissingClientCa);
+    }
+    let cert_chain = _read_certificates(&tls_config.cert_file)?;
+    let private_key = PrivateKeyDer::from_pem_file(&tls_config.key_file)
+        .map_err(|e| TlsError::Pem(tls_config.key_file.to_owned(), e))?;
+
+    let provider = Arc::new(default_provider());
+    let builder = ServerConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()
+        .map_err(TlsError::Rustls)?;
+
+    let builder = match &tls_config.client_ca_file {
+        Some(client_ca_file) => {
+            let mut roots = RootCertStore::empty();
+            for cert in _read_certificates(client_ca_file)? {
+                roots.add(cert).map_err(TlsError::Rustls)?;
+            }
+            let verifier_builder =
+                WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+            let verifier = if tls_config.require_client_cert {
+                verifier_builder.build()
+            } else {
+                verifier_builder.allow_unauthenticated().build()
+            };
+            builder.with_client_cert_verifier(verifier.map_err(TlsError::ClientVerifier)?)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let server_config = builder
+        .with_single_cert(cert_chain, private_key)
+        .map_err(TlsError::Rustls)?;
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    os::unix::{
+        fs::{DirBuilderExt, FileTypeExt, PermissionsExt},
+        net::UnixStream,
+    },
+    path::{Path, PathBuf},
+    process,
+};
+use tokio::net::UnixListener;
+
+/// Settings of the Unix domain socket listener, which runs next to the TCP one.
+/// Changing them requires a restart.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UnixSocketConfig {
+    /// Path of the socket file.
+    pub path: PathBuf,
+
+    /// Permissions of the socket file (e.g. `0o770`), clients need write access to connect.
+    #[serde(default)]
+    pub permissions: Option<u32>,
+}
+
+/// Binds the Unix domain socket, replacing a stale socket file left by a previous run.
+/// Fails if another server is still listening on it.
+///
+/// The socket is bound in a private directory and moved into place once its permissions
+/// are set, so that no client can connect before they apply.
+pub fn bind_unix_socket(unix_socket_config: &UnixSocketConfig) -> io::Result<UnixListener> {
+    let path = &unix_socket_config.path;
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{:?} exists and is not a socket", path),
+            ));
+        }
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("{:?} is used by a running server", path),
+            ));
+        }
+    }
+
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{:?} is not a valid socket path", path),
+        )
+    })?;
+    let private_dir = path.with_file_name(format!(
+        ".{}.{}",
+        file_name.to_string_lossy(),
+        process::id()
+    ));
+    if fs::symlink_metadata(&private_dir).is_ok() {
+        fs::remove_dir_all(&private_dir)?;
+    }
+    fs::DirBuilder::new().mode(0o700).create(&private_dir)?;
+
+    let result = _bind_and_move(&private_dir.join(file_name), unix_socket_config);
+    fs::remove_dir_all(&private_dir)?;
+    result
+}
+
+/// Binds the socket at a temporary path, sets its permissions and renames it to its final
+/// path (replacing a stale socket file).
+fn _bind_and_move(
+    temporary_path: &Path,
+    unix_socket_config: &UnixSocketConfig,
+) -> io::Result<UnixListener> {
+    let listener = UnixListener::bind(temporary_path)?;
+    if let Some(permissions) = unix_socket_config.permissions {
+        fs::set_permissions(temporary_path, fs::Permissions::from_mode(permissions))?;
+    }
+    fs::rename(temporary_path, &unix_socket_config.path)?;
+    Ok(listener)
+}
+
+/// Removes the socket file when the server shuts down.
+pub fn remove_unix_socket(unix_socket_config: &UnixSocketConfig) -> io::Result<()> {
+    fs::remove_file(&unix_socket_config.path)
+}